@@ -5,8 +5,8 @@ use ash::vk;
 use glam::{Mat4, Vec3};
 use log::{info, trace};
 
+use phobos::{accel, image};
 use phobos::graph::pass::ClearColor;
-use phobos::image;
 use phobos::pipeline::raytracing::RayTracingPipelineBuilder;
 use phobos::pool::LocalPool;
 use phobos::prelude::*;
@@ -332,10 +332,27 @@ impl ExampleApp for RaytracingSample {
     fn frame(&mut self, ctx: Context, ifc: InFlightContext) -> Result<SubmitBatch<All>> {
         let swap = image!("swapchain");
         let rt_image = image!("rt_out");
+        let tlas = accel!("tlas");
 
         let mut pool = LocalPool::new(ctx.pool.clone())?;
 
+        // Rebuild the TLAS every frame, so instances could be moved around. The graph makes sure the build
+        // is finished before the ray tracing pass reads from it.
+        let tlas_pass = PassBuilder::new("tlas_build")
+            .build_acceleration_structure(&tlas)
+            .execute_fn(|cmd, _, _, _| {
+                let build_info = tlas_build_info(&self.instances)
+                    .dst(&self.tlas.accel)
+                    .scratch_data(self.tlas.scratch.address());
+                cmd.build_acceleration_structure(&build_info)
+            })
+            .build();
+
         let rt_pass = PassBuilder::new("raytrace")
+            .read_acceleration_structure(
+                tlas_pass.output(&tlas).unwrap(),
+                PipelineStage::RAY_TRACING_SHADER_KHR,
+            )
             .write_storage_image(&rt_image, PipelineStage::RAY_TRACING_SHADER_KHR)
             .execute_fn(|cmd, ifc, bindings, _| {
                 let view = Mat4::look_at_rh(
@@ -348,7 +365,7 @@ impl ExampleApp for RaytracingSample {
                 cmd.bind_ray_tracing_pipeline("rt")?
                     .push_constant(vk::ShaderStageFlags::RAYGEN_KHR, 0, &view)
                     .push_constant(vk::ShaderStageFlags::RAYGEN_KHR, 64, &projection)
-                    .bind_acceleration_structure(0, 0, &self.tlas.accel)?
+                    .resolve_and_bind_acceleration_structure(0, 0, &tlas, bindings)?
                     .resolve_and_bind_storage_image(0, 1, &rt_image, bindings)?
                     .trace_rays(800, 600, 1)
             })
@@ -377,6 +394,7 @@ impl ExampleApp for RaytracingSample {
 
         let present = PassBuilder::present("present", render_pass.output(&swap).unwrap());
         let mut graph = PassGraph::new()
+            .add_pass(tlas_pass)?
             .add_pass(rt_pass)?
             .add_pass(render_pass)?
             .add_pass(present)?
@@ -385,6 +403,7 @@ impl ExampleApp for RaytracingSample {
         let mut bindings = PhysicalResourceBindings::new();
         bindings.bind_image("swapchain", &ifc.swapchain_image);
         bindings.bind_image("rt_out", &self.attachment_view);
+        bindings.bind_acceleration_structure("tlas", &self.tlas.accel);
         let cmd = ctx.exec.on_domain::<All>()?;
         let cmd = graph.record(cmd, &bindings, &mut pool, None, &mut ())?;
         let cmd = cmd.finish()?;
@@ -12,6 +12,7 @@ pub struct CommandPool {
     #[derivative(Debug = "ignore")]
     device: Device,
     handle: vk::CommandPool,
+    family: u32,
 }
 
 impl CommandPool {
@@ -30,9 +31,33 @@ impl CommandPool {
         Ok(CommandPool {
             device,
             handle,
+            family,
         })
     }
 
+    /// Allocate a single command buffer from this pool.
+    /// # Errors
+    /// * Fails if the Vulkan allocation fails.
+    pub fn allocate(&self, level: vk::CommandBufferLevel) -> Result<vk::CommandBuffer> {
+        let info = vk::CommandBufferAllocateInfo {
+            s_type: vk::StructureType::COMMAND_BUFFER_ALLOCATE_INFO,
+            p_next: std::ptr::null(),
+            command_pool: self.handle,
+            level,
+            command_buffer_count: 1,
+        };
+        let handle = unsafe { self.device.allocate_command_buffers(&info)? }
+            .into_iter()
+            .next()
+            .ok_or(Error::Uncategorized("Command buffer allocation failed."))?;
+        Ok(handle)
+    }
+
+    /// Get the queue family this command pool allocates command buffers for.
+    pub fn family(&self) -> u32 {
+        self.family
+    }
+
     /// Get unsafe access to the underlying `VkCommandPool` handle.
     /// # Safety
     /// - Access to the command pool **and** command buffers allocated from it must be externally synchronized.
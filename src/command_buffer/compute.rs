@@ -3,7 +3,7 @@
 use anyhow::Result;
 use ash::vk;
 
-use crate::{Allocator, ComputeCmdBuffer, ComputeSupport};
+use crate::{Allocator, BufferView, ComputeCmdBuffer, ComputeSupport};
 use crate::command_buffer::IncompleteCommandBuffer;
 use crate::core::device::ExtensionID;
 use crate::query_pool::{AccelerationStructurePropertyQuery, QueryPool};
@@ -73,6 +73,34 @@ impl<D: ComputeSupport + ExecutionDomain, A: Allocator> ComputeCmdBuffer
         Ok(self)
     }
 
+    /// Dispatch compute invocations with the amount of workgroups in each dimension read from a [`vk::DispatchIndirectCommand`]
+    /// at the start of `buffer`. This is useful when the amount of work is determined by a previous pass on the GPU.
+    ///
+    /// This function also flushes the current descriptor set state, in the same way as [`Self::dispatch()`].
+    ///
+    /// See also: [`vkCmdDispatchIndirect`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdDispatchIndirect.html)
+    ///
+    /// # Errors
+    /// * Fails if updating the descriptor state fails.
+    /// # Example
+    /// ```
+    /// # use phobos::*;
+    /// # use phobos::sync::domain::ExecutionDomain;
+    /// # use anyhow::Result;
+    /// fn compute_indirect<D: ExecutionDomain + ComputeSupport>(cmd: IncompleteCommandBuffer<D>, args: &BufferView) -> Result<IncompleteCommandBuffer<D>> {
+    ///     cmd.bind_compute_pipeline("my_pipeline")?
+    ///        .dispatch_indirect(args)
+    /// }
+    /// ```
+    fn dispatch_indirect(mut self, buffer: &BufferView) -> Result<Self> {
+        self = self.ensure_descriptor_state()?;
+        unsafe {
+            self.device
+                .cmd_dispatch_indirect(self.handle, buffer.handle(), buffer.offset());
+        }
+        Ok(self)
+    }
+
     /// Build a single acceleration structure. This is a write operation to the acceleration structure, so
     /// it must be synchronized.
     fn build_acceleration_structure(self, info: &AccelerationStructureBuildInfo) -> Result<Self>
@@ -1,16 +1,94 @@
 //! Contains implementations of the graphics domain for command buffers
 
 use anyhow::{bail, Result};
+use ash::extensions::ext;
 use ash::vk;
 
 use crate::{Allocator, BufferView, Error, GfxSupport, GraphicsCmdBuffer, ImageView};
 use crate::command_buffer::IncompleteCommandBuffer;
+use crate::command_buffer::state::RenderingInfo;
 use crate::core::device::ExtensionID;
 use crate::sync::domain::ExecutionDomain;
 
+impl<D: GfxSupport + ExecutionDomain, A: Allocator> IncompleteCommandBuffer<'_, D, A> {
+    /// Fail if a dynamic state cannot be used on this device, or if the bound graphics pipeline does not declare it as dynamic.
+    /// Dynamic state may be set before binding a pipeline, in which case only the device is checked.
+    fn require_dynamic_state(&self, state: vk::DynamicState) -> Result<()> {
+        self.device.require_dynamic_state(state)?;
+        match &self.current_dynamic_states {
+            Some((pipeline, states)) if !states.contains(&state) => {
+                Err(Error::DynamicStateNotEnabled {
+                    pipeline: pipeline.clone(),
+                    state,
+                }
+                .into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Fail if `count` consecutive color attachments starting at `first_attachment` are not all part of the current render pass.
+    /// Outside a render pass, any range of color attachments is accepted.
+    fn require_color_attachments(&self, first_attachment: u32, count: usize) -> Result<()> {
+        let Some(rendering) = &self.current_rendering_state else { return Ok(()); };
+        let end = first_attachment as u64 + count as u64;
+        if end > rendering.color_formats.len() as u64 {
+            return Err(Error::ColorAttachmentOutOfBounds {
+                first: first_attachment,
+                end,
+                count: rendering.color_formats.len(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Get the function pointers for `VK_EXT_extended_dynamic_state3` after checking a dynamic state from it can be used.
+    fn dynamic_state3(&self, state: vk::DynamicState) -> Result<&ext::ExtendedDynamicState3> {
+        self.require_dynamic_state(state)?;
+        // SAFETY: require_dynamic_state() verified that the extension is enabled.
+        Ok(self.device.dynamic_state3().unwrap())
+    }
+}
+
 impl<D: GfxSupport + ExecutionDomain, A: Allocator> GraphicsCmdBuffer
     for IncompleteCommandBuffer<'_, D, A>
 {
+    /// Begin a dynamic render pass. Pipelines bound inside the render pass are created for its attachment formats.
+    /// The attachments must already be in the layouts given in `info`.
+    /// # Errors
+    /// * Fails if a render pass is already active.
+    /// # Example
+    /// ```
+    /// # use anyhow::Result;
+    /// # use phobos::*;
+    /// # use phobos::sync::domain::*;
+    /// fn clear_image<C: GraphicsCmdBuffer>(cmd: C, image: &ImageView) -> Result<C> {
+    ///     let info = RenderingInfo::new(vk::Rect2D {
+    ///         offset: vk::Offset2D { x: 0, y: 0 },
+    ///         extent: vk::Extent2D { width: image.width(), height: image.height() },
+    ///     })
+    ///     .color_attachment(RenderingAttachmentInfo::color(image).clear_color(ClearColor::Float([0.0; 4])));
+    ///     cmd.begin_rendering(&info)?.end_rendering()
+    /// }
+    /// ```
+    fn begin_rendering(self, info: &RenderingInfo) -> Result<Self> {
+        if self.current_rendering_state.is_some() {
+            return Err(Error::Uncategorized("A render pass is already active").into());
+        }
+        Ok(self.cmd_begin_rendering(info))
+    }
+
+    /// End the current dynamic render pass.
+    /// # Errors
+    /// * Fails if no render pass is active.
+    fn end_rendering(self) -> Result<Self> {
+        if self.current_rendering_state.is_none() {
+            return Err(Error::Uncategorized("No render pass is active").into());
+        }
+        Ok(self.cmd_end_rendering())
+    }
+
     /// Sets the viewport and scissor regions to the entire render area. Can only be called inside a renderpass.
     /// # Example
     /// ```
@@ -159,6 +237,222 @@ impl<D: GfxSupport + ExecutionDomain, A: Allocator> GraphicsCmdBuffer
         Ok(self)
     }
 
+    /// Issue `draw_count` drawcalls with their parameters read from `buffer`, which holds a [`vk::DrawIndirectCommand`]
+    /// every `stride` bytes. This will flush the current descriptor state and actually bind the descriptor sets.
+    /// A `draw_count` larger than one requires the `multiDrawIndirect` feature.
+    /// Directly translates to [`vkCmdDrawIndirect`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdDrawIndirect.html).
+    /// # Errors
+    /// * Fails if flushing the descriptor state fails.
+    /// # Example
+    /// ```
+    /// # use anyhow::Result;
+    /// # use phobos::*;
+    /// # use phobos::sync::domain::*;
+    /// fn draw_indirect<C: GraphicsCmdBuffer>(cmd: C, vertex_buffer: &BufferView, draws: &BufferView) -> Result<C> {
+    ///     cmd.full_viewport_scissor()
+    ///        .bind_graphics_pipeline("my_pipeline")?
+    ///        .bind_vertex_buffer(0, vertex_buffer)
+    ///        .draw_indirect(draws, 16, std::mem::size_of::<vk::DrawIndirectCommand>() as u32)
+    /// }
+    /// ```
+    fn draw_indirect(mut self, buffer: &BufferView, draw_count: u32, stride: u32) -> Result<Self> {
+        self = self.ensure_descriptor_state()?;
+        unsafe {
+            self.device.cmd_draw_indirect(
+                self.handle,
+                buffer.handle(),
+                buffer.offset(),
+                draw_count,
+                stride,
+            );
+        }
+        Ok(self)
+    }
+
+    /// Issue `draw_count` indexed drawcalls with their parameters read from `buffer`, which holds a [`vk::DrawIndexedIndirectCommand`]
+    /// every `stride` bytes. This will flush the current descriptor state and actually bind the descriptor sets.
+    /// A `draw_count` larger than one requires the `multiDrawIndirect` feature.
+    /// Directly translates to [`vkCmdDrawIndexedIndirect`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdDrawIndexedIndirect.html).
+    /// # Errors
+    /// * Fails if flushing the descriptor state fails.
+    fn draw_indexed_indirect(
+        mut self,
+        buffer: &BufferView,
+        draw_count: u32,
+        stride: u32,
+    ) -> Result<Self> {
+        self = self.ensure_descriptor_state()?;
+        unsafe {
+            self.device.cmd_draw_indexed_indirect(
+                self.handle,
+                buffer.handle(),
+                buffer.offset(),
+                draw_count,
+                stride,
+            );
+        }
+        Ok(self)
+    }
+
+    /// Issue drawcalls with their parameters read from `buffer`, which holds a [`vk::DrawIndirectCommand`] every `stride` bytes.
+    /// The number of drawcalls is read from the `u32` at the start of `count_buffer`, and is at most `max_draw_count`.
+    /// This will flush the current descriptor state and actually bind the descriptor sets.
+    /// Requires the `drawIndirectCount` feature of Vulkan 1.2.
+    /// Directly translates to [`vkCmdDrawIndirectCount`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdDrawIndirectCount.html).
+    /// # Errors
+    /// * Fails if flushing the descriptor state fails.
+    /// # Example
+    /// ```
+    /// # use anyhow::Result;
+    /// # use phobos::*;
+    /// # use phobos::sync::domain::*;
+    /// // `draws` and `count` were filled by a culling compute shader.
+    /// fn draw_culled<C: GraphicsCmdBuffer>(cmd: C, draws: &BufferView, count: &BufferView) -> Result<C> {
+    ///     cmd.full_viewport_scissor()
+    ///        .bind_graphics_pipeline("my_pipeline")?
+    ///        .draw_indirect_count(draws, count, 1024, std::mem::size_of::<vk::DrawIndirectCommand>() as u32)
+    /// }
+    /// ```
+    fn draw_indirect_count(
+        mut self,
+        buffer: &BufferView,
+        count_buffer: &BufferView,
+        max_draw_count: u32,
+        stride: u32,
+    ) -> Result<Self> {
+        self = self.ensure_descriptor_state()?;
+        unsafe {
+            self.device.cmd_draw_indirect_count(
+                self.handle,
+                buffer.handle(),
+                buffer.offset(),
+                count_buffer.handle(),
+                count_buffer.offset(),
+                max_draw_count,
+                stride,
+            );
+        }
+        Ok(self)
+    }
+
+    /// Issue indexed drawcalls with their parameters read from `buffer`, which holds a [`vk::DrawIndexedIndirectCommand`] every `stride` bytes.
+    /// The number of drawcalls is read from the `u32` at the start of `count_buffer`, and is at most `max_draw_count`.
+    /// This will flush the current descriptor state and actually bind the descriptor sets.
+    /// Requires the `drawIndirectCount` feature of Vulkan 1.2.
+    /// Directly translates to [`vkCmdDrawIndexedIndirectCount`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdDrawIndexedIndirectCount.html).
+    /// # Errors
+    /// * Fails if flushing the descriptor state fails.
+    fn draw_indexed_indirect_count(
+        mut self,
+        buffer: &BufferView,
+        count_buffer: &BufferView,
+        max_draw_count: u32,
+        stride: u32,
+    ) -> Result<Self> {
+        self = self.ensure_descriptor_state()?;
+        unsafe {
+            self.device.cmd_draw_indexed_indirect_count(
+                self.handle,
+                buffer.handle(),
+                buffer.offset(),
+                count_buffer.handle(),
+                count_buffer.offset(),
+                max_draw_count,
+                stride,
+            );
+        }
+        Ok(self)
+    }
+
+    /// Issue a mesh shading drawcall, launching `x * y * z` task shader workgroups, or mesh shader workgroups if the
+    /// pipeline has no task shader. This will flush the current descriptor state and actually bind the descriptor sets.
+    /// Requires [`ExtensionID::MeshShader`] to be enabled.
+    /// Directly translates to [`vkCmdDrawMeshTasksEXT`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdDrawMeshTasksEXT.html).
+    /// # Errors
+    /// * Fails if [`ExtensionID::MeshShader`] is not enabled.
+    /// * Fails if flushing the descriptor state fails.
+    /// # Example
+    /// ```
+    /// # use anyhow::Result;
+    /// # use phobos::*;
+    /// # use phobos::sync::domain::*;
+    /// fn draw_meshlets<C: GraphicsCmdBuffer>(cmd: C, meshlet_count: u32) -> Result<C> {
+    ///     cmd.full_viewport_scissor()
+    ///        .bind_graphics_pipeline("meshlets")?
+    ///        // One task shader workgroup for every 32 meshlets
+    ///        .draw_mesh_tasks((meshlet_count + 31) / 32, 1, 1)
+    /// }
+    /// ```
+    fn draw_mesh_tasks(mut self, x: u32, y: u32, z: u32) -> Result<Self> {
+        self.device.require_extension(ExtensionID::MeshShader)?;
+        self = self.ensure_descriptor_state()?;
+        let fns = self.device.mesh_shader().unwrap();
+        unsafe {
+            fns.cmd_draw_mesh_tasks(self.handle, x, y, z);
+        }
+        Ok(self)
+    }
+
+    /// Issue `draw_count` mesh shading drawcalls with their parameters read from `buffer`, which holds a
+    /// [`vk::DrawMeshTasksIndirectCommandEXT`] every `stride` bytes. This will flush the current descriptor state and actually
+    /// bind the descriptor sets. Requires [`ExtensionID::MeshShader`] to be enabled.
+    /// Directly translates to [`vkCmdDrawMeshTasksIndirectEXT`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdDrawMeshTasksIndirectEXT.html).
+    /// # Errors
+    /// * Fails if [`ExtensionID::MeshShader`] is not enabled.
+    /// * Fails if flushing the descriptor state fails.
+    fn draw_mesh_tasks_indirect(
+        mut self,
+        buffer: &BufferView,
+        draw_count: u32,
+        stride: u32,
+    ) -> Result<Self> {
+        self.device.require_extension(ExtensionID::MeshShader)?;
+        self = self.ensure_descriptor_state()?;
+        let fns = self.device.mesh_shader().unwrap();
+        unsafe {
+            fns.cmd_draw_mesh_tasks_indirect(
+                self.handle,
+                buffer.handle(),
+                buffer.offset(),
+                draw_count,
+                stride,
+            );
+        }
+        Ok(self)
+    }
+
+    /// Issue mesh shading drawcalls with their parameters read from `buffer`, which holds a [`vk::DrawMeshTasksIndirectCommandEXT`]
+    /// every `stride` bytes. The number of drawcalls is read from the `u32` at the start of `count_buffer`, and is at most `max_draw_count`.
+    /// This will flush the current descriptor state and actually bind the descriptor sets.
+    /// Requires [`ExtensionID::MeshShader`] to be enabled, and the `drawIndirectCount` feature of Vulkan 1.2.
+    /// Directly translates to [`vkCmdDrawMeshTasksIndirectCountEXT`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdDrawMeshTasksIndirectCountEXT.html).
+    /// # Errors
+    /// * Fails if [`ExtensionID::MeshShader`] is not enabled.
+    /// * Fails if flushing the descriptor state fails.
+    fn draw_mesh_tasks_indirect_count(
+        mut self,
+        buffer: &BufferView,
+        count_buffer: &BufferView,
+        max_draw_count: u32,
+        stride: u32,
+    ) -> Result<Self> {
+        self.device.require_extension(ExtensionID::MeshShader)?;
+        self = self.ensure_descriptor_state()?;
+        let fns = self.device.mesh_shader().unwrap();
+        unsafe {
+            fns.cmd_draw_mesh_tasks_indirect_count(
+                self.handle,
+                buffer.handle(),
+                buffer.offset(),
+                count_buffer.handle(),
+                count_buffer.offset(),
+                max_draw_count,
+                stride,
+            );
+        }
+        Ok(self)
+    }
+
     /// Issue a `vkCmdTraceRaysKHR` command. Requires [`ExtensionID::RayTracingPipeline`] to be enabled.
     fn trace_rays(mut self, width: u32, height: u32, depth: u32) -> Result<Self>
     where
@@ -186,6 +480,7 @@ impl<D: GfxSupport + ExecutionDomain, A: Allocator> GraphicsCmdBuffer
     /// Bind a graphics pipeline by name.
     /// # Errors
     /// * Fails if the pipeline was not previously registered in the pipeline cache.
+    /// * Fails with [`Error::PipelineSampleCountMismatch`] if the sample count of the pipeline does not match the attachments of the current render pass.
     /// # Example
     /// ```
     /// # use phobos::*;
@@ -200,7 +495,17 @@ impl<D: GfxSupport + ExecutionDomain, A: Allocator> GraphicsCmdBuffer
     fn bind_graphics_pipeline(mut self, name: &str) -> Result<Self> {
         let Some(rendering_state) = self.current_rendering_state.clone() else { return Err(Error::NoRenderpass.into()) };
         let cache = self.pipeline_cache.clone();
+        let samples = self.current_rendering_samples;
         cache.with_pipeline(name, rendering_state, |pipeline| {
+            if pipeline.samples != samples {
+                return Err(Error::PipelineSampleCountMismatch {
+                    pipeline: name.to_owned(),
+                    expected: samples,
+                    found: pipeline.samples,
+                }
+                .into());
+            }
+            self.current_dynamic_states = Some((name.to_owned(), pipeline.dynamic_states.clone()));
             self.bind_pipeline_impl(
                 pipeline.handle,
                 pipeline.layout,
@@ -329,6 +634,9 @@ impl<D: GfxSupport + ExecutionDomain, A: Allocator> GraphicsCmdBuffer
     /// Set the polygon mode. Only available if `VK_EXT_extended_dynamic_state3` was enabled on device creation.
     /// This extension is automatically requested when available.
     /// Equivalent to [`vkCmdSetPolygonModeEXT`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdSetPolygonModeEXT.html)
+    /// # Errors
+    /// * Fails if `VK_EXT_extended_dynamic_state3` is not enabled, or the device does not support this dynamic state.
+    /// * Fails if the bound pipeline was not created with [`vk::DynamicState::POLYGON_MODE_EXT`].
     /// # Example
     /// ```
     /// # use anyhow::Result;
@@ -340,16 +648,261 @@ impl<D: GfxSupport + ExecutionDomain, A: Allocator> GraphicsCmdBuffer
     /// }
     /// ```
     fn set_polygon_mode(self, mode: vk::PolygonMode) -> Result<Self> {
-        let funcs = self
-            .device
-            .dynamic_state3()
-            .ok_or_else::<anyhow::Error, _>(|| {
-                Error::ExtensionNotSupported(ExtensionID::ExtendedDynamicState3).into()
-            })?;
+        let funcs = self.dynamic_state3(vk::DynamicState::POLYGON_MODE_EXT)?;
         // SAFETY: Vulkan API call. This function pointer is not null because we just verified its availability.
         unsafe {
             funcs.cmd_set_polygon_mode(self.handle, mode);
         }
         Ok(self)
     }
+
+    /// Set the cull mode. Equivalent to [`vkCmdSetCullMode`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdSetCullMode.html)
+    /// # Errors
+    /// * Fails if the bound pipeline was not created with [`vk::DynamicState::CULL_MODE`].
+    /// # Example
+    /// ```
+    /// # use anyhow::Result;
+    /// # use phobos::*;
+    /// # use phobos::sync::domain::*;
+    /// fn draw_two_sided<C: GraphicsCmdBuffer>(cmd: C) -> Result<C> {
+    ///     cmd.set_cull_mode(vk::CullModeFlags::NONE)?
+    ///         .draw(6, 1, 0, 0)
+    /// }
+    /// ```
+    fn set_cull_mode(self, mode: vk::CullModeFlags) -> Result<Self> {
+        self.require_dynamic_state(vk::DynamicState::CULL_MODE)?;
+        // SAFETY: Vulkan API call.
+        unsafe {
+            self.device.cmd_set_cull_mode(self.handle, mode);
+        }
+        Ok(self)
+    }
+
+    /// Set the front face orientation. Equivalent to [`vkCmdSetFrontFace`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdSetFrontFace.html)
+    /// # Errors
+    /// * Fails if the bound pipeline was not created with [`vk::DynamicState::FRONT_FACE`].
+    fn set_front_face(self, front_face: vk::FrontFace) -> Result<Self> {
+        self.require_dynamic_state(vk::DynamicState::FRONT_FACE)?;
+        // SAFETY: Vulkan API call.
+        unsafe {
+            self.device.cmd_set_front_face(self.handle, front_face);
+        }
+        Ok(self)
+    }
+
+    /// Set the primitive topology. The topology must be of the same class as the topology the pipeline was created with.
+    /// Equivalent to [`vkCmdSetPrimitiveTopology`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdSetPrimitiveTopology.html)
+    /// # Errors
+    /// * Fails if the bound pipeline was not created with [`vk::DynamicState::PRIMITIVE_TOPOLOGY`].
+    fn set_primitive_topology(self, topology: vk::PrimitiveTopology) -> Result<Self> {
+        self.require_dynamic_state(vk::DynamicState::PRIMITIVE_TOPOLOGY)?;
+        // SAFETY: Vulkan API call.
+        unsafe {
+            self.device
+                .cmd_set_primitive_topology(self.handle, topology);
+        }
+        Ok(self)
+    }
+
+    /// Enable or disable the depth test. Equivalent to [`vkCmdSetDepthTestEnable`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdSetDepthTestEnable.html)
+    /// # Errors
+    /// * Fails if the bound pipeline was not created with [`vk::DynamicState::DEPTH_TEST_ENABLE`].
+    fn set_depth_test_enable(self, enable: bool) -> Result<Self> {
+        self.require_dynamic_state(vk::DynamicState::DEPTH_TEST_ENABLE)?;
+        // SAFETY: Vulkan API call.
+        unsafe {
+            self.device.cmd_set_depth_test_enable(self.handle, enable);
+        }
+        Ok(self)
+    }
+
+    /// Enable or disable depth writes. Equivalent to [`vkCmdSetDepthWriteEnable`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdSetDepthWriteEnable.html)
+    /// # Errors
+    /// * Fails if the bound pipeline was not created with [`vk::DynamicState::DEPTH_WRITE_ENABLE`].
+    /// # Example
+    /// ```
+    /// # use anyhow::Result;
+    /// # use phobos::*;
+    /// # use phobos::sync::domain::*;
+    /// // Draw transparent geometry with the same pipeline as opaque geometry.
+    /// fn draw_transparent<C: GraphicsCmdBuffer>(cmd: C) -> Result<C> {
+    ///     cmd.set_depth_write_enable(false)?
+    ///         .set_depth_compare_op(vk::CompareOp::LESS_OR_EQUAL)?
+    ///         .draw(6, 1, 0, 0)
+    /// }
+    /// ```
+    fn set_depth_write_enable(self, enable: bool) -> Result<Self> {
+        self.require_dynamic_state(vk::DynamicState::DEPTH_WRITE_ENABLE)?;
+        // SAFETY: Vulkan API call.
+        unsafe {
+            self.device.cmd_set_depth_write_enable(self.handle, enable);
+        }
+        Ok(self)
+    }
+
+    /// Set the depth compare operation. Equivalent to [`vkCmdSetDepthCompareOp`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdSetDepthCompareOp.html)
+    /// # Errors
+    /// * Fails if the bound pipeline was not created with [`vk::DynamicState::DEPTH_COMPARE_OP`].
+    fn set_depth_compare_op(self, op: vk::CompareOp) -> Result<Self> {
+        self.require_dynamic_state(vk::DynamicState::DEPTH_COMPARE_OP)?;
+        // SAFETY: Vulkan API call.
+        unsafe {
+            self.device.cmd_set_depth_compare_op(self.handle, op);
+        }
+        Ok(self)
+    }
+
+    /// Enable or disable the stencil test. Equivalent to [`vkCmdSetStencilTestEnable`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdSetStencilTestEnable.html)
+    /// # Errors
+    /// * Fails if the bound pipeline was not created with [`vk::DynamicState::STENCIL_TEST_ENABLE`].
+    fn set_stencil_test_enable(self, enable: bool) -> Result<Self> {
+        self.require_dynamic_state(vk::DynamicState::STENCIL_TEST_ENABLE)?;
+        // SAFETY: Vulkan API call.
+        unsafe {
+            self.device.cmd_set_stencil_test_enable(self.handle, enable);
+        }
+        Ok(self)
+    }
+
+    /// Set the stencil operations for the given faces. Equivalent to [`vkCmdSetStencilOp`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdSetStencilOp.html)
+    /// # Errors
+    /// * Fails if the bound pipeline was not created with [`vk::DynamicState::STENCIL_OP`].
+    fn set_stencil_op(
+        self,
+        faces: vk::StencilFaceFlags,
+        fail_op: vk::StencilOp,
+        pass_op: vk::StencilOp,
+        depth_fail_op: vk::StencilOp,
+        compare_op: vk::CompareOp,
+    ) -> Result<Self> {
+        self.require_dynamic_state(vk::DynamicState::STENCIL_OP)?;
+        // SAFETY: Vulkan API call.
+        unsafe {
+            self.device.cmd_set_stencil_op(
+                self.handle,
+                faces,
+                fail_op,
+                pass_op,
+                depth_fail_op,
+                compare_op,
+            );
+        }
+        Ok(self)
+    }
+
+    /// Enable or disable depth bias. Equivalent to [`vkCmdSetDepthBiasEnable`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdSetDepthBiasEnable.html)
+    /// # Errors
+    /// * Fails if the bound pipeline was not created with [`vk::DynamicState::DEPTH_BIAS_ENABLE`].
+    fn set_depth_bias_enable(self, enable: bool) -> Result<Self> {
+        self.require_dynamic_state(vk::DynamicState::DEPTH_BIAS_ENABLE)?;
+        // SAFETY: Vulkan API call.
+        unsafe {
+            self.device.cmd_set_depth_bias_enable(self.handle, enable);
+        }
+        Ok(self)
+    }
+
+    /// Set the depth bias factors. Equivalent to [`vkCmdSetDepthBias`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdSetDepthBias.html)
+    /// # Errors
+    /// * Fails if the bound pipeline was not created with [`vk::DynamicState::DEPTH_BIAS`].
+    /// # Example
+    /// ```
+    /// # use anyhow::Result;
+    /// # use phobos::*;
+    /// # use phobos::sync::domain::*;
+    /// fn draw_shadow_casters<C: GraphicsCmdBuffer>(cmd: C) -> Result<C> {
+    ///     cmd.set_depth_bias_enable(true)?
+    ///         .set_depth_bias(1.25, 0.0, 1.75)?
+    ///         .draw(36, 1, 0, 0)
+    /// }
+    /// ```
+    fn set_depth_bias(self, constant_factor: f32, clamp: f32, slope_factor: f32) -> Result<Self> {
+        self.require_dynamic_state(vk::DynamicState::DEPTH_BIAS)?;
+        // SAFETY: Vulkan API call.
+        unsafe {
+            self.device
+                .cmd_set_depth_bias(self.handle, constant_factor, clamp, slope_factor);
+        }
+        Ok(self)
+    }
+
+    /// Enable or disable blending for consecutive color attachments, starting at `first_attachment`.
+    /// Only available if `VK_EXT_extended_dynamic_state3` was enabled on device creation.
+    /// Equivalent to [`vkCmdSetColorBlendEnableEXT`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdSetColorBlendEnableEXT.html)
+    /// # Errors
+    /// * Fails if `VK_EXT_extended_dynamic_state3` is not enabled, or the device does not support this dynamic state.
+    /// * Fails if the bound pipeline was not created with [`vk::DynamicState::COLOR_BLEND_ENABLE_EXT`].
+    /// * Fails if the attachments are out of bounds of the color attachments of the current render pass.
+    fn set_color_blend_enable(self, first_attachment: u32, enable: &[bool]) -> Result<Self> {
+        let funcs = self.dynamic_state3(vk::DynamicState::COLOR_BLEND_ENABLE_EXT)?;
+        self.require_color_attachments(first_attachment, enable.len())?;
+        let enable = enable
+            .iter()
+            .map(|enable| vk::Bool32::from(*enable))
+            .collect::<Vec<_>>();
+        // SAFETY: Vulkan API call. This function pointer is not null because we just verified its availability.
+        unsafe {
+            funcs.cmd_set_color_blend_enable(self.handle, first_attachment, &enable);
+        }
+        Ok(self)
+    }
+
+    /// Set the blend equations for consecutive color attachments, starting at `first_attachment`.
+    /// Only available if `VK_EXT_extended_dynamic_state3` was enabled on device creation.
+    /// Equivalent to [`vkCmdSetColorBlendEquationEXT`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdSetColorBlendEquationEXT.html)
+    /// # Errors
+    /// * Fails if `VK_EXT_extended_dynamic_state3` is not enabled, or the device does not support this dynamic state.
+    /// * Fails if the bound pipeline was not created with [`vk::DynamicState::COLOR_BLEND_EQUATION_EXT`].
+    /// * Fails if the attachments are out of bounds of the color attachments of the current render pass.
+    /// # Example
+    /// ```
+    /// # use anyhow::Result;
+    /// # use phobos::*;
+    /// # use phobos::sync::domain::*;
+    /// fn set_alpha_blending<C: GraphicsCmdBuffer>(cmd: C) -> Result<C> {
+    ///     cmd.set_color_blend_enable(0, &[true])?
+    ///         .set_color_blend_equation(0, &[vk::ColorBlendEquationEXT {
+    ///             src_color_blend_factor: vk::BlendFactor::SRC_ALPHA,
+    ///             dst_color_blend_factor: vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+    ///             color_blend_op: vk::BlendOp::ADD,
+    ///             src_alpha_blend_factor: vk::BlendFactor::ONE,
+    ///             dst_alpha_blend_factor: vk::BlendFactor::ZERO,
+    ///             alpha_blend_op: vk::BlendOp::ADD,
+    ///         }])
+    /// }
+    /// ```
+    fn set_color_blend_equation(
+        self,
+        first_attachment: u32,
+        equations: &[vk::ColorBlendEquationEXT],
+    ) -> Result<Self> {
+        let funcs = self.dynamic_state3(vk::DynamicState::COLOR_BLEND_EQUATION_EXT)?;
+        self.require_color_attachments(first_attachment, equations.len())?;
+        // SAFETY: Vulkan API call. This function pointer is not null because we just verified its availability.
+        unsafe {
+            funcs.cmd_set_color_blend_equation(self.handle, first_attachment, equations);
+        }
+        Ok(self)
+    }
+
+    /// Set the color write masks for consecutive color attachments, starting at `first_attachment`.
+    /// Only available if `VK_EXT_extended_dynamic_state3` was enabled on device creation.
+    /// Equivalent to [`vkCmdSetColorWriteMaskEXT`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdSetColorWriteMaskEXT.html)
+    /// # Errors
+    /// * Fails if `VK_EXT_extended_dynamic_state3` is not enabled, or the device does not support this dynamic state.
+    /// * Fails if the bound pipeline was not created with [`vk::DynamicState::COLOR_WRITE_MASK_EXT`].
+    /// * Fails if the attachments are out of bounds of the color attachments of the current render pass.
+    fn set_color_write_mask(
+        self,
+        first_attachment: u32,
+        masks: &[vk::ColorComponentFlags],
+    ) -> Result<Self> {
+        let funcs = self.dynamic_state3(vk::DynamicState::COLOR_WRITE_MASK_EXT)?;
+        self.require_color_attachments(first_attachment, masks.len())?;
+        // SAFETY: Vulkan API call. This function pointer is not null because we just verified its availability.
+        unsafe {
+            funcs.cmd_set_color_write_mask(self.handle, first_attachment, masks);
+        }
+        Ok(self)
+    }
 }
@@ -9,14 +9,18 @@ use anyhow::{anyhow, ensure, Result};
 use ash::vk;
 
 use crate::{
-    Allocator, BufferView, DebugMessenger, DescriptorCache, DescriptorSet, Device, ImageView,
-    IncompleteCmdBuffer, PhysicalResourceBindings, PipelineCache, PipelineStage, Sampler,
+    Allocator, BufferView, DebugMessenger, DescriptorCache, DescriptorSet, Device, Event,
+    ImageView, IncompleteCmdBuffer, PhysicalResourceBindings, PipelineCache, PipelineStage, Sampler,
     VirtualResource,
 };
-use crate::command_buffer::{CommandBuffer, IncompleteCommandBuffer};
+use crate::command_buffer::{
+    CommandBuffer, ImageState, IncompleteCommandBuffer, SecondaryCommandBuffer,
+    SecondaryRenderingInfo,
+};
 use crate::command_buffer::state::{RenderingAttachmentInfo, RenderingInfo};
-use crate::core::queue::Queue;
+use crate::core::queue::{Queue, QueueInfo};
 use crate::descriptor::builder::DescriptorSetBuilder;
+use crate::graph::resource::WRITE_ACCESS;
 use crate::pipeline::create_info::PipelineRenderingInfo;
 use crate::query_pool::{QueryPool, ScopedQuery, TimestampQuery};
 use crate::raytracing::acceleration_structure::AccelerationStructure;
@@ -54,15 +58,20 @@ for IncompleteCommandBuffer<'q, D, A>
             device,
             handle,
             timestamp_valid_bits: queue_lock.family_properties().timestamp_valid_bits,
-            queue_lock,
+            queue_info: *queue_lock.info(),
+            queue_lock: Some(queue_lock),
+            secondary: false,
             current_pipeline_layout: vk::PipelineLayout::null(),
             current_set_layouts: vec![],
             current_bindpoint: vk::PipelineBindPoint::default(),
             current_rendering_state: None,
+            current_rendering_samples: vk::SampleCountFlags::TYPE_1,
             current_render_area: Default::default(),
             current_descriptor_sets: None,
             descriptor_state_needs_update: false,
             current_sbt_regions: None,
+            current_dynamic_states: None,
+            image_states: HashMap::new(),
             descriptor_cache: descriptors,
             pipeline_cache: pipelines,
             _domain: PhantomData,
@@ -84,7 +93,13 @@ for IncompleteCommandBuffer<'q, D, A>
     ///     Ok(cmd)
     /// }
     /// ```
+    /// # Errors
+    /// * Fails if this is a secondary command buffer. Use [`IncompleteCommandBuffer::finish_secondary()`] for those instead.
     fn finish(self) -> Result<CommandBuffer<D>> {
+        ensure!(
+            !self.secondary,
+            "secondary command buffers must be finished with finish_secondary()"
+        );
         // SAFETY:
         // * `self` is valid, so `device` and `self.handle` are valid.
         // * `self` is valid, so this command buffer is in the recording state (see `new()`).
@@ -96,7 +111,7 @@ for IncompleteCommandBuffer<'q, D, A>
     }
 }
 
-impl<D: ExecutionDomain, A: Allocator> IncompleteCommandBuffer<'_, D, A> {
+impl<'q, D: ExecutionDomain, A: Allocator> IncompleteCommandBuffer<'q, D, A> {
     /// Bind a descriptor set to the command buffer.
     /// # Errors
     /// - Fails if no pipeline was bound.
@@ -424,10 +439,44 @@ impl<D: ExecutionDomain, A: Allocator> IncompleteCommandBuffer<'_, D, A> {
         Ok(self)
     }
 
+    /// Binds a new descriptor with descriptor type [`vk::DescriptorType::ACCELERATION_STRUCTURE_KHR`]. The acceleration structure
+    /// bound to this is obtained by resolving the input resource from the given resource bindings.
+    /// This binding is not actually flushed to the command buffer until the next draw or dispatch call.
+    /// # Errors
+    /// * Fails if the virtual resource has no physical binding associated to it.
+    /// # Example
+    /// ```
+    /// # use anyhow::Result;
+    /// # use phobos::sync::domain::ExecutionDomain;
+    /// # use phobos::*;
+    /// fn use_resolve_and_bind<'q, D: ExecutionDomain + GfxSupport>(cmd: IncompleteCommandBuffer<'q, D>, accel: &AccelerationStructure) -> Result<IncompleteCommandBuffer<'q, D>> {
+    ///     let resource = VirtualResource::acceleration_structure("tlas");
+    ///     let mut bindings = PhysicalResourceBindings::new();
+    ///     bindings.bind_acceleration_structure("tlas", accel);
+    ///
+    ///     cmd.resolve_and_bind_acceleration_structure(0, 0, &resource, &bindings)?
+    ///        .trace_rays(1920, 1080, 1)
+    /// }
+    /// ```
+    pub fn resolve_and_bind_acceleration_structure(
+        mut self,
+        set: u32,
+        binding: u32,
+        resource: &VirtualResource,
+        bindings: &PhysicalResourceBindings,
+    ) -> Result<Self> {
+        self.modify_descriptor_set(set, |builder| {
+            builder.resolve_and_bind_acceleration_structure(binding, resource, bindings)
+        })?;
+        Ok(self)
+    }
+
     /// Transitions an image layout manually. For attachment layouts and other
     /// resources used in the pass graph, this can be done automatically.
+    /// Outside the pass graph, prefer [`IncompleteCommandBuffer::require_layout()`], which remembers the old layout for you.
+    /// The new layout is also recorded for later calls to `require_layout()`.
     pub fn transition_image(
-        self,
+        mut self,
         image: &ImageView,
         src_stage: PipelineStage,
         dst_stage: PipelineStage,
@@ -462,9 +511,105 @@ impl<D: ExecutionDomain, A: Allocator> IncompleteCommandBuffer<'_, D, A> {
             image_memory_barrier_count: 1,
             p_image_memory_barriers: &barrier,
         };
+        let state = if dst_access.intersects(WRITE_ACCESS) {
+            ImageState::written(to, dst_stage, dst_access)
+        } else {
+            ImageState::transitioned(to, dst_stage, dst_access)
+        };
+        self.image_states.insert(image.id(), state);
         self.pipeline_barrier(&dependency)
     }
 
+    /// Make sure an image is in the given layout, and that all earlier accesses to it are visible to the given stage and access.
+    /// This remembers the last layout and access of every image view used with it in this command buffer, and only
+    /// inserts a barrier if one is needed. Reads in the same layout do not need a barrier if an earlier barrier already made
+    /// the last write visible to their stage and access.
+    ///
+    /// The layout of an image must be known before it is first used with this function in a command buffer. Tell the tracker
+    /// which layout it is in with [`IncompleteCommandBuffer::assume_layout()`], or assume [`vk::ImageLayout::UNDEFINED`]
+    /// to discard its contents.
+    ///
+    /// Layouts are tracked per image view, so views that overlap the same image region are not tracked together. Layout changes made
+    /// by the pass graph, render passes, secondary command buffers or raw [`IncompleteCommandBuffer::pipeline_barrier()`] calls are not tracked either.
+    /// # Errors
+    /// * Fails if the layout of the image is not known. Call [`IncompleteCommandBuffer::assume_layout()`] first.
+    /// # Example
+    /// ```
+    /// # use phobos::*;
+    /// # use phobos::sync::domain::*;
+    /// # use anyhow::Result;
+    /// fn upload_and_sample<'q>(cmd: IncompleteCommandBuffer<'q, All>, staging: &BufferView, texture: &ImageView) -> Result<IncompleteCommandBuffer<'q, All>> {
+    ///     // The texture is overwritten, so its old contents can be discarded.
+    ///     let cmd = cmd
+    ///         .assume_layout(texture, vk::ImageLayout::UNDEFINED)
+    ///         .require_layout(texture, vk::ImageLayout::TRANSFER_DST_OPTIMAL, PipelineStage::TRANSFER, vk::AccessFlags2::TRANSFER_WRITE)?
+    ///         .copy_buffer_to_image(staging, texture)?
+    ///         .require_layout(texture, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL, PipelineStage::FRAGMENT_SHADER, vk::AccessFlags2::SHADER_READ)?;
+    ///     Ok(cmd)
+    /// }
+    /// ```
+    pub fn require_layout(
+        mut self,
+        image: &ImageView,
+        layout: vk::ImageLayout,
+        stage: PipelineStage,
+        access: vk::AccessFlags2,
+    ) -> Result<Self> {
+        let previous = self.image_states.get(&image.id()).copied().ok_or_else(|| {
+            anyhow!("layout of image view {} is unknown, call assume_layout() first", image.id())
+        })?;
+        // Reads in the same layout need no barrier if an earlier barrier already made the last write visible to them.
+        // Later writes must still wait for these reads.
+        let visible = previous.write_stage == PipelineStage::NONE
+            || (previous.visible_stage.contains(stage) && previous.visible_access.contains(access));
+        if previous.layout == layout && !access.intersects(WRITE_ACCESS) && visible {
+            self.image_states.insert(
+                image.id(),
+                ImageState {
+                    read_stage: previous.read_stage | stage,
+                    read_access: previous.read_access | access,
+                    ..previous
+                },
+            );
+            return Ok(self);
+        }
+        // Only the last write needs to be made available, reads since then only need an execution dependency.
+        let mut cmd = self.transition_image(
+            image,
+            previous.write_stage | previous.read_stage,
+            stage,
+            previous.layout,
+            layout,
+            previous.write_access,
+            access,
+        );
+        // The barrier made the last write visible to this read, in addition to the reads it was already visible to.
+        if previous.layout == layout && !access.intersects(WRITE_ACCESS) {
+            cmd.image_states.insert(
+                image.id(),
+                ImageState {
+                    visible_stage: previous.visible_stage | stage,
+                    visible_access: previous.visible_access | access,
+                    read_stage: previous.read_stage | stage,
+                    read_access: previous.read_access | access,
+                    ..previous
+                },
+            );
+        }
+        Ok(cmd)
+    }
+
+    /// Tell the layout tracker of [`IncompleteCommandBuffer::require_layout()`] which layout an image is in, for example because
+    /// it was transitioned in an earlier submission. Use [`vk::ImageLayout::UNDEFINED`] if the contents of the image can be discarded.
+    /// This does not record any commands.
+    pub fn assume_layout(mut self, image: &ImageView, layout: vk::ImageLayout) -> Self {
+        self.image_states.insert(
+            image.id(),
+            ImageState::written(layout, PipelineStage::NONE, vk::AccessFlags2::NONE),
+        );
+        self
+    }
+
     /// Insert a global memory barrier. If you want to create a barrier for a buffer, prefer using this as every driver
     /// implements buffer barriers as global memory barriers anyway.
     /// Uses [`vkCmdPipelineBarrier2`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdPipelineBarrier2KHR.html) directly.
@@ -509,6 +654,31 @@ impl<D: ExecutionDomain, A: Allocator> IncompleteCommandBuffer<'_, D, A> {
         self
     }
 
+    /// Signal an event once all commands before it have completed the first half of `dependency`. Together with
+    /// [`IncompleteCommandBuffer::wait_event()`], this splits a pipeline barrier into two halves, so that commands recorded
+    /// in between are not blocked by it. Direct translation of [`vkCmdSetEvent2`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdSetEvent2.html).
+    pub fn set_event(self, event: &Event, dependency: &vk::DependencyInfo) -> Self {
+        unsafe {
+            self.device
+                .cmd_set_event2(self.handle, event.handle(), dependency);
+        }
+        self
+    }
+
+    /// Wait for an event signaled with [`IncompleteCommandBuffer::set_event()`] before executing the second half of `dependency`.
+    /// `dependency` must be identical to the one the event was signaled with.
+    /// Direct translation of [`vkCmdWaitEvents2`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdWaitEvents2.html).
+    pub fn wait_event(self, event: &Event, dependency: &vk::DependencyInfo) -> Self {
+        unsafe {
+            self.device.cmd_wait_events2(
+                self.handle,
+                std::slice::from_ref(&event.handle()),
+                std::slice::from_ref(dependency),
+            );
+        }
+        self
+    }
+
     /// Upload a single value of push constants. These are small packets of data stored inside the command buffer, so their state is tracked while recording and executing.
     /// Direct translation of [`vkCmdPushConstants`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdPushConstants.html).
     /// # Example
@@ -601,8 +771,20 @@ impl<D: ExecutionDomain, A: Allocator> IncompleteCommandBuffer<'_, D, A> {
         Ok(self)
     }
 
+    /// Update the state used to look up pipelines and set viewports to that of a dynamic render pass.
+    fn set_rendering_state(&mut self, info: &SecondaryRenderingInfo) {
+        self.current_rendering_state = Some(PipelineRenderingInfo {
+            view_mask: info.view_mask,
+            color_formats: info.color_formats.clone(),
+            depth_format: info.depth_format,
+            stencil_format: info.stencil_format,
+        });
+        self.current_rendering_samples = info.samples;
+        self.current_render_area = info.render_area;
+    }
+
     /// Begins a dynamic renderpass. This must be called before binding any pipelines.
-    pub(crate) fn begin_rendering(mut self, info: &RenderingInfo) -> Self {
+    pub(crate) fn cmd_begin_rendering(mut self, info: &RenderingInfo) -> Self {
         let map_attachment = |attachment: &RenderingAttachmentInfo| vk::RenderingAttachmentInfo {
             s_type: vk::StructureType::RENDERING_ATTACHMENT_INFO,
             p_next: std::ptr::null(),
@@ -617,9 +799,12 @@ impl<D: ExecutionDomain, A: Allocator> IncompleteCommandBuffer<'_, D, A> {
                 Some(view) => unsafe { view.handle() },
                 None => vk::ImageView::null(),
             },
-            resolve_image_layout: attachment
-                .resolve_image_layout
-                .unwrap_or(vk::ImageLayout::UNDEFINED),
+            resolve_image_layout: match &attachment.resolve_image_view {
+                Some(_) => attachment
+                    .resolve_image_layout
+                    .unwrap_or(attachment.image_layout),
+                None => vk::ImageLayout::UNDEFINED,
+            },
             load_op: attachment.load_op,
             store_op: attachment.store_op,
             clear_value: attachment.clear_value,
@@ -656,39 +841,147 @@ impl<D: ExecutionDomain, A: Allocator> IncompleteCommandBuffer<'_, D, A> {
             self.device.cmd_begin_rendering(self.handle, &vk_info);
         }
 
-        self.current_rendering_state = Some(PipelineRenderingInfo {
-            view_mask: info.view_mask,
-            color_formats: info
-                .color_attachments
-                .iter()
-                .map(|attachment| attachment.image_view.format())
-                .collect(),
-            depth_format: info
-                .depth_attachment
-                .as_ref()
-                .map(|attachment| attachment.image_view.format()),
-            stencil_format: info
-                .stencil_attachment
-                .as_ref()
-                .map(|attachment| attachment.image_view.format()),
-        });
-        self.current_render_area = info.render_area;
+        self.set_rendering_state(&info.into());
 
         self
     }
 
     /// Ends a dynamic renderpass.
-    pub(crate) fn end_rendering(mut self) -> Self {
+    pub(crate) fn cmd_end_rendering(mut self) -> Self {
         unsafe {
-            // Safety: self is valid, the caller must ensure begin_rendering() was called first.
+            // Safety: self is valid, the caller must ensure cmd_begin_rendering() was called first.
             self.device.cmd_end_rendering(self.handle);
         }
         self.current_rendering_state = None;
+        self.current_rendering_samples = vk::SampleCountFlags::TYPE_1;
         self.current_render_area = vk::Rect2D::default();
 
         self
     }
 
+    /// Begin recording a secondary command buffer, to be executed by a primary command buffer on a queue described by `queue_info`.
+    /// Unlike primary command buffers, this does not hold a lock on the queue.
+    /// If `rendering` is given, the secondary command buffer is executed inside the dynamic render pass it describes.
+    /// This render pass must be started with [`vk::RenderingFlags::CONTENTS_SECONDARY_COMMAND_BUFFERS`].
+    pub(crate) fn new_secondary(
+        device: Device,
+        handle: vk::CommandBuffer,
+        queue_info: QueueInfo,
+        timestamp_valid_bits: u32,
+        rendering: Option<&SecondaryRenderingInfo>,
+        pipelines: PipelineCache<A>,
+        descriptors: DescriptorCache,
+    ) -> Result<Self> {
+        let rendering_info = rendering.map(|info| vk::CommandBufferInheritanceRenderingInfo {
+            s_type: vk::StructureType::COMMAND_BUFFER_INHERITANCE_RENDERING_INFO,
+            p_next: std::ptr::null(),
+            flags: info.flags & !vk::RenderingFlags::CONTENTS_SECONDARY_COMMAND_BUFFERS,
+            view_mask: info.view_mask,
+            color_attachment_count: info.color_formats.len() as u32,
+            p_color_attachment_formats: info.color_formats.as_ptr(),
+            depth_attachment_format: info.depth_format.unwrap_or(vk::Format::UNDEFINED),
+            stencil_attachment_format: info.stencil_format.unwrap_or(vk::Format::UNDEFINED),
+            rasterization_samples: info.samples,
+        });
+        let inheritance_info = vk::CommandBufferInheritanceInfo {
+            s_type: vk::StructureType::COMMAND_BUFFER_INHERITANCE_INFO,
+            p_next: match &rendering_info {
+                Some(info) => (info as *const _) as *const std::ffi::c_void,
+                None => std::ptr::null(),
+            },
+            render_pass: vk::RenderPass::null(),
+            subpass: 0,
+            framebuffer: vk::Framebuffer::null(),
+            occlusion_query_enable: vk::FALSE,
+            query_flags: vk::QueryControlFlags::empty(),
+            pipeline_statistics: vk::QueryPipelineStatisticFlags::empty(),
+        };
+        let mut flags = vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT;
+        if rendering.is_some() {
+            flags |= vk::CommandBufferUsageFlags::RENDER_PASS_CONTINUE;
+        }
+        let begin_info = vk::CommandBufferBeginInfo {
+            s_type: vk::StructureType::COMMAND_BUFFER_BEGIN_INFO,
+            p_next: std::ptr::null(),
+            flags,
+            p_inheritance_info: &inheritance_info,
+        };
+        unsafe {
+            // SAFETY:
+            // * A valid VkDevice was passed in
+            // * The command buffer passed in is a valid secondary command buffer.
+            // * The begin_info structure and its inheritance info are valid.
+            device.begin_command_buffer(handle, &begin_info)?;
+        }
+
+        let mut cmd = IncompleteCommandBuffer {
+            device,
+            handle,
+            queue_lock: None,
+            secondary: true,
+            queue_info,
+            timestamp_valid_bits,
+            current_pipeline_layout: vk::PipelineLayout::null(),
+            current_set_layouts: vec![],
+            current_bindpoint: vk::PipelineBindPoint::default(),
+            current_rendering_state: None,
+            current_rendering_samples: vk::SampleCountFlags::TYPE_1,
+            current_render_area: Default::default(),
+            current_descriptor_sets: None,
+            descriptor_state_needs_update: false,
+            current_sbt_regions: None,
+            current_dynamic_states: None,
+            image_states: HashMap::new(),
+            descriptor_cache: descriptors,
+            pipeline_cache: pipelines,
+            _domain: PhantomData,
+        };
+        if let Some(info) = rendering {
+            cmd.set_rendering_state(info);
+        }
+        Ok(cmd)
+    }
+
+    /// Finish recording a secondary command buffer obtained from [`ExecutionManager::on_domain_secondary()`](crate::ExecutionManager::on_domain_secondary).
+    /// The result can only be executed by a primary command buffer with [`IncompleteCommandBuffer::execute_commands()`].
+    /// # Errors
+    /// * Fails if this is a primary command buffer. Use [`IncompleteCmdBuffer::finish()`] for those instead.
+    pub fn finish_secondary(self) -> Result<SecondaryCommandBuffer<D>> {
+        ensure!(self.secondary, "primary command buffers must be finished with finish()");
+        // SAFETY:
+        // * `self` is valid, so `device` and `self.handle` are valid.
+        // * `self` is valid, so this command buffer is in the recording state (see `new_secondary()`).
+        unsafe { self.device.end_command_buffer(self.handle)? }
+        Ok(SecondaryCommandBuffer {
+            handle: self.handle,
+            _domain: PhantomData,
+        })
+    }
+
+    /// Execute secondary command buffers. Equivalent of [`vkCmdExecuteCommands`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkCmdExecuteCommands.html).
+    /// The secondary command buffers must be obtained from [`ExecutionManager::on_domain_secondary()`](crate::ExecutionManager::on_domain_secondary),
+    /// and if they were recorded with rendering info, the matching render pass must be active.
+    /// After this, no pipeline or descriptor sets are bound anymore.
+    pub fn execute_commands(mut self, cmds: &[SecondaryCommandBuffer<D>]) -> Self {
+        let handles = cmds.iter().map(|cmd| cmd.handle).collect::<Vec<_>>();
+        unsafe {
+            // SAFETY: self is valid, and all command buffers are finished secondary command buffers.
+            self.device.cmd_execute_commands(self.handle, &handles);
+        }
+        // The state bound in the secondary command buffers does not carry over to this command buffer.
+        self.current_pipeline_layout = vk::PipelineLayout::null();
+        self.current_set_layouts.clear();
+        self.current_descriptor_sets = None;
+        self.descriptor_state_needs_update = false;
+        self.current_dynamic_states = None;
+        self
+    }
+
+    /// Get the timestamp valid bits of the queue this command buffer was allocated from.
+    pub(crate) fn timestamp_valid_bits(&self) -> u32 {
+        self.timestamp_valid_bits
+    }
+
     /// Start a label region.
     #[cfg(feature = "debug-markers")]
     pub(crate) fn begin_label(
@@ -717,4 +1010,50 @@ impl<D: ExecutionDomain, A: Allocator> IncompleteCommandBuffer<'_, D, A> {
     pub unsafe fn handle(&self) -> vk::CommandBuffer {
         self.handle
     }
+
+    /// Get the device this command buffer was allocated from.
+    pub(crate) fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Get information about the queue this command buffer was allocated from.
+    pub(crate) fn queue_info(&self) -> &QueueInfo {
+        &self.queue_info
+    }
+
+    /// Reinterpret this command buffer as a command buffer over another domain. This is used to record passes
+    /// for a different domain into this command buffer.
+    /// # Errors
+    /// * Fails if the queue this command buffer was allocated from does not support all operations in `queue_flags`.
+    pub(crate) fn into_domain<D2: ExecutionDomain>(
+        self,
+        queue_flags: vk::QueueFlags,
+    ) -> Result<IncompleteCommandBuffer<'q, D2, A>> {
+        ensure!(
+            self.queue_info.flags.contains(queue_flags),
+            "queue does not support {queue_flags:?}"
+        );
+        Ok(IncompleteCommandBuffer {
+            device: self.device,
+            handle: self.handle,
+            queue_lock: self.queue_lock,
+            secondary: self.secondary,
+            queue_info: self.queue_info,
+            timestamp_valid_bits: self.timestamp_valid_bits,
+            current_pipeline_layout: self.current_pipeline_layout,
+            current_set_layouts: self.current_set_layouts,
+            current_bindpoint: self.current_bindpoint,
+            current_rendering_state: self.current_rendering_state,
+            current_rendering_samples: self.current_rendering_samples,
+            current_render_area: self.current_render_area,
+            current_descriptor_sets: self.current_descriptor_sets,
+            descriptor_state_needs_update: self.descriptor_state_needs_update,
+            current_sbt_regions: self.current_sbt_regions,
+            current_dynamic_states: self.current_dynamic_states,
+            image_states: self.image_states,
+            descriptor_cache: self.descriptor_cache,
+            pipeline_cache: self.pipeline_cache,
+            _domain: PhantomData,
+        })
+    }
 }
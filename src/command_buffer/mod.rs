@@ -4,7 +4,8 @@
 //! Vulkan command buffers need to call [`vkEndCommandBuffer`](https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkEndCommandBuffer.html) before they can be submitted.
 //! After this call, no more commands should be recorded to it. For this reason, we expose two command buffer types. The [`IncompleteCommandBuffer`] still accepts commands, and can only
 //! be converted into a [`CommandBuffer`] by calling [`IncompleteCommandBuffer::finish`](crate::command_buffer::IncompleteCommandBuffer::finish). This turns it into a complete command buffer, which can
-//! be submitted to the execution manager.
+//! be submitted to the execution manager. Secondary command buffers are finished into a [`SecondaryCommandBuffer`] instead, which
+//! can only be executed by a primary command buffer.
 //!
 //! # Commands
 //! All commands are implemented through traits for each [domain](crate::sync::domain). These are all defined inside the [`traits`] module, and are most easily imported
@@ -21,9 +22,9 @@ use ash::vk;
 
 use crate::{
     Allocator, CmdBuffer, DefaultAllocator, DescriptorCache, Device, Error,
-    ExecutionManager, PipelineCache,
+    ExecutionManager, PipelineCache, PipelineStage,
 };
-use crate::core::queue::Queue;
+use crate::core::queue::{Queue, QueueInfo};
 use crate::descriptor::builder::DescriptorSetBuilder;
 use crate::pipeline::create_info::PipelineRenderingInfo;
 use crate::sync::domain::ExecutionDomain;
@@ -31,11 +32,11 @@ use crate::sync::domain::ExecutionDomain;
 pub mod compute;
 pub mod graphics;
 pub mod incomplete;
+pub mod state;
 pub mod traits;
 pub mod transfer;
 
 pub(crate) mod command_pool;
-pub(crate) mod state;
 
 /// This struct represents a finished command buffer. This command buffer can't be recorded to anymore.
 /// It can only be obtained by calling [`IncompleteCommandBuffer::finish()`](crate::IncompleteCommandBuffer::finish).
@@ -57,6 +58,16 @@ pub struct CommandBuffer<D: ExecutionDomain> {
     _domain: PhantomData<D>,
 }
 
+/// A finished secondary command buffer. It cannot be submitted on its own, and can only be executed by a primary command buffer
+/// of the same domain with [`IncompleteCommandBuffer::execute_commands()`]. It is freed together with the
+/// [`LocalPool`](crate::pool::LocalPool) it was allocated from.
+/// It can only be obtained by calling [`IncompleteCommandBuffer::finish_secondary()`].
+#[derive(Debug)]
+pub struct SecondaryCommandBuffer<D: ExecutionDomain> {
+    handle: vk::CommandBuffer,
+    _domain: PhantomData<D>,
+}
+
 /// This struct represents an incomplete command buffer.
 /// This is a command buffer that has not been finished yet with [`IncompleteCommandBuffer::finish()`](crate::IncompleteCommandBuffer::finish).
 /// Calling this method will turn it into an immutable command buffer which can then be submitted
@@ -88,23 +99,116 @@ pub struct IncompleteCommandBuffer<'q, D: ExecutionDomain, A: Allocator = Defaul
     #[derivative(Debug = "ignore")]
     device: Device,
     handle: vk::CommandBuffer,
-    queue_lock: MutexGuard<'q, Queue>,
+    /// Lock on the queue this command buffer was allocated from. Secondary command buffers do not hold a lock,
+    /// so they can be recorded on other threads while the primary command buffer is being recorded.
+    queue_lock: Option<MutexGuard<'q, Queue>>,
+    /// Whether this is a secondary command buffer, which must be finished with [`IncompleteCommandBuffer::finish_secondary()`].
+    secondary: bool,
+    queue_info: QueueInfo,
     timestamp_valid_bits: u32,
     current_pipeline_layout: vk::PipelineLayout,
     current_set_layouts: Vec<vk::DescriptorSetLayout>,
     // TODO: Note: technically not correct
     current_bindpoint: vk::PipelineBindPoint,
     current_rendering_state: Option<PipelineRenderingInfo>,
+    current_rendering_samples: vk::SampleCountFlags,
     current_render_area: vk::Rect2D,
     current_descriptor_sets: Option<HashMap<u32, DescriptorSetBuilder<'static>>>,
     descriptor_state_needs_update: bool,
     current_sbt_regions: Option<[vk::StridedDeviceAddressRegionKHR; 4]>,
+    /// Name and dynamic states of the bound graphics pipeline.
+    current_dynamic_states: Option<(String, Vec<vk::DynamicState>)>,
+    /// Last known state of each image view used with [`IncompleteCommandBuffer::require_layout()`], keyed by image view id.
+    image_states: HashMap<u64, ImageState>,
     // TODO: Only update disturbed descriptor sets
     descriptor_cache: DescriptorCache,
     pipeline_cache: PipelineCache<A>,
     _domain: PhantomData<D>,
 }
 
+/// Layout of an image view, its last write and the reads since then.
+#[derive(Debug, Copy, Clone)]
+struct ImageState {
+    layout: vk::ImageLayout,
+    /// Stage and access of the last write. Layout transitions are tracked as a write in the destination stage of their barrier.
+    write_stage: PipelineStage,
+    write_access: vk::AccessFlags2,
+    /// Stages and accesses the last write was made visible to by earlier barriers.
+    visible_stage: PipelineStage,
+    visible_access: vk::AccessFlags2,
+    /// Reads since the last write, which a later write must wait for.
+    read_stage: PipelineStage,
+    read_access: vk::AccessFlags2,
+}
+
+impl ImageState {
+    /// State after a write in the given stage, which no barrier made visible yet.
+    fn written(layout: vk::ImageLayout, stage: PipelineStage, access: vk::AccessFlags2) -> Self {
+        Self {
+            layout,
+            write_stage: stage,
+            write_access: access,
+            visible_stage: PipelineStage::NONE,
+            visible_access: vk::AccessFlags2::NONE,
+            read_stage: PipelineStage::NONE,
+            read_access: vk::AccessFlags2::NONE,
+        }
+    }
+
+    /// State after a barrier that may have transitioned the layout, and made the image visible to a read in the given stage.
+    fn transitioned(
+        layout: vk::ImageLayout,
+        stage: PipelineStage,
+        access: vk::AccessFlags2,
+    ) -> Self {
+        Self {
+            layout,
+            write_stage: stage,
+            write_access: vk::AccessFlags2::NONE,
+            visible_stage: stage,
+            visible_access: access,
+            read_stage: stage,
+            read_access: access,
+        }
+    }
+}
+
+/// Describes the dynamic render pass a secondary command buffer is executed in. This must match the
+/// render pass that is active when the secondary command buffer is executed, and can be created from its
+/// [`RenderingInfo`](state::RenderingInfo) with `From`.
+/// See also [`ExecutionManager::on_domain_secondary()`].
+#[derive(Debug, Clone)]
+pub struct SecondaryRenderingInfo {
+    /// Rendering flags of the render pass, excluding [`vk::RenderingFlags::CONTENTS_SECONDARY_COMMAND_BUFFERS`].
+    pub flags: vk::RenderingFlags,
+    /// View mask of the render pass.
+    pub view_mask: u32,
+    /// Format of each color attachment.
+    pub color_formats: Vec<vk::Format>,
+    /// Format of the depth attachment, if there is one.
+    pub depth_format: Option<vk::Format>,
+    /// Format of the stencil attachment, if there is one.
+    pub stencil_format: Option<vk::Format>,
+    /// Sample count of all attachments.
+    pub samples: vk::SampleCountFlags,
+    /// Render area of the render pass. This is used by [`GraphicsCmdBuffer::full_viewport_scissor()`](crate::GraphicsCmdBuffer::full_viewport_scissor).
+    pub render_area: vk::Rect2D,
+}
+
+impl Default for SecondaryRenderingInfo {
+    fn default() -> Self {
+        Self {
+            flags: vk::RenderingFlags::empty(),
+            view_mask: 0,
+            color_formats: vec![],
+            depth_format: None,
+            stencil_format: None,
+            samples: vk::SampleCountFlags::TYPE_1,
+            render_area: vk::Rect2D::default(),
+        }
+    }
+}
+
 impl<D: ExecutionDomain, A: Allocator> CmdBuffer<A> for CommandBuffer<D> {
     /// Immediately delete a command buffer. Generally you do not need to call this manually, since
     /// commands buffers submitted through the [`ExecutionManager`] already do this cleanup.
@@ -127,3 +231,13 @@ impl<D: ExecutionDomain> CommandBuffer<D> {
         self.handle
     }
 }
+
+impl<D: ExecutionDomain> SecondaryCommandBuffer<D> {
+    /// Get unsafe access to the underlying command buffer
+    /// # Safety
+    /// Any vulkan calls that modify the command buffer state may lead to validation errors or put the
+    /// system in an undefined state.
+    pub unsafe fn handle(&self) -> vk::CommandBuffer {
+        self.handle
+    }
+}
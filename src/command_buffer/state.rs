@@ -1,26 +1,215 @@
-//! Command buffer rendering state
+//! Describes dynamic render passes, started with [`GraphicsCmdBuffer::begin_rendering()`](crate::GraphicsCmdBuffer::begin_rendering).
+//!
+//! # Example
+//! ```
+//! use phobos::prelude::*;
+//!
+//! let info = RenderingInfo::new(vk::Rect2D {
+//!     offset: vk::Offset2D { x: 0, y: 0 },
+//!     extent: vk::Extent2D { width: color.width(), height: color.height() },
+//! })
+//! .color_attachment(
+//!     RenderingAttachmentInfo::color(&color).clear_color(ClearColor::Float([0.0, 0.0, 0.0, 1.0])),
+//! )
+//! .depth_attachment(
+//!     RenderingAttachmentInfo::depth(&depth)
+//!         .clear_depth_stencil(ClearDepthStencil { depth: 1.0, stencil: 0 })
+//!         .store_op(vk::AttachmentStoreOp::DONT_CARE),
+//! );
+//! let cmd = cmd
+//!     .begin_rendering(&info)?
+//!     .bind_graphics_pipeline("pipeline")?
+//!     .full_viewport_scissor()
+//!     .draw(3, 1, 0, 0)?
+//!     .end_rendering()?;
+//! ```
 
 use ash::vk;
 
-use crate::ImageView;
-
-pub(crate) struct RenderingAttachmentInfo {
-    pub image_view: ImageView,
-    pub image_layout: vk::ImageLayout,
-    pub resolve_mode: Option<vk::ResolveModeFlags>,
-    pub resolve_image_view: Option<ImageView>,
-    pub resolve_image_layout: Option<vk::ImageLayout>,
-    pub load_op: vk::AttachmentLoadOp,
-    pub store_op: vk::AttachmentStoreOp,
-    pub clear_value: vk::ClearValue,
+use crate::{ClearColor, ClearDepthStencil, ImageView};
+use crate::command_buffer::SecondaryRenderingInfo;
+use crate::util::to_vk::IntoVulkanType;
+
+/// Describes a single attachment of a dynamic render pass. By default, the previous contents of the attachment
+/// are loaded, and the new contents are stored.
+#[derive(Derivative, Clone)]
+#[derivative(Debug)]
+pub struct RenderingAttachmentInfo {
+    pub(crate) image_view: ImageView,
+    pub(crate) image_layout: vk::ImageLayout,
+    pub(crate) resolve_mode: Option<vk::ResolveModeFlags>,
+    pub(crate) resolve_image_view: Option<ImageView>,
+    pub(crate) resolve_image_layout: Option<vk::ImageLayout>,
+    pub(crate) load_op: vk::AttachmentLoadOp,
+    pub(crate) store_op: vk::AttachmentStoreOp,
+    #[derivative(Debug = "ignore")]
+    pub(crate) clear_value: vk::ClearValue,
+}
+
+/// Describes a dynamic render pass. Build this with [`RenderingInfo::new()`] and the attachment methods.
+#[derive(Debug, Clone)]
+pub struct RenderingInfo {
+    pub(crate) flags: vk::RenderingFlags,
+    pub(crate) render_area: vk::Rect2D,
+    pub(crate) layer_count: u32,
+    pub(crate) view_mask: u32,
+    pub(crate) color_attachments: Vec<RenderingAttachmentInfo>,
+    pub(crate) depth_attachment: Option<RenderingAttachmentInfo>,
+    pub(crate) stencil_attachment: Option<RenderingAttachmentInfo>,
+}
+
+impl RenderingAttachmentInfo {
+    fn new(image_view: &ImageView, image_layout: vk::ImageLayout) -> Self {
+        Self {
+            image_view: image_view.clone(),
+            image_layout,
+            resolve_mode: None,
+            resolve_image_view: None,
+            resolve_image_layout: None,
+            load_op: vk::AttachmentLoadOp::LOAD,
+            store_op: vk::AttachmentStoreOp::STORE,
+            clear_value: vk::ClearValue::default(),
+        }
+    }
+
+    /// Create a color attachment in the `COLOR_ATTACHMENT_OPTIMAL` layout.
+    pub fn color(image_view: &ImageView) -> Self {
+        Self::new(image_view, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+    }
+
+    /// Create a depth attachment in the `DEPTH_ATTACHMENT_OPTIMAL` layout.
+    pub fn depth(image_view: &ImageView) -> Self {
+        Self::new(image_view, vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL)
+    }
+
+    /// Create a stencil attachment in the `STENCIL_ATTACHMENT_OPTIMAL` layout.
+    pub fn stencil(image_view: &ImageView) -> Self {
+        Self::new(image_view, vk::ImageLayout::STENCIL_ATTACHMENT_OPTIMAL)
+    }
+
+    /// Set the layout the attachment is in during the render pass.
+    pub fn layout(mut self, layout: vk::ImageLayout) -> Self {
+        self.image_layout = layout;
+        self
+    }
+
+    /// Set the load operation of the attachment. To clear the attachment, use [`Self::clear_color()`] or
+    /// [`Self::clear_depth_stencil()`] instead.
+    pub fn load_op(mut self, op: vk::AttachmentLoadOp) -> Self {
+        self.load_op = op;
+        self
+    }
+
+    /// Set the store operation of the attachment.
+    pub fn store_op(mut self, op: vk::AttachmentStoreOp) -> Self {
+        self.store_op = op;
+        self
+    }
+
+    /// Clear this color attachment at the start of the render pass.
+    pub fn clear_color(mut self, color: ClearColor) -> Self {
+        self.load_op = vk::AttachmentLoadOp::CLEAR;
+        self.clear_value = vk::ClearValue {
+            color: color.into_vulkan(),
+        };
+        self
+    }
+
+    /// Clear this depth or stencil attachment at the start of the render pass.
+    pub fn clear_depth_stencil(mut self, clear: ClearDepthStencil) -> Self {
+        self.load_op = vk::AttachmentLoadOp::CLEAR;
+        self.clear_value = vk::ClearValue {
+            depth_stencil: clear.into_vulkan(),
+        };
+        self
+    }
+
+    /// Resolve this multisampled attachment to `image_view` at the end of the render pass. Unless set with
+    /// [`Self::resolve_layout()`], the resolve image is expected to be in the same layout as the attachment.
+    pub fn resolve(mut self, mode: vk::ResolveModeFlags, image_view: &ImageView) -> Self {
+        self.resolve_mode = Some(mode);
+        self.resolve_image_view = Some(image_view.clone());
+        self
+    }
+
+    /// Set the layout the resolve image is in during the render pass.
+    pub fn resolve_layout(mut self, layout: vk::ImageLayout) -> Self {
+        self.resolve_image_layout = Some(layout);
+        self
+    }
+}
+
+impl RenderingInfo {
+    /// Start describing a render pass that renders to `render_area` of a single layer, without any attachments.
+    pub fn new(render_area: vk::Rect2D) -> Self {
+        Self {
+            flags: vk::RenderingFlags::empty(),
+            render_area,
+            layer_count: 1,
+            view_mask: 0,
+            color_attachments: vec![],
+            depth_attachment: None,
+            stencil_attachment: None,
+        }
+    }
+
+    /// Add a color attachment. Color attachments are bound in the order they are added.
+    pub fn color_attachment(mut self, attachment: RenderingAttachmentInfo) -> Self {
+        self.color_attachments.push(attachment);
+        self
+    }
+
+    /// Set the depth attachment.
+    pub fn depth_attachment(mut self, attachment: RenderingAttachmentInfo) -> Self {
+        self.depth_attachment = Some(attachment);
+        self
+    }
+
+    /// Set the stencil attachment.
+    pub fn stencil_attachment(mut self, attachment: RenderingAttachmentInfo) -> Self {
+        self.stencil_attachment = Some(attachment);
+        self
+    }
+
+    /// Set the rendering flags. To execute secondary command buffers inside the render pass, set
+    /// [`vk::RenderingFlags::CONTENTS_SECONDARY_COMMAND_BUFFERS`].
+    pub fn flags(mut self, flags: vk::RenderingFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Set the number of layers that are rendered to. This is ignored if a view mask is set.
+    pub fn layer_count(mut self, layer_count: u32) -> Self {
+        self.layer_count = layer_count;
+        self
+    }
+
+    /// Set the view mask for multiview rendering.
+    pub fn view_mask(mut self, view_mask: u32) -> Self {
+        self.view_mask = view_mask;
+        self
+    }
 }
 
-pub(crate) struct RenderingInfo {
-    pub flags: vk::RenderingFlags,
-    pub render_area: vk::Rect2D,
-    pub layer_count: u32,
-    pub view_mask: u32,
-    pub color_attachments: Vec<RenderingAttachmentInfo>,
-    pub depth_attachment: Option<RenderingAttachmentInfo>,
-    pub stencil_attachment: Option<RenderingAttachmentInfo>,
+impl From<&RenderingInfo> for SecondaryRenderingInfo {
+    fn from(info: &RenderingInfo) -> Self {
+        let format = |attachment: &RenderingAttachmentInfo| attachment.image_view.format();
+        Self {
+            flags: info.flags & !vk::RenderingFlags::CONTENTS_SECONDARY_COMMAND_BUFFERS,
+            view_mask: info.view_mask,
+            color_formats: info.color_attachments.iter().map(format).collect(),
+            depth_format: info.depth_attachment.as_ref().map(format),
+            stencil_format: info.stencil_attachment.as_ref().map(format),
+            // All attachments have the same sample count, so we only need to look at one of them.
+            samples: info
+                .color_attachments
+                .iter()
+                .chain(info.depth_attachment.iter())
+                .chain(info.stencil_attachment.iter())
+                .map(|attachment| attachment.image_view.samples())
+                .next()
+                .unwrap_or(vk::SampleCountFlags::TYPE_1),
+            render_area: info.render_area,
+        }
+    }
 }
@@ -1,5 +1,6 @@
 //! Defines the traits with command buffer operations
 
+use std::ops::Range;
 use std::sync::MutexGuard;
 
 use anyhow::Result;
@@ -9,6 +10,7 @@ use crate::{
     Allocator, BufferView, DescriptorCache, Device, ExecutionManager, ImageView, PipelineCache,
 };
 use crate::command_buffer::CommandBuffer;
+use crate::command_buffer::state::RenderingInfo;
 use crate::core::queue::Queue;
 use crate::query_pool::{AccelerationStructurePropertyQuery, QueryPool};
 use crate::raytracing::*;
@@ -20,16 +22,124 @@ pub trait TransferCmdBuffer {
     /// Copy one buffer view to another buffer view.
     /// Both views must have the same length.
     fn copy_buffer(self, src: &BufferView, dst: &BufferView) -> Result<Self>
+    where
+        Self: Sized;
+    /// Copy regions of one buffer view to another buffer view. Equivalent of `vkCmdCopyBuffer`.
+    fn copy_buffer_regions(
+        self,
+        src: &BufferView,
+        dst: &BufferView,
+        regions: &[vk::BufferCopy],
+    ) -> Result<Self>
     where
         Self: Sized;
     /// Copy a buffer to an image.
     fn copy_buffer_to_image(self, src: &BufferView, dst: &ImageView) -> Result<Self>
     where
         Self: Sized;
+    /// Copy regions of a buffer to an image. Equivalent of `vkCmdCopyBufferToImage`.
+    fn copy_buffer_to_image_regions(
+        self,
+        src: &BufferView,
+        dst: &ImageView,
+        regions: &[vk::BufferImageCopy],
+    ) -> Result<Self>
+    where
+        Self: Sized;
+    /// Copy one image view to another image view.
+    /// Both views must have the same size.
+    fn copy_image(self, src: &ImageView, dst: &ImageView) -> Result<Self>
+    where
+        Self: Sized;
+    /// Copy regions of one image to another image. Equivalent of `vkCmdCopyImage`.
+    fn copy_image_regions(
+        self,
+        src: &ImageView,
+        dst: &ImageView,
+        regions: &[vk::ImageCopy],
+    ) -> Result<Self>
+    where
+        Self: Sized;
+    /// Copy an image to a buffer.
+    fn copy_image_to_buffer(self, src: &ImageView, dst: &BufferView) -> Result<Self>
+    where
+        Self: Sized;
+    /// Copy regions of an image to a buffer. Equivalent of `vkCmdCopyImageToBuffer`.
+    fn copy_image_to_buffer_regions(
+        self,
+        src: &ImageView,
+        dst: &BufferView,
+        regions: &[vk::BufferImageCopy],
+    ) -> Result<Self>
+    where
+        Self: Sized;
+    /// Fill a buffer view with a repeated value.
+    fn fill_buffer(self, dst: &BufferView, data: u32) -> Result<Self>
+    where
+        Self: Sized;
+    /// Fill byte ranges of a buffer view with a repeated value. Equivalent of `vkCmdFillBuffer`.
+    fn fill_buffer_regions(
+        self,
+        dst: &BufferView,
+        regions: &[Range<vk::DeviceSize>],
+        data: u32,
+    ) -> Result<Self>
+    where
+        Self: Sized;
+    /// Write a small amount of data to a buffer view.
+    fn update_buffer<T: Copy>(self, dst: &BufferView, data: &[T]) -> Result<Self>
+    where
+        Self: Sized;
+    /// Write a small amount of data to regions of a buffer view. Equivalent of `vkCmdUpdateBuffer`.
+    fn update_buffer_regions<T: Copy>(
+        self,
+        dst: &BufferView,
+        regions: &[(vk::DeviceSize, &[T])],
+    ) -> Result<Self>
+    where
+        Self: Sized;
+    /// Clear an image view to a color.
+    fn clear_color_image(self, image: &ImageView, color: vk::ClearColorValue) -> Result<Self>
+    where
+        Self: Sized;
+    /// Clear subresource ranges of an image to a color. Equivalent of `vkCmdClearColorImage`.
+    fn clear_color_image_regions(
+        self,
+        image: &ImageView,
+        color: vk::ClearColorValue,
+        ranges: &[vk::ImageSubresourceRange],
+    ) -> Result<Self>
+    where
+        Self: Sized;
+    /// Clear a depth or stencil image view.
+    fn clear_depth_stencil_image(
+        self,
+        image: &ImageView,
+        value: vk::ClearDepthStencilValue,
+    ) -> Result<Self>
+    where
+        Self: Sized;
+    /// Clear subresource ranges of a depth or stencil image. Equivalent of `vkCmdClearDepthStencilImage`.
+    fn clear_depth_stencil_image_regions(
+        self,
+        image: &ImageView,
+        value: vk::ClearDepthStencilValue,
+        ranges: &[vk::ImageSubresourceRange],
+    ) -> Result<Self>
+    where
+        Self: Sized;
 }
 
 /// Trait representing a command buffer that supports graphics commands.
 pub trait GraphicsCmdBuffer: TransferCmdBuffer {
+    /// Begin a dynamic render pass. Equivalent of `vkCmdBeginRendering`.
+    fn begin_rendering(self, info: &RenderingInfo) -> Result<Self>
+    where
+        Self: Sized;
+    /// End the current dynamic render pass. Equivalent of `vkCmdEndRendering`.
+    fn end_rendering(self) -> Result<Self>
+    where
+        Self: Sized;
     /// Automatically set viewport and scissor region to the entire render area
     fn full_viewport_scissor(self) -> Self;
     /// Sets the viewport. The equivalent of `vkCmdSetViewport`.
@@ -55,6 +165,62 @@ pub trait GraphicsCmdBuffer: TransferCmdBuffer {
         vertex_offset: i32,
         first_instance: u32,
     ) -> Result<Self>
+    where
+        Self: Sized;
+    /// Record drawcalls with parameters read from a buffer. Equivalent of `vkCmdDrawIndirect`.
+    fn draw_indirect(self, buffer: &BufferView, draw_count: u32, stride: u32) -> Result<Self>
+    where
+        Self: Sized;
+    /// Record indexed drawcalls with parameters read from a buffer. Equivalent of `vkCmdDrawIndexedIndirect`.
+    fn draw_indexed_indirect(
+        self,
+        buffer: &BufferView,
+        draw_count: u32,
+        stride: u32,
+    ) -> Result<Self>
+    where
+        Self: Sized;
+    /// Record drawcalls with parameters and draw count read from buffers. Equivalent of `vkCmdDrawIndirectCount`.
+    fn draw_indirect_count(
+        self,
+        buffer: &BufferView,
+        count_buffer: &BufferView,
+        max_draw_count: u32,
+        stride: u32,
+    ) -> Result<Self>
+    where
+        Self: Sized;
+    /// Record indexed drawcalls with parameters and draw count read from buffers. Equivalent of `vkCmdDrawIndexedIndirectCount`.
+    fn draw_indexed_indirect_count(
+        self,
+        buffer: &BufferView,
+        count_buffer: &BufferView,
+        max_draw_count: u32,
+        stride: u32,
+    ) -> Result<Self>
+    where
+        Self: Sized;
+    /// Record a mesh shading drawcall. Equivalent of `vkCmdDrawMeshTasksEXT`.
+    fn draw_mesh_tasks(self, x: u32, y: u32, z: u32) -> Result<Self>
+    where
+        Self: Sized;
+    /// Record mesh shading drawcalls with parameters read from a buffer. Equivalent of `vkCmdDrawMeshTasksIndirectEXT`.
+    fn draw_mesh_tasks_indirect(
+        self,
+        buffer: &BufferView,
+        draw_count: u32,
+        stride: u32,
+    ) -> Result<Self>
+    where
+        Self: Sized;
+    /// Record mesh shading drawcalls with parameters and draw count read from buffers. Equivalent of `vkCmdDrawMeshTasksIndirectCountEXT`.
+    fn draw_mesh_tasks_indirect_count(
+        self,
+        buffer: &BufferView,
+        count_buffer: &BufferView,
+        max_draw_count: u32,
+        stride: u32,
+    ) -> Result<Self>
     where
         Self: Sized;
     /// Start raytracing. Equivalent of `vkCmdTraceRays`.
@@ -97,6 +263,73 @@ pub trait GraphicsCmdBuffer: TransferCmdBuffer {
     fn set_polygon_mode(self, mode: vk::PolygonMode) -> Result<Self>
     where
         Self: Sized;
+    /// Set the cull mode. Equivalent of `vkCmdSetCullMode`.
+    fn set_cull_mode(self, mode: vk::CullModeFlags) -> Result<Self>
+    where
+        Self: Sized;
+    /// Set the front face orientation. Equivalent of `vkCmdSetFrontFace`.
+    fn set_front_face(self, front_face: vk::FrontFace) -> Result<Self>
+    where
+        Self: Sized;
+    /// Set the primitive topology. Equivalent of `vkCmdSetPrimitiveTopology`.
+    fn set_primitive_topology(self, topology: vk::PrimitiveTopology) -> Result<Self>
+    where
+        Self: Sized;
+    /// Enable or disable the depth test. Equivalent of `vkCmdSetDepthTestEnable`.
+    fn set_depth_test_enable(self, enable: bool) -> Result<Self>
+    where
+        Self: Sized;
+    /// Enable or disable depth writes. Equivalent of `vkCmdSetDepthWriteEnable`.
+    fn set_depth_write_enable(self, enable: bool) -> Result<Self>
+    where
+        Self: Sized;
+    /// Set the depth compare operation. Equivalent of `vkCmdSetDepthCompareOp`.
+    fn set_depth_compare_op(self, op: vk::CompareOp) -> Result<Self>
+    where
+        Self: Sized;
+    /// Enable or disable the stencil test. Equivalent of `vkCmdSetStencilTestEnable`.
+    fn set_stencil_test_enable(self, enable: bool) -> Result<Self>
+    where
+        Self: Sized;
+    /// Set the stencil operations. Equivalent of `vkCmdSetStencilOp`.
+    fn set_stencil_op(
+        self,
+        faces: vk::StencilFaceFlags,
+        fail_op: vk::StencilOp,
+        pass_op: vk::StencilOp,
+        depth_fail_op: vk::StencilOp,
+        compare_op: vk::CompareOp,
+    ) -> Result<Self>
+    where
+        Self: Sized;
+    /// Enable or disable depth bias. Equivalent of `vkCmdSetDepthBiasEnable`.
+    fn set_depth_bias_enable(self, enable: bool) -> Result<Self>
+    where
+        Self: Sized;
+    /// Set the depth bias factors. Equivalent of `vkCmdSetDepthBias`.
+    fn set_depth_bias(self, constant_factor: f32, clamp: f32, slope_factor: f32) -> Result<Self>
+    where
+        Self: Sized;
+    /// Enable or disable blending per color attachment. Equivalent of `vkCmdSetColorBlendEnableEXT`.
+    fn set_color_blend_enable(self, first_attachment: u32, enable: &[bool]) -> Result<Self>
+    where
+        Self: Sized;
+    /// Set the blend equation per color attachment. Equivalent of `vkCmdSetColorBlendEquationEXT`.
+    fn set_color_blend_equation(
+        self,
+        first_attachment: u32,
+        equations: &[vk::ColorBlendEquationEXT],
+    ) -> Result<Self>
+    where
+        Self: Sized;
+    /// Set the color write mask per color attachment. Equivalent of `vkCmdSetColorWriteMaskEXT`.
+    fn set_color_write_mask(
+        self,
+        first_attachment: u32,
+        masks: &[vk::ColorComponentFlags],
+    ) -> Result<Self>
+    where
+        Self: Sized;
 }
 
 /// Trait representing a command buffer that supports compute commands.
@@ -113,6 +346,11 @@ pub trait ComputeCmdBuffer: TransferCmdBuffer {
     where
         Self: Sized;
 
+    /// Dispatch a compute invocation with the workgroup counts read from a buffer. See `vkCmdDispatchIndirect`
+    fn dispatch_indirect(self, buffer: &BufferView) -> Result<Self>
+    where
+        Self: Sized;
+
     /// Build an acceleration structure
     fn build_acceleration_structure(self, info: &AccelerationStructureBuildInfo) -> Result<Self>
     where
@@ -1,12 +1,150 @@
 //! Contains implementations of the transfer domain for command buffers
 
+use std::ops::Range;
+
 use anyhow::Result;
 use ash::vk;
 
 use crate::command_buffer::IncompleteCommandBuffer;
+use crate::resource::image::format_texel_block;
 use crate::sync::domain::ExecutionDomain;
 use crate::{Allocator, BufferView, Error, ImageView, TransferCmdBuffer, TransferSupport};
 
+/// Maximum amount of bytes that can be written with a single `vkCmdUpdateBuffer` call.
+const MAX_UPDATE_SIZE: usize = 65536;
+
+/// Translate a byte range relative to a buffer view to an offset into the underlying buffer.
+fn buffer_offset(
+    view: &BufferView,
+    offset: vk::DeviceSize,
+    size: vk::DeviceSize,
+) -> Option<vk::DeviceSize> {
+    let end = offset.checked_add(size)?;
+    (end <= view.size()).then(|| view.offset() + offset)
+}
+
+/// Get the amount of bytes of the buffer that a copy between a buffer and an image reads or writes, starting at the
+/// buffer offset of the region. `block` is the texel block of the image format, see [`format_texel_block()`].
+/// Returns `None` on overflow.
+fn buffer_image_footprint(
+    (block_size, block_width, block_height): (u64, u32, u32),
+    region: &vk::BufferImageCopy,
+) -> Option<vk::DeviceSize> {
+    let extent = region.image_extent;
+    if extent.width == 0 || extent.height == 0 || extent.depth == 0 {
+        return Some(0);
+    }
+    let row_length = match region.buffer_row_length {
+        0 => extent.width,
+        length => length,
+    };
+    let image_height = match region.buffer_image_height {
+        0 => extent.height,
+        height => height,
+    };
+    let blocks = |texels: u32, block: u32| texels.div_ceil(block) as u64;
+    // Array layers and depth slices are laid out the same way, and one of them is always 1.
+    let slices = (extent.depth as u64).checked_mul(region.image_subresource.layer_count as u64)?;
+    let rows = slices
+        .checked_sub(1)?
+        .checked_mul(blocks(image_height, block_height))?
+        .checked_add(blocks(extent.height, block_height) - 1)?;
+    rows.checked_mul(blocks(row_length, block_width))?
+        .checked_add(blocks(extent.width, block_width))?
+        .checked_mul(block_size)
+}
+
+/// Translate the buffer offset of a copy between a buffer and an image relative to a buffer view to an offset into the
+/// underlying buffer, making sure all texels of the copy fit in the buffer view.
+fn buffer_image_offset(
+    buffer: &BufferView,
+    image: &ImageView,
+    region: &vk::BufferImageCopy,
+) -> Result<vk::DeviceSize> {
+    let size = match format_texel_block(image.format(), region.image_subresource.aspect_mask) {
+        // Formats with an unknown block size can only be checked for their offset.
+        None => 0,
+        Some(block) => buffer_image_footprint(block, region).ok_or(Error::InvalidBufferCopy)?,
+    };
+    Ok(buffer_offset(buffer, region.buffer_offset, size).ok_or(Error::InvalidBufferCopy)?)
+}
+
+/// Get the subresource layers of the first mip level of an image view.
+fn view_layers(view: &ImageView) -> vk::ImageSubresourceLayers {
+    vk::ImageSubresourceLayers {
+        aspect_mask: view.aspect(),
+        mip_level: view.base_level(),
+        base_array_layer: view.base_layer(),
+        layer_count: view.layer_count(),
+    }
+}
+
+/// Translate subresource layers relative to an image view to layers of the underlying image.
+fn image_layers(
+    view: &ImageView,
+    layers: vk::ImageSubresourceLayers,
+) -> Result<vk::ImageSubresourceLayers> {
+    let layer_end = layers.base_array_layer.checked_add(layers.layer_count);
+    if layers.mip_level >= view.level_count()
+        || layers.layer_count == 0
+        || layer_end.filter(|end| *end <= view.layer_count()).is_none()
+    {
+        return Err(
+            Error::Uncategorized("Subresource range is out of bounds of the image view").into()
+        );
+    }
+    Ok(vk::ImageSubresourceLayers {
+        aspect_mask: layers.aspect_mask,
+        mip_level: view.base_level() + layers.mip_level,
+        base_array_layer: view.base_layer() + layers.base_array_layer,
+        layer_count: layers.layer_count,
+    })
+}
+
+/// Translate a subresource range relative to an image view to a range of the underlying image.
+fn image_range(
+    view: &ImageView,
+    range: vk::ImageSubresourceRange,
+) -> Result<vk::ImageSubresourceRange> {
+    let level_count = match range.level_count {
+        vk::REMAINING_MIP_LEVELS => view.level_count().saturating_sub(range.base_mip_level),
+        count => count,
+    };
+    let layer_count = match range.layer_count {
+        vk::REMAINING_ARRAY_LAYERS => view.layer_count().saturating_sub(range.base_array_layer),
+        count => count,
+    };
+    let level_end = range.base_mip_level.checked_add(level_count);
+    let layer_end = range.base_array_layer.checked_add(layer_count);
+    if level_count == 0
+        || layer_count == 0
+        || level_end.filter(|end| *end <= view.level_count()).is_none()
+        || layer_end.filter(|end| *end <= view.layer_count()).is_none()
+    {
+        return Err(
+            Error::Uncategorized("Subresource range is out of bounds of the image view").into()
+        );
+    }
+    Ok(vk::ImageSubresourceRange {
+        aspect_mask: range.aspect_mask,
+        base_mip_level: view.base_level() + range.base_mip_level,
+        level_count,
+        base_array_layer: view.base_layer() + range.base_array_layer,
+        layer_count,
+    })
+}
+
+impl<D: TransferSupport + ExecutionDomain, A: Allocator> IncompleteCommandBuffer<'_, D, A> {
+    /// Fail if the queue this command buffer was allocated from does not support any of the given operations.
+    fn require_queue_flags(&self, flags: vk::QueueFlags, message: &'static str) -> Result<()> {
+        if self.queue_info.flags.intersects(flags) {
+            Ok(())
+        } else {
+            Err(Error::Uncategorized(message).into())
+        }
+    }
+}
+
 impl<D: TransferSupport + ExecutionDomain, A: Allocator> TransferCmdBuffer
     for IncompleteCommandBuffer<'_, D, A>
 {
@@ -28,24 +166,60 @@ impl<D: TransferSupport + ExecutionDomain, A: Allocator> TransferCmdBuffer
         }
 
         let copy = vk::BufferCopy {
-            src_offset: src.offset(),
-            dst_offset: dst.offset(),
+            src_offset: 0,
+            dst_offset: 0,
             size: src.size(),
         };
+        self.copy_buffer_regions(src, dst, std::slice::from_ref(&copy))
+    }
+
+    /// Copy regions of one buffer to the other. The offsets of each region are relative to the start of the buffer views.
+    /// # Errors
+    /// * Fails if a region is out of bounds of one of the buffer views.
+    /// # Example
+    /// ```
+    /// # use anyhow::Result;
+    /// # use phobos::*;
+    /// # use phobos::sync::domain::*;
+    /// fn copy_second_half<C: TransferCmdBuffer>(cmd: C, src: &BufferView, dst: &BufferView) -> Result<C> {
+    ///     let half = src.size() / 2;
+    ///     cmd.copy_buffer_regions(src, dst, &[vk::BufferCopy {
+    ///         src_offset: half,
+    ///         dst_offset: 0,
+    ///         size: half,
+    ///     }])
+    /// }
+    /// ```
+    fn copy_buffer_regions(
+        self,
+        src: &BufferView,
+        dst: &BufferView,
+        regions: &[vk::BufferCopy],
+    ) -> Result<Self> {
+        let regions = regions
+            .iter()
+            .map(|region| {
+                Ok(vk::BufferCopy {
+                    src_offset: buffer_offset(src, region.src_offset, region.size)
+                        .ok_or(Error::InvalidBufferCopy)?,
+                    dst_offset: buffer_offset(dst, region.dst_offset, region.size)
+                        .ok_or(Error::InvalidBufferCopy)?,
+                    size: region.size,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
 
         unsafe {
-            self.device.cmd_copy_buffer(
-                self.handle,
-                src.handle(),
-                dst.handle(),
-                std::slice::from_ref(&copy),
-            );
+            self.device
+                .cmd_copy_buffer(self.handle, src.handle(), dst.handle(), &regions);
         }
 
         Ok(self)
     }
 
     /// Copy a buffer to the base mip level of the specified image.
+    /// # Errors
+    /// * Fails if the buffer view is too small to hold all texels of the image view.
     /// # Example
     /// ```
     /// # use anyhow::Result;
@@ -58,19 +232,15 @@ impl<D: TransferSupport + ExecutionDomain, A: Allocator> TransferCmdBuffer
     fn copy_buffer_to_image(self, src: &BufferView, dst: &ImageView) -> Result<Self>
     where
         Self: Sized, {
-        let copy = vk::BufferImageCopy {
-            buffer_offset: src.offset(),
+        let mut copy = vk::BufferImageCopy {
+            buffer_offset: 0,
             buffer_row_length: dst.width(),
             buffer_image_height: dst.height(),
-            image_subresource: vk::ImageSubresourceLayers {
-                aspect_mask: dst.aspect(),
-                mip_level: dst.base_level(),
-                base_array_layer: dst.base_layer(),
-                layer_count: dst.layer_count(),
-            },
+            image_subresource: view_layers(dst),
             image_offset: Default::default(),
             image_extent: dst.size(),
         };
+        copy.buffer_offset = buffer_image_offset(src, dst, &copy)?;
 
         unsafe {
             self.device.cmd_copy_buffer_to_image(
@@ -84,4 +254,485 @@ impl<D: TransferSupport + ExecutionDomain, A: Allocator> TransferCmdBuffer
 
         Ok(self)
     }
+
+    /// Copy regions of a buffer to an image in the `TRANSFER_DST_OPTIMAL` layout. The buffer offset of each region is relative
+    /// to the start of the buffer view, and the image subresource is relative to the image view.
+    /// # Errors
+    /// * Fails if the texels of a region do not fit in the buffer view, starting at its buffer offset.
+    /// * Fails if an image subresource is out of bounds of the image view.
+    /// # Example
+    /// ```
+    /// # use anyhow::Result;
+    /// # use phobos::*;
+    /// # use phobos::sync::domain::*;
+    /// // Upload a 64x64 tile to an atlas.
+    /// fn upload_tile<C: TransferCmdBuffer>(cmd: C, src: &BufferView, atlas: &ImageView, x: i32, y: i32) -> Result<C> {
+    ///     cmd.copy_buffer_to_image_regions(src, atlas, &[vk::BufferImageCopy {
+    ///         buffer_offset: 0,
+    ///         buffer_row_length: 0,
+    ///         buffer_image_height: 0,
+    ///         image_subresource: vk::ImageSubresourceLayers {
+    ///             aspect_mask: vk::ImageAspectFlags::COLOR,
+    ///             mip_level: 0,
+    ///             base_array_layer: 0,
+    ///             layer_count: 1,
+    ///         },
+    ///         image_offset: vk::Offset3D { x, y, z: 0 },
+    ///         image_extent: vk::Extent3D { width: 64, height: 64, depth: 1 },
+    ///     }])
+    /// }
+    /// ```
+    fn copy_buffer_to_image_regions(
+        self,
+        src: &BufferView,
+        dst: &ImageView,
+        regions: &[vk::BufferImageCopy],
+    ) -> Result<Self> {
+        let regions = regions
+            .iter()
+            .map(|region| {
+                Ok(vk::BufferImageCopy {
+                    buffer_offset: buffer_image_offset(src, dst, region)?,
+                    image_subresource: image_layers(dst, region.image_subresource)?,
+                    ..*region
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        unsafe {
+            self.device.cmd_copy_buffer_to_image(
+                self.handle,
+                src.handle(),
+                dst.image(),
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &regions,
+            );
+        }
+
+        Ok(self)
+    }
+
+    /// Copy the base mip level of an image in the `TRANSFER_SRC_OPTIMAL` layout to the base mip level of
+    /// an image in the `TRANSFER_DST_OPTIMAL` layout.
+    /// # Errors
+    /// * Fails if the image views do not have the same size and layer count.
+    /// # Example
+    /// ```
+    /// # use anyhow::Result;
+    /// # use phobos::*;
+    /// # use phobos::sync::domain::*;
+    /// fn copy_image<C: TransferCmdBuffer>(cmd: C, src: &ImageView, dst: &ImageView) -> Result<C> {
+    ///     cmd.copy_image(src, dst)
+    /// }
+    /// ```
+    fn copy_image(self, src: &ImageView, dst: &ImageView) -> Result<Self> {
+        if src.size() != dst.size() || src.layer_count() != dst.layer_count() {
+            return Err(Error::InvalidImageCopy.into());
+        }
+
+        let copy = vk::ImageCopy {
+            src_subresource: view_layers(src),
+            src_offset: Default::default(),
+            dst_subresource: view_layers(dst),
+            dst_offset: Default::default(),
+            extent: src.size(),
+        };
+
+        unsafe {
+            self.device.cmd_copy_image(
+                self.handle,
+                src.image(),
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                dst.image(),
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                std::slice::from_ref(&copy),
+            );
+        }
+
+        Ok(self)
+    }
+
+    /// Copy regions of an image in the `TRANSFER_SRC_OPTIMAL` layout to an image in the `TRANSFER_DST_OPTIMAL` layout.
+    /// The subresources of each region are relative to the image views.
+    /// # Errors
+    /// * Fails if a subresource is out of bounds of its image view.
+    /// # Example
+    /// ```
+    /// # use anyhow::Result;
+    /// # use phobos::*;
+    /// # use phobos::sync::domain::*;
+    /// fn copy_mip<C: TransferCmdBuffer>(cmd: C, src: &ImageView, dst: &ImageView, level: u32) -> Result<C> {
+    ///     let layers = vk::ImageSubresourceLayers {
+    ///         aspect_mask: vk::ImageAspectFlags::COLOR,
+    ///         mip_level: level,
+    ///         base_array_layer: 0,
+    ///         layer_count: 1,
+    ///     };
+    ///     cmd.copy_image_regions(src, dst, &[vk::ImageCopy {
+    ///         src_subresource: layers,
+    ///         src_offset: Default::default(),
+    ///         dst_subresource: layers,
+    ///         dst_offset: Default::default(),
+    ///         extent: vk::Extent3D {
+    ///             width: (src.width() >> level).max(1),
+    ///             height: (src.height() >> level).max(1),
+    ///             depth: 1,
+    ///         },
+    ///     }])
+    /// }
+    /// ```
+    fn copy_image_regions(
+        self,
+        src: &ImageView,
+        dst: &ImageView,
+        regions: &[vk::ImageCopy],
+    ) -> Result<Self> {
+        let regions = regions
+            .iter()
+            .map(|region| {
+                Ok(vk::ImageCopy {
+                    src_subresource: image_layers(src, region.src_subresource)?,
+                    dst_subresource: image_layers(dst, region.dst_subresource)?,
+                    ..*region
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        unsafe {
+            self.device.cmd_copy_image(
+                self.handle,
+                src.image(),
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                dst.image(),
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &regions,
+            );
+        }
+
+        Ok(self)
+    }
+
+    /// Copy the base mip level of an image in the `TRANSFER_SRC_OPTIMAL` layout to a buffer. The texels are tightly packed in the buffer.
+    /// # Errors
+    /// * Fails if the buffer view is too small to hold all texels of the image view.
+    /// # Example
+    /// ```
+    /// # use anyhow::Result;
+    /// # use phobos::*;
+    /// # use phobos::sync::domain::*;
+    /// fn readback<C: TransferCmdBuffer>(cmd: C, src: &ImageView, dst: &BufferView) -> Result<C> {
+    ///     cmd.copy_image_to_buffer(src, dst)
+    /// }
+    /// ```
+    fn copy_image_to_buffer(self, src: &ImageView, dst: &BufferView) -> Result<Self> {
+        let mut copy = vk::BufferImageCopy {
+            buffer_offset: 0,
+            buffer_row_length: src.width(),
+            buffer_image_height: src.height(),
+            image_subresource: view_layers(src),
+            image_offset: Default::default(),
+            image_extent: src.size(),
+        };
+        copy.buffer_offset = buffer_image_offset(dst, src, &copy)?;
+
+        unsafe {
+            self.device.cmd_copy_image_to_buffer(
+                self.handle,
+                src.image(),
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                dst.handle(),
+                std::slice::from_ref(&copy),
+            );
+        }
+
+        Ok(self)
+    }
+
+    /// Copy regions of an image in the `TRANSFER_SRC_OPTIMAL` layout to a buffer. The buffer offset of each region is relative
+    /// to the start of the buffer view, and the image subresource is relative to the image view.
+    /// # Errors
+    /// * Fails if the texels of a region do not fit in the buffer view, starting at its buffer offset.
+    /// * Fails if an image subresource is out of bounds of the image view.
+    /// # Example
+    /// ```
+    /// # use anyhow::Result;
+    /// # use phobos::*;
+    /// # use phobos::sync::domain::*;
+    /// // Read back a single pixel.
+    /// fn read_pixel<C: TransferCmdBuffer>(cmd: C, src: &ImageView, dst: &BufferView, x: i32, y: i32) -> Result<C> {
+    ///     cmd.copy_image_to_buffer_regions(src, dst, &[vk::BufferImageCopy {
+    ///         buffer_offset: 0,
+    ///         buffer_row_length: 0,
+    ///         buffer_image_height: 0,
+    ///         image_subresource: vk::ImageSubresourceLayers {
+    ///             aspect_mask: vk::ImageAspectFlags::COLOR,
+    ///             mip_level: 0,
+    ///             base_array_layer: 0,
+    ///             layer_count: 1,
+    ///         },
+    ///         image_offset: vk::Offset3D { x, y, z: 0 },
+    ///         image_extent: vk::Extent3D { width: 1, height: 1, depth: 1 },
+    ///     }])
+    /// }
+    /// ```
+    fn copy_image_to_buffer_regions(
+        self,
+        src: &ImageView,
+        dst: &BufferView,
+        regions: &[vk::BufferImageCopy],
+    ) -> Result<Self> {
+        let regions = regions
+            .iter()
+            .map(|region| {
+                Ok(vk::BufferImageCopy {
+                    buffer_offset: buffer_image_offset(dst, src, region)?,
+                    image_subresource: image_layers(src, region.image_subresource)?,
+                    ..*region
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        unsafe {
+            self.device.cmd_copy_image_to_buffer(
+                self.handle,
+                src.image(),
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                dst.handle(),
+                &regions,
+            );
+        }
+
+        Ok(self)
+    }
+
+    /// Fill a buffer with a repeated 32-bit value.
+    /// # Errors
+    /// * Fails if the offset or size of the buffer view is not a multiple of 4.
+    /// # Example
+    /// ```
+    /// # use anyhow::Result;
+    /// # use phobos::*;
+    /// # use phobos::sync::domain::*;
+    /// fn zero_buffer<C: TransferCmdBuffer>(cmd: C, buffer: &BufferView) -> Result<C> {
+    ///     cmd.fill_buffer(buffer, 0)
+    /// }
+    /// ```
+    fn fill_buffer(self, dst: &BufferView, data: u32) -> Result<Self> {
+        self.fill_buffer_regions(dst, std::slice::from_ref(&(0..dst.size())), data)
+    }
+
+    /// Fill byte ranges of a buffer with a repeated 32-bit value. The ranges are relative to the start of the buffer view.
+    /// # Errors
+    /// * Fails if a range is empty or out of bounds of the buffer view.
+    /// * Fails if the start or size of a range is not a multiple of 4.
+    /// # Example
+    /// ```
+    /// # use anyhow::Result;
+    /// # use phobos::*;
+    /// # use phobos::sync::domain::*;
+    /// fn reset_counters<C: TransferCmdBuffer>(cmd: C, buffer: &BufferView) -> Result<C> {
+    ///     cmd.fill_buffer_regions(buffer, &[0..4, 256..260], 0)
+    /// }
+    /// ```
+    fn fill_buffer_regions(
+        self,
+        dst: &BufferView,
+        regions: &[Range<vk::DeviceSize>],
+        data: u32,
+    ) -> Result<Self> {
+        for region in regions {
+            if region.start >= region.end {
+                return Err(Error::Uncategorized("Buffer fill range must not be empty").into());
+            }
+            let size = region.end - region.start;
+            let offset = buffer_offset(dst, region.start, size).ok_or(Error::InvalidBufferCopy)?;
+            if offset % 4 != 0 || size % 4 != 0 {
+                return Err(
+                    Error::Uncategorized("Buffer fill range must be aligned to 4 bytes").into()
+                );
+            }
+            unsafe {
+                self.device
+                    .cmd_fill_buffer(self.handle, dst.handle(), offset, size, data);
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Write data to the start of a buffer. The data is stored inside the command buffer, so this is only meant for small updates.
+    /// # Errors
+    /// * Fails if the data is empty, or does not fit in the buffer view.
+    /// * Fails if the offset of the buffer view or the size of the data is not a multiple of 4.
+    /// * Fails if the data is larger than 65536 bytes.
+    /// # Example
+    /// ```
+    /// # use anyhow::Result;
+    /// # use phobos::*;
+    /// # use phobos::sync::domain::*;
+    /// fn update_params<C: TransferCmdBuffer>(cmd: C, buffer: &BufferView) -> Result<C> {
+    ///     cmd.update_buffer(buffer, &[1.0f32, 0.5, 0.25, 0.0])
+    /// }
+    /// ```
+    fn update_buffer<T: Copy>(self, dst: &BufferView, data: &[T]) -> Result<Self> {
+        self.update_buffer_regions(dst, &[(0, data)])
+    }
+
+    /// Write data to regions of a buffer. Each region is given as an offset relative to the start of the buffer view,
+    /// and the data to write there.
+    /// # Errors
+    /// * Fails if the data of a region is empty, or does not fit in the buffer view.
+    /// * Fails if the offset of a region or the size of its data is not a multiple of 4.
+    /// * Fails if the data of a region is larger than 65536 bytes.
+    /// # Example
+    /// ```
+    /// # use anyhow::Result;
+    /// # use phobos::*;
+    /// # use phobos::sync::domain::*;
+    /// fn update_indices<C: TransferCmdBuffer>(cmd: C, buffer: &BufferView) -> Result<C> {
+    ///     cmd.update_buffer_regions(buffer, &[(0, &[0u32, 1, 2]), (64, &[2u32, 1, 3])])
+    /// }
+    /// ```
+    fn update_buffer_regions<T: Copy>(
+        self,
+        dst: &BufferView,
+        regions: &[(vk::DeviceSize, &[T])],
+    ) -> Result<Self> {
+        for (offset, data) in regions {
+            let size = std::mem::size_of_val(*data);
+            if size == 0 {
+                return Err(Error::Uncategorized("Buffer update data must not be empty").into());
+            }
+            let offset = buffer_offset(dst, *offset, size as vk::DeviceSize)
+                .ok_or(Error::InvalidBufferCopy)?;
+            if offset % 4 != 0 || size % 4 != 0 || size > MAX_UPDATE_SIZE {
+                return Err(Error::Uncategorized(
+                    "Buffer update must be aligned to 4 bytes and at most 65536 bytes large",
+                )
+                .into());
+            }
+            // SAFETY: T is Copy, so it is valid to view the data as plain bytes.
+            let bytes = unsafe { std::slice::from_raw_parts(data.as_ptr() as *const u8, size) };
+            unsafe {
+                self.device
+                    .cmd_update_buffer(self.handle, dst.handle(), offset, bytes);
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Clear all subresources of an image view in the `TRANSFER_DST_OPTIMAL` layout to a color.
+    /// # Errors
+    /// * Fails if the queue of this command buffer does not support graphics or compute operations.
+    /// # Example
+    /// ```
+    /// # use anyhow::Result;
+    /// # use phobos::*;
+    /// # use phobos::sync::domain::*;
+    /// fn clear_to_black<C: TransferCmdBuffer>(cmd: C, image: &ImageView) -> Result<C> {
+    ///     cmd.clear_color_image(image, vk::ClearColorValue { float32: [0.0, 0.0, 0.0, 1.0] })
+    /// }
+    /// ```
+    fn clear_color_image(self, image: &ImageView, color: vk::ClearColorValue) -> Result<Self> {
+        let range = vk::ImageSubresourceRange {
+            aspect_mask: image.aspect(),
+            base_mip_level: 0,
+            level_count: vk::REMAINING_MIP_LEVELS,
+            base_array_layer: 0,
+            layer_count: vk::REMAINING_ARRAY_LAYERS,
+        };
+        self.clear_color_image_regions(image, color, std::slice::from_ref(&range))
+    }
+
+    /// Clear subresource ranges of an image in the `TRANSFER_DST_OPTIMAL` layout to a color. The ranges are relative to the image view.
+    /// # Errors
+    /// * Fails if the queue of this command buffer does not support graphics or compute operations.
+    /// * Fails if a range is out of bounds of the image view.
+    fn clear_color_image_regions(
+        self,
+        image: &ImageView,
+        color: vk::ClearColorValue,
+        ranges: &[vk::ImageSubresourceRange],
+    ) -> Result<Self> {
+        self.require_queue_flags(
+            vk::QueueFlags::GRAPHICS | vk::QueueFlags::COMPUTE,
+            "Clearing a color image requires a graphics or compute queue",
+        )?;
+        let ranges = ranges
+            .iter()
+            .map(|range| image_range(image, *range))
+            .collect::<Result<Vec<_>>>()?;
+
+        unsafe {
+            self.device.cmd_clear_color_image(
+                self.handle,
+                image.image(),
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &color,
+                &ranges,
+            );
+        }
+
+        Ok(self)
+    }
+
+    /// Clear all subresources of a depth or stencil image view in the `TRANSFER_DST_OPTIMAL` layout.
+    /// # Errors
+    /// * Fails if the queue of this command buffer does not support graphics operations.
+    /// # Example
+    /// ```
+    /// # use anyhow::Result;
+    /// # use phobos::*;
+    /// # use phobos::sync::domain::*;
+    /// fn reset_depth<C: TransferCmdBuffer>(cmd: C, depth: &ImageView) -> Result<C> {
+    ///     cmd.clear_depth_stencil_image(depth, vk::ClearDepthStencilValue { depth: 1.0, stencil: 0 })
+    /// }
+    /// ```
+    fn clear_depth_stencil_image(
+        self,
+        image: &ImageView,
+        value: vk::ClearDepthStencilValue,
+    ) -> Result<Self> {
+        let range = vk::ImageSubresourceRange {
+            aspect_mask: image.aspect(),
+            base_mip_level: 0,
+            level_count: vk::REMAINING_MIP_LEVELS,
+            base_array_layer: 0,
+            layer_count: vk::REMAINING_ARRAY_LAYERS,
+        };
+        self.clear_depth_stencil_image_regions(image, value, std::slice::from_ref(&range))
+    }
+
+    /// Clear subresource ranges of a depth or stencil image in the `TRANSFER_DST_OPTIMAL` layout. The ranges are relative to the image view.
+    /// # Errors
+    /// * Fails if the queue of this command buffer does not support graphics operations.
+    /// * Fails if a range is out of bounds of the image view.
+    fn clear_depth_stencil_image_regions(
+        self,
+        image: &ImageView,
+        value: vk::ClearDepthStencilValue,
+        ranges: &[vk::ImageSubresourceRange],
+    ) -> Result<Self> {
+        self.require_queue_flags(
+            vk::QueueFlags::GRAPHICS,
+            "Clearing a depth stencil image requires a graphics queue",
+        )?;
+        let ranges = ranges
+            .iter()
+            .map(|range| image_range(image, *range))
+            .collect::<Result<Vec<_>>>()?;
+
+        unsafe {
+            self.device.cmd_clear_depth_stencil_image(
+                self.handle,
+                image.image(),
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &value,
+                &ranges,
+            );
+        }
+
+        Ok(self)
+    }
 }
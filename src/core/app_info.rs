@@ -140,6 +140,8 @@ pub struct AppSettings<'a, Window: WindowInterface> {
     pub scratch_buffer_size: u64,
     /// Whether to enable raytracing extensions.
     pub raytracing: bool,
+    /// Whether to enable mesh and task shaders.
+    pub mesh_shading: bool,
     /// FSR2 context settings.
     #[cfg(feature = "fsr2")]
     pub fsr2_settings: Fsr2Settings,
@@ -160,6 +162,7 @@ impl<'a, Window: WindowInterface> Default for AppSettings<'a, Window> {
             gpu_requirements: GPURequirements::default(),
             scratch_buffer_size: 1,
             raytracing: false,
+            mesh_shading: false,
             #[cfg(feature = "fsr2")]
             fsr2_settings: Fsr2Settings::default(),
         }
@@ -262,6 +265,13 @@ impl<'a, Window: WindowInterface> AppBuilder<'a, Window> {
         self
     }
 
+    /// Enable mesh and task shaders.
+    /// Will try to enable `VK_EXT_mesh_shader` if it is available.
+    pub fn mesh_shading(mut self, enabled: bool) -> Self {
+        self.inner.mesh_shading = enabled;
+        self
+    }
+
     /// Set the initial FSR2 display size
     #[cfg(feature = "fsr2")]
     pub fn fsr2_display_size(mut self, width: u32, height: u32) -> Self {
@@ -40,6 +40,8 @@ pub enum ExtensionID {
     AccelerationStructure,
     /// `VK_KHR_ray_tracing_pipeline` provides raytracing pipelines and ray query objects in shaders.
     RayTracingPipeline,
+    /// `VK_EXT_mesh_shader` provides mesh and task shaders.
+    MeshShader,
 }
 
 impl std::fmt::Display for ExtensionID {
@@ -62,10 +64,14 @@ struct DeviceInner {
     extensions: HashSet<ExtensionID>,
     #[derivative(Debug = "ignore")]
     dynamic_state3: Option<ext::ExtendedDynamicState3>,
+    /// Dynamic states from `VK_EXT_extended_dynamic_state3` that are supported and enabled.
+    dynamic_states3: HashSet<vk::DynamicState>,
     #[derivative(Debug = "ignore")]
     acceleration_structure: Option<khr::AccelerationStructure>,
     #[derivative(Debug = "ignore")]
     rt_pipeline: Option<khr::RayTracingPipeline>,
+    #[derivative(Debug = "ignore")]
+    mesh_shader: Option<ext::MeshShader>,
 }
 
 /// Wrapper around a `VkDevice`. The device provides access to almost the entire
@@ -183,6 +189,18 @@ impl Device {
             false
         };
 
+        let mesh_shader_supported = if settings.mesh_shading {
+            add_if_supported(
+                ExtensionID::MeshShader,
+                ext::MeshShader::name(),
+                &mut enabled_extensions,
+                &mut extension_names,
+                available_extensions.as_slice(),
+            )
+        } else {
+            false
+        };
+
         let ray_query_name = CStr::from_bytes_with_nul(b"VK_KHR_ray_query\0")?;
         let ray_query_supported = settings.raytracing
             && available_extensions.iter().any(|ext| {
@@ -233,10 +251,50 @@ impl Device {
             .push_next(&mut features_1_2)
             .push_next(&mut features_1_3);
 
+        // Dynamic states from VK_EXT_extended_dynamic_state3 are separate features, so only enable those that are supported.
+        let mut supported_dynamic_state3 =
+            vk::PhysicalDeviceExtendedDynamicState3FeaturesEXT::default();
+        if dynamic_state3_supported {
+            let mut features2 =
+                vk::PhysicalDeviceFeatures2::builder().push_next(&mut supported_dynamic_state3);
+            // SAFETY: Vulkan API call. We have a valid reference to a PhysicalDevice, so handle() is valid.
+            unsafe {
+                instance.get_physical_device_features2(physical_device.handle(), &mut features2);
+            }
+        }
         let mut features_dynamic_state3 = vk::PhysicalDeviceExtendedDynamicState3FeaturesEXT {
-            extended_dynamic_state3_polygon_mode: vk::TRUE,
+            extended_dynamic_state3_polygon_mode: supported_dynamic_state3
+                .extended_dynamic_state3_polygon_mode,
+            extended_dynamic_state3_color_blend_enable: supported_dynamic_state3
+                .extended_dynamic_state3_color_blend_enable,
+            extended_dynamic_state3_color_blend_equation: supported_dynamic_state3
+                .extended_dynamic_state3_color_blend_equation,
+            extended_dynamic_state3_color_write_mask: supported_dynamic_state3
+                .extended_dynamic_state3_color_write_mask,
             ..Default::default()
         };
+        let dynamic_states3 = [
+            (
+                vk::DynamicState::POLYGON_MODE_EXT,
+                features_dynamic_state3.extended_dynamic_state3_polygon_mode,
+            ),
+            (
+                vk::DynamicState::COLOR_BLEND_ENABLE_EXT,
+                features_dynamic_state3.extended_dynamic_state3_color_blend_enable,
+            ),
+            (
+                vk::DynamicState::COLOR_BLEND_EQUATION_EXT,
+                features_dynamic_state3.extended_dynamic_state3_color_blend_equation,
+            ),
+            (
+                vk::DynamicState::COLOR_WRITE_MASK_EXT,
+                features_dynamic_state3.extended_dynamic_state3_color_write_mask,
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, supported)| *supported == vk::TRUE)
+        .map(|(state, _)| state)
+        .collect::<HashSet<_>>();
         if dynamic_state3_supported {
             info = info.push_next(&mut features_dynamic_state3);
         }
@@ -280,6 +338,16 @@ impl Device {
             info = info.push_next(&mut features_ray_tracing_pipeline);
         }
 
+        let mut features_mesh_shader = vk::PhysicalDeviceMeshShaderFeaturesEXT {
+            task_shader: vk::TRUE,
+            mesh_shader: vk::TRUE,
+            ..Default::default()
+        };
+
+        if mesh_shader_supported {
+            info = info.push_next(&mut features_mesh_shader);
+        }
+
         let info = info.build();
 
         let handle = unsafe { instance.create_device(physical_device.handle(), &info, None)? };
@@ -304,6 +372,12 @@ impl Device {
             None
         };
 
+        let mesh_shader = if mesh_shader_supported {
+            Some(ext::MeshShader::new(instance, &handle))
+        } else {
+            None
+        };
+
         let mut properties2 = vk::PhysicalDeviceProperties2::builder();
 
         let mut accel_properties = if accel_supported {
@@ -373,8 +447,10 @@ impl Device {
             rt_properties,
             extensions: enabled_extensions,
             dynamic_state3,
+            dynamic_states3,
             acceleration_structure,
             rt_pipeline,
+            mesh_shader,
             #[cfg(feature = "fsr2")]
             fsr2_context: Mutex::new(fsr2),
         };
@@ -489,6 +565,28 @@ impl Device {
         }
     }
 
+    /// Returns `Ok` if the given dynamic state can be used on this device, `Err` otherwise.
+    /// Dynamic states from `VK_EXT_extended_dynamic_state3` require the extension, and must also be supported by the device.
+    /// All other dynamic states phobos exposes are part of core Vulkan 1.3.
+    /// # Errors
+    /// * Fails if the extension providing the dynamic state is not enabled.
+    /// * Fails if the device does not support the dynamic state.
+    pub fn require_dynamic_state(&self, state: vk::DynamicState) -> Result<()> {
+        match state {
+            vk::DynamicState::POLYGON_MODE_EXT
+            | vk::DynamicState::COLOR_BLEND_ENABLE_EXT
+            | vk::DynamicState::COLOR_BLEND_EQUATION_EXT
+            | vk::DynamicState::COLOR_WRITE_MASK_EXT => {
+                self.require_extension(ExtensionID::ExtendedDynamicState3)?;
+                if !self.inner.dynamic_states3.contains(&state) {
+                    return Err(Error::DynamicStateNotSupported(state).into());
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Access to the function pointers for `VK_EXT_dynamic_state_3`
     /// Access to the function pointers for `VK_EXT_dynamic_state_3`
     /// Returns `None` if the extension was not enabled or not available.
@@ -529,6 +627,13 @@ impl Device {
         self.inner.rt_pipeline.as_ref()
     }
 
+    /// Access to the function pointers for `VK_EXT_mesh_shader`
+    ///
+    /// Returns `None` if the extension is not enabled
+    pub fn mesh_shader(&self) -> Option<&ext::MeshShader> {
+        self.inner.mesh_shader.as_ref()
+    }
+
     /// True we only have a single queue, and thus the sharing mode for resources is always `VK_SHARING_MODE_EXCLUSIVE`.
     /// Not extremely useful on the user side, but maybe you want to know whether one physical queue is being multiplexed
     /// behind your back.
@@ -4,10 +4,12 @@ use std::ffi::NulError;
 use std::sync::PoisonError;
 
 use ash;
+use ash::vk;
 use gpu_allocator::AllocationError;
 use thiserror::Error;
 
 use crate::core::device::ExtensionID;
+use crate::graph::resource::ResourceType;
 
 /// Error type that phobos can return.
 #[derive(Error, Debug)]
@@ -54,6 +56,90 @@ pub enum Error {
     /// No resource was bound to a virtual resource
     #[error("No resource bound to virtual resource `{0}`")]
     NoResourceBound(String),
+    /// A pass in a pass graph uses a virtual resource that has no physical resource bound to it.
+    #[error("Pass `{pass}` uses resource `{resource}`, but no physical resource is bound to it.")]
+    MissingBinding {
+        /// Name of the pass using the resource.
+        pass: String,
+        /// Uid of the virtual resource.
+        resource: String,
+    },
+    /// A pass in a pass graph uses a virtual resource that is bound to a different type of physical resource.
+    #[error("Pass `{pass}` uses resource `{resource}` as {expected:?} resource, but a different type of resource is bound to it.")]
+    BindingTypeMismatch {
+        /// Name of the pass using the resource.
+        pass: String,
+        /// Uid of the virtual resource.
+        resource: String,
+        /// The type of the virtual resource.
+        expected: ResourceType,
+    },
+    /// The format of an image bound to an attachment cannot be used the way the attachment is declared.
+    #[error(
+        "Pass `{pass}` uses resource `{resource}` with format {format:?} as {usage} attachment."
+    )]
+    AttachmentFormatMismatch {
+        /// Name of the pass using the resource.
+        pass: String,
+        /// Uid of the virtual resource.
+        resource: String,
+        /// Format of the bound image.
+        format: vk::Format,
+        /// How the attachment is used, for example `color` or `depth`.
+        usage: &'static str,
+    },
+    /// An image bound to an attachment has a different sample count than the other attachments of its pass.
+    #[error("Pass `{pass}` expects resource `{resource}` to have sample count {expected:?}, but it has sample count {found:?}.")]
+    SampleCountMismatch {
+        /// Name of the pass using the resource.
+        pass: String,
+        /// Uid of the virtual resource.
+        resource: String,
+        /// Sample count required by the pass.
+        expected: vk::SampleCountFlags,
+        /// Sample count of the bound image.
+        found: vk::SampleCountFlags,
+    },
+    /// A sub-graph with the same name was already added to the graph.
+    #[error("A sub-graph named `{0}` was already added to this graph.")]
+    DuplicateSubGraph(String),
+    /// A sub-graph uses a resource that was neither created through the sub-graph nor imported into it.
+    #[error("Sub-graph `{subgraph}` uses resource `{resource}`, which was neither created through it nor imported into it.")]
+    UnprefixedSubGraphResource {
+        /// Name of the sub-graph.
+        subgraph: String,
+        /// Name of the resource.
+        resource: String,
+    },
+    /// A pass uses a part of an image that overlaps with a different part of the same image used in the graph. The graph
+    /// tracks both separately, so it cannot synchronize between them.
+    #[error(
+        "Pass `{pass}` uses `{resource}`, which overlaps with `{other}` used in the same graph."
+    )]
+    OverlappingSubresources {
+        /// Name of the pass.
+        pass: String,
+        /// The resource used by the pass.
+        resource: String,
+        /// The overlapping resource used in the graph.
+        other: String,
+    },
+    /// A pass graph cannot be recorded with the given bindings. Contains every problem that was found.
+    #[error("Pass graph is invalid: {}", .0.iter().map(|problem| problem.to_string()).collect::<Vec<_>>().join(" "))]
+    InvalidPassGraph(Vec<Error>),
+    /// A pass graph was recorded in parallel, but a pass executor is not `Send`.
+    #[error("Pass `{0}` cannot be recorded in parallel, because its executor is not `Send`.")]
+    PassExecutorNotSend(String),
+    /// A graphics pipeline was bound inside a render pass with attachments that have a different sample count.
+    #[error("Pipeline `{pipeline}` has sample count {found:?}, but the attachments of the current render pass have sample count {expected:?}.")]
+    PipelineSampleCountMismatch {
+        /// Name of the pipeline.
+        pipeline: String,
+        /// Sample count of the attachments.
+        expected: vk::SampleCountFlags,
+        /// Sample count of the pipeline.
+        found: vk::SampleCountFlags,
+    },
     /// Named pipeline not registered in the pipeline cache.
     #[error("Named pipeline `{0}` not found.")]
     PipelineNotFound(String),
@@ -78,6 +164,9 @@ pub enum Error {
     /// Buffer copy between views of different sizes is not allowed.
     #[error("Buffer copy has invalid buffer views as range.")]
     InvalidBufferCopy,
+    /// Image copy between views of different sizes is not allowed.
+    #[error("Image copy has image views of different sizes as range.")]
+    InvalidImageCopy,
     /// Mappable buffer expected
     #[error("Requested mappable buffer, but buffer does not have a memory map")]
     UnmappableBuffer,
@@ -111,6 +200,27 @@ pub enum Error {
     /// Function call requires extension to be enabled, but this extension was not requested or not available.
     #[error("Extension {0} required for this feature, but not enabled.")]
     ExtensionNotSupported(ExtensionID),
+    /// Dynamic state is provided by an enabled extension, but not supported by the device.
+    #[error("Dynamic state {0:?} is not supported by the device.")]
+    DynamicStateNotSupported(vk::DynamicState),
+    /// Tried to set dynamic state that the bound pipeline does not declare as dynamic.
+    #[error("Pipeline `{pipeline}` was not created with dynamic state {state:?}.")]
+    DynamicStateNotEnabled {
+        /// Name of the bound pipeline.
+        pipeline: String,
+        /// The dynamic state that was set.
+        state: vk::DynamicState,
+    },
+    /// Tried to set dynamic state for more color attachments than the current render pass has.
+    #[error("Dynamic state was set for color attachments {first} to {end}, but the current render pass only has {count} color attachments.")]
+    ColorAttachmentOutOfBounds {
+        /// First color attachment the state was set for.
+        first: u32,
+        /// End of the range of color attachments the state was set for, exclusive.
+        end: u64,
+        /// Amount of color attachments in the current render pass.
+        count: usize,
+    },
     /// Uncategorized error.
     #[error("Uncategorized error: `{0}`")]
     Uncategorized(&'static str),
@@ -147,17 +147,7 @@ impl Queue {
         pipelines: PipelineCache<A>,
         descriptors: DescriptorCache,
     ) -> Result<CmdBuf> {
-        let info = vk::CommandBufferAllocateInfo {
-            s_type: vk::StructureType::COMMAND_BUFFER_ALLOCATE_INFO,
-            p_next: std::ptr::null(),
-            command_pool: unsafe { queue_lock.pool.handle() },
-            level: vk::CommandBufferLevel::PRIMARY,
-            command_buffer_count: 1,
-        };
-        let handle = unsafe { device.allocate_command_buffers(&info)? }
-            .into_iter()
-            .next()
-            .ok_or_else(|| Error::Uncategorized("Command buffer allocation failed."))?;
+        let handle = queue_lock.pool.allocate(vk::CommandBufferLevel::PRIMARY)?;
 
         CmdBuf::new(
             device,
@@ -221,6 +221,27 @@ impl<'r> DescriptorSetBuilder<'r> {
         })
     }
 
+    /// Resolve the virtual resource through the given bindings, and bind it as an acceleration structure to the specified slot.
+    /// # Errors
+    /// Fails if the binding did not exist, or did not contain an acceleration structure.
+    pub fn resolve_and_bind_acceleration_structure(
+        &mut self,
+        binding: u32,
+        resource: &VirtualResource,
+        bindings: &PhysicalResourceBindings,
+    ) -> Result<()> {
+        if let Some(PhysicalResource::AccelerationStructure(accel)) = bindings.resolve(resource) {
+            self.inner.bindings.push(DescriptorBinding {
+                binding,
+                ty: vk::DescriptorType::ACCELERATION_STRUCTURE_KHR,
+                descriptors: vec![DescriptorContents::AccelerationStructure(*accel)],
+            });
+            Ok(())
+        } else {
+            Err(Error::NoResourceBound(resource.uid().to_owned()).into())
+        }
+    }
+
     /// Build the descriptor set creation info to pass into the cache.
     pub fn build(self) -> DescriptorSetBinding {
         self.inner
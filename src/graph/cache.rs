@@ -0,0 +1,156 @@
+//! Caching of built pass graphs across frames.
+//!
+//! Most applications build a graph with the exact same structure every frame. Building a graph involves culling passes, creating
+//! barriers and merging them, and assigning memory to transient resources, which is all wasted work when the structure did not change.
+//! A [`PassGraphCache`] stores the result of these steps, keyed by the [structure hash](crate::PassGraph::structure_hash) of the graph.
+//! Since different graphs can have the same hash, the full structure is stored as well, and compared before a stored graph is reused.
+//! Calling [`PassGraph::build_cached()`](crate::PassGraph::build_cached) instead of [`PassGraph::build()`](crate::PassGraph::build)
+//! reuses this result if a graph with the same structure was built before, so that only the pass executors and physical resource bindings
+//! differ between frames.
+//!
+//! # Example
+//!
+//! ```
+//! use phobos::prelude::*;
+//!
+//! let mut cache = PassGraphCache::new();
+//! // Every frame:
+//! let swap = image!("swapchain");
+//! let graph = PassGraph::<domain::Graphics>::new()
+//!     .add_pass(PassBuilder::present("present", &swap))?
+//!     .build_cached(&mut cache)?;
+//! // Record the graph as usual, and let the cache clean up graphs that have not been used for a while.
+//! cache.next_frame();
+//! ```
+
+use std::collections::HashMap;
+
+use ash::vk;
+use petgraph::Graph;
+
+use crate::graph::pass::PassQueue;
+use crate::graph::pass_graph::{PassResource, PassResourceBarrier};
+use crate::graph::resource::{ResourceState, ResourceUsage};
+use crate::graph::transient::{TransientResource, TransientResourceInfo};
+use crate::graph::virtual_resource::{HashedResource, VirtualResource};
+use crate::pipeline::PipelineStage;
+
+/// Everything about the usage of a resource in a pass that affects how a graph is built.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct ResourceStructure {
+    pub(crate) usage: ResourceUsage,
+    pub(crate) resource: VirtualResource,
+    pub(crate) stage: PipelineStage,
+    pub(crate) layout: vk::ImageLayout,
+    pub(crate) load_op: Option<vk::AttachmentLoadOp>,
+    pub(crate) store_op: Option<vk::AttachmentStoreOp>,
+}
+
+/// Everything about a pass that affects how a graph is built.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct PassStructure {
+    pub(crate) name: String,
+    pub(crate) queue: PassQueue,
+    pub(crate) is_renderpass: bool,
+    pub(crate) inputs: Vec<ResourceStructure>,
+    pub(crate) outputs: Vec<ResourceStructure>,
+}
+
+/// The structure of a pass graph before it is built. Two graphs with the same structure are built into the same graph.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct GraphStructure {
+    /// All passes, in the order they were added to the graph.
+    pub(crate) passes: Vec<PassStructure>,
+    pub(crate) transients: Vec<(VirtualResource, TransientResourceInfo)>,
+    pub(crate) exports: Vec<VirtualResource>,
+    pub(crate) histories: Vec<VirtualResource>,
+    pub(crate) imports: Vec<(VirtualResource, ResourceState)>,
+}
+
+/// Node in a compiled pass graph.
+#[derive(Debug, Clone)]
+pub(crate) enum CompiledNode {
+    /// A pass, referred to by its name, and the number of passes with the same name that were added to the graph before it.
+    Task(String, usize),
+    /// A barrier node.
+    Barrier(PassResourceBarrier),
+}
+
+/// The result of building a pass graph, without any of its pass executors.
+#[derive(Debug, Clone)]
+pub(crate) struct CompiledPassGraph {
+    /// Structure of the graph this was built from.
+    pub(crate) structure: GraphStructure,
+    pub(crate) graph: Graph<CompiledNode, HashedResource>,
+    pub(crate) source_outputs: Vec<PassResource>,
+    pub(crate) transients: Vec<TransientResource>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    value: CompiledPassGraph,
+    ttl: u32,
+}
+
+/// Stores built pass graphs so they can be reused by graphs with the same structure.
+/// See the [module-level documentation](crate::graph::cache) for more information.
+#[derive(Debug, Default)]
+pub struct PassGraphCache {
+    store: HashMap<u64, Entry>,
+}
+
+impl PassGraphCache {
+    /// Amount of calls to [`PassGraphCache::next_frame`] that have to happen without using a graph for it to be removed from the cache.
+    const MAX_TIME_TO_LIVE: u32 = 8;
+
+    /// Create a new, empty pass graph cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the number of graphs stored in the cache.
+    pub fn len(&self) -> usize {
+        self.store.len()
+    }
+
+    /// Returns true if there are no graphs stored in the cache.
+    pub fn is_empty(&self) -> bool {
+        self.store.is_empty()
+    }
+
+    /// Remove all graphs from the cache.
+    pub fn clear(&mut self) {
+        self.store.clear();
+    }
+
+    /// Updates the cache to remove graphs that have not been used for too long. Call this once per frame.
+    pub fn next_frame(&mut self) {
+        self.store.iter_mut().for_each(|(_, entry)| entry.ttl -= 1);
+        self.store.retain(|_, entry| entry.ttl != 0);
+    }
+
+    /// Look up a compiled graph by its structure hash. Only returns the graph if its structure is equal to `structure`.
+    pub(crate) fn get(
+        &mut self,
+        hash: u64,
+        structure: &GraphStructure,
+    ) -> Option<&CompiledPassGraph> {
+        let entry = self.store.get_mut(&hash)?;
+        if entry.value.structure != *structure {
+            return None;
+        }
+        entry.ttl = Self::MAX_TIME_TO_LIVE;
+        Some(&entry.value)
+    }
+
+    /// Store a compiled graph under its structure hash.
+    pub(crate) fn insert(&mut self, hash: u64, graph: CompiledPassGraph) {
+        self.store.insert(
+            hash,
+            Entry {
+                value: graph,
+                ttl: Self::MAX_TIME_TO_LIVE,
+            },
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! Temporal resources that carry their contents from one frame to the next.
+//!
+//! Techniques like temporal anti-aliasing and reprojection read the result of the previous frame, while writing the result of
+//! the current frame. This is done with two physical images that swap roles every frame, stored in a [`HistoryImage`].
+//!
+//! In the graph, an image is declared as a history image with [`PassGraph::history_image()`]. Passes write to it as usual,
+//! and read the previous frame's contents through [`VirtualResource::history()`]. Unlike other resources, the contents of the
+//! previous frame are not discarded at the start of the graph. Instead, the image starts in the layout it was left in by the
+//! previous frame. This layout is stored in the [`HistoryImage`] when a graph using it is recorded.
+//!
+//! # Example
+//!
+//! ```
+//! use phobos::prelude::*;
+//!
+//! // Created once, and kept alive across frames.
+//! let mut taa_images = HistoryImage::new(image_a.view(vk::ImageAspectFlags::COLOR)?, image_b.view(vk::ImageAspectFlags::COLOR)?);
+//!
+//! // Every frame:
+//! let taa = image!("taa");
+//! let resolve = PassBuilder::render("taa_resolve")
+//!     .sample_image(&taa.history(), PipelineStage::FRAGMENT_SHADER)
+//!     .clear_color_attachment(&taa, ClearColor::Float([0.0; 4]))?
+//!     .build();
+//! let graph = PassGraph::<domain::Graphics>::new()
+//!     .history_image(&taa)?
+//!     .add_pass(resolve)?
+//!     .build()?;
+//! let mut bindings = PhysicalResourceBindings::new();
+//! bindings.bind_history_image("taa", &taa_images);
+//! // Record and submit the graph, then swap the images for the next frame.
+//! taa_images.advance();
+//! ```
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use ash::vk;
+
+use crate::{Allocator, Error, ImageView, PassGraph, PhysicalResourceBindings, VirtualResource};
+use crate::graph::pass_graph::PassResource;
+use crate::graph::resource::ResourceType;
+use crate::graph::task_graph::Node;
+use crate::sync::domain::ExecutionDomain;
+
+/// A pair of images that swap roles every frame. One image holds the result of the previous frame, and the other one
+/// receives the result of the current frame. Bind it to a graph with [`PhysicalResourceBindings::bind_history_image()`](crate::PhysicalResourceBindings::bind_history_image).
+#[derive(Debug, Clone)]
+pub struct HistoryImage {
+    views: [ImageView; 2],
+    /// The layout each image was left in by the last frame that used it.
+    layouts: [Arc<Mutex<vk::ImageLayout>>; 2],
+    current: usize,
+    has_history: bool,
+}
+
+impl HistoryImage {
+    /// Create a new history image from two image views. Both images should have the same format and size.
+    /// In the first frame, the history image has no valid contents yet.
+    pub fn new(first: ImageView, second: ImageView) -> Self {
+        HistoryImage {
+            views: [first, second],
+            layouts: [
+                Arc::new(Mutex::new(vk::ImageLayout::UNDEFINED)),
+                Arc::new(Mutex::new(vk::ImageLayout::UNDEFINED)),
+            ],
+            current: 0,
+            has_history: false,
+        }
+    }
+
+    /// Get the image written to in the current frame.
+    pub fn current(&self) -> &ImageView {
+        &self.views[self.current]
+    }
+
+    /// Get the image holding the result of the previous frame.
+    pub fn previous(&self) -> &ImageView {
+        &self.views[1 - self.current]
+    }
+
+    /// Get the layout the current image was left in by the last frame that used it.
+    pub(crate) fn current_layout(&self) -> &Arc<Mutex<vk::ImageLayout>> {
+        &self.layouts[self.current]
+    }
+
+    /// Get the layout the previous image was left in by the last frame that used it.
+    pub(crate) fn previous_layout(&self) -> &Arc<Mutex<vk::ImageLayout>> {
+        &self.layouts[1 - self.current]
+    }
+
+    /// Returns true if the previous image holds the result of a previous frame.
+    pub fn has_history(&self) -> bool {
+        self.has_history
+    }
+
+    /// Swap the images, so the current image becomes the previous image. Call this once per frame, after the graph using this
+    /// history image was recorded.
+    pub fn advance(&mut self) {
+        self.current = 1 - self.current;
+        self.has_history = true;
+    }
+
+    /// Drop the contents of the previous frame, for example after a camera cut. The next frame reads the previous image
+    /// with undefined contents.
+    pub fn reset(&mut self) {
+        self.has_history = false;
+    }
+}
+
+impl<'cb, D: ExecutionDomain, U, A: Allocator> PassGraph<'cb, D, U, A> {
+    /// Declare an image as a history image. Passes can read the contents this image had at the end of the previous frame
+    /// through [`VirtualResource::history()`]. The image is exported, so passes writing to it are never culled.
+    /// See the [`history`](crate::graph::history) module documentation for more information.
+    /// # Errors
+    /// * Fails if `resource` is not an image resource.
+    /// * Fails if `resource` is not the initial version of the image, or a subresource.
+    /// * Fails if `resource` was already declared as a history image.
+    pub fn history_image(mut self, resource: &VirtualResource) -> Result<Self> {
+        if resource.resource_type() != ResourceType::Image {
+            return Err(Error::Uncategorized("History image must be an image resource").into());
+        }
+        if !resource.is_source() || resource.subresource_range().is_some() {
+            return Err(Error::Uncategorized(
+                "History image must be the initial version of a whole image",
+            )
+            .into());
+        }
+        if self
+            .histories
+            .iter()
+            .any(|history| history.is_associated_with(resource))
+        {
+            return Err(
+                Error::Uncategorized("Resource was already declared as history image").into()
+            );
+        }
+        self.histories.push(resource.clone());
+        Ok(self.export(resource))
+    }
+
+    /// Get the last usage of a resource in this graph. This is a read of its newest version, or otherwise the write that produced it.
+    /// The stages of all reads of the newest version are combined.
+    fn final_usage(&self, resource: &VirtualResource) -> Option<PassResource> {
+        let mut last: Option<((usize, bool), PassResource)> = None;
+        for node in self.graph.graph.node_indices() {
+            if node == self.source() {
+                continue;
+            }
+            let Node::Task(task) = self.graph.graph.node_weight(node).unwrap() else { continue; };
+            let usages = task
+                .inputs
+                .iter()
+                .map(|input| (true, input))
+                .chain(task.outputs.iter().map(|output| (false, output)))
+                .filter(|(_, usage)| usage.resource.is_associated_with(resource));
+            for (is_input, usage) in usages {
+                let key = (usage.resource.version(), is_input);
+                match &mut last {
+                    Some((last_key, last)) if *last_key == key => last.stage |= usage.stage,
+                    Some((last_key, _)) if *last_key > key => {}
+                    _ => last = Some((key, usage.clone())),
+                }
+            }
+        }
+        last.map(|(_, usage)| usage)
+    }
+
+    /// Pass in the build step that connects each history image with the previous frame.
+    /// The history resource waits for its last usage in the previous frame. Its layout is only a placeholder here, since the
+    /// layout the image was actually left in is only known when recording. See [`PassGraph::store_history_layouts()`].
+    /// Since the images swap every frame, the image written to in this frame must also wait for the reads of the history in the previous frame.
+    pub(crate) fn set_history_sources(&mut self) -> Result<()> {
+        let histories = self
+            .histories
+            .iter()
+            .map(|resource| {
+                let history = resource.history();
+                (
+                    self.final_usage(resource),
+                    self.final_usage(&history),
+                    resource.clone(),
+                    history,
+                )
+            })
+            .collect::<Vec<_>>();
+        let source = self.source();
+        let Node::Task(source) = self.graph.graph.node_weight_mut(source).unwrap() else { return Err(Error::NodeNotFound.into()); };
+        for (current_usage, history_usage, current, history) in histories {
+            for output in &mut source.outputs {
+                if output.resource.is_associated_with(&history) {
+                    if let Some(usage) = &current_usage {
+                        output.usage = usage.usage.clone();
+                        output.stage = usage.stage;
+                        output.layout = usage.layout;
+                    }
+                } else if output.resource.is_associated_with(&current) {
+                    if let Some(usage) = &history_usage {
+                        output.stage |= usage.stage;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Store the layout every history image is left in at the end of this graph in its [`HistoryImage`], so the next frame
+    /// can start from it. Called after the graph was recorded.
+    pub(crate) fn store_history_layouts(&self, bindings: &PhysicalResourceBindings) -> Result<()> {
+        for resource in &self.histories {
+            for resource in [resource.clone(), resource.history()] {
+                if let Some(usage) = self.final_usage(&resource) {
+                    bindings.set_history_layout(&resource, usage.layout)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,266 @@
+//! Export a built pass graph to JSON, for use in external tooling.
+//!
+//! Unlike the [`GraphViz`](crate::graph::pass_graph::GraphViz) output, the JSON export includes all details of the barriers in the graph,
+//! and the order in which the graph is recorded. This makes it suitable for diffing graphs, or displaying them in a custom viewer.
+//!
+//! The exported document has the following structure:
+//! ```json
+//! {
+//!     "passes": [{
+//!         "id": 1, "name": "present", "queue": "main", "renderpass": false, "layer_count": 1, "view_mask": 0,
+//!         "inputs": [...], "outputs": [...]
+//!     }],
+//!     "barriers": [{
+//!         "id": 2, "queue": "main", "resource": {...},
+//!         "src_stage": "COLOR_ATTACHMENT_OUTPUT", "dst_stage": "BOTTOM_OF_PIPE",
+//!         "src_access": "COLOR_ATTACHMENT_WRITE", "dst_access": "NONE",
+//!         "old_layout": "COLOR_ATTACHMENT_OPTIMAL", "new_layout": "PRESENT_SRC_KHR",
+//!         "producers": [1], "consumers": [3]
+//!     }],
+//!     "transients": [{ "name": "gbuffer", "slot": 0 }],
+//!     "execution_order": [0, 2, 1, 3]
+//! }
+//! ```
+//! Each resource is written as `{ "uid": "color+", "name": "color", "version": 1, "type": "image", "usage": "color_attachment", "stage": "...", "layout": "..." }`.
+//! Layouts are `null` for buffer and acceleration structure resources. Node ids are only meaningful within a single export.
+//!
+//! # Example
+//!
+//! ```
+//! use phobos::prelude::*;
+//!
+//! let graph = graph.build()?;
+//! std::fs::write("graph.json", graph.json()?)?;
+//! ```
+
+use std::fmt::{Debug, Write};
+
+use anyhow::Result;
+use petgraph::{Direction, Incoming, Outgoing};
+use petgraph::graph::NodeIndex;
+
+use crate::Allocator;
+use crate::graph::pass::PassQueue;
+use crate::graph::pass_graph::{BuiltPassGraph, PassGraph, PassGraphInner, PassResource};
+use crate::graph::resource::{AttachmentType, ResourceType, ResourceUsage};
+use crate::graph::task_graph::Node;
+use crate::sync::domain::ExecutionDomain;
+
+/// Trait that is implemented for built pass graphs to export them in a machine-readable format.
+pub trait GraphJson {
+    /// Get the JSON representation of this graph. See the [module-level documentation](crate::graph::json) for the format.
+    fn json(&self) -> Result<String>;
+}
+
+/// Minimal JSON document model, so we do not need a serialization library for this.
+enum Value {
+    Null,
+    Bool(bool),
+    Number(u64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(&'static str, Value)>),
+}
+
+impl Value {
+    fn string(value: impl Into<String>) -> Self {
+        Value::String(value.into())
+    }
+
+    fn write(&self, out: &mut String, indent: usize) -> std::fmt::Result {
+        match self {
+            Value::Null => out.write_str("null"),
+            Value::Bool(value) => write!(out, "{value}"),
+            Value::Number(value) => write!(out, "{value}"),
+            Value::String(value) => write_string(out, value),
+            Value::Array(values) if values.is_empty() => out.write_str("[]"),
+            Value::Array(values) => {
+                out.write_str("[\n")?;
+                for (i, value) in values.iter().enumerate() {
+                    write!(out, "{:1$}", "", (indent + 1) * 2)?;
+                    value.write(out, indent + 1)?;
+                    let separator = if i + 1 == values.len() { "\n" } else { ",\n" };
+                    out.write_str(separator)?;
+                }
+                write!(out, "{:1$}]", "", indent * 2)
+            }
+            Value::Object(fields) if fields.is_empty() => out.write_str("{}"),
+            Value::Object(fields) => {
+                out.write_str("{\n")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    write!(out, "{:1$}", "", (indent + 1) * 2)?;
+                    write_string(out, key)?;
+                    out.write_str(": ")?;
+                    value.write(out, indent + 1)?;
+                    let separator = if i + 1 == fields.len() { "\n" } else { ",\n" };
+                    out.write_str(separator)?;
+                }
+                write!(out, "{:1$}}}", "", indent * 2)
+            }
+        }
+    }
+}
+
+fn write_string(out: &mut String, value: &str) -> std::fmt::Result {
+    out.write_char('"')?;
+    for c in value.chars() {
+        match c {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32)?,
+            c => out.write_char(c)?,
+        }
+    }
+    out.write_char('"')
+}
+
+fn node_id(node: NodeIndex) -> Value {
+    Value::Number(node.index() as u64)
+}
+
+fn neighbors<D: ExecutionDomain, U, A: Allocator>(
+    graph: &PassGraphInner<D, U, A>,
+    node: NodeIndex,
+    direction: Direction,
+) -> Value {
+    let mut neighbors = graph
+        .neighbors_directed(node, direction)
+        .collect::<Vec<_>>();
+    neighbors.sort();
+    Value::Array(neighbors.into_iter().map(node_id).collect())
+}
+
+fn queue_name(queue: PassQueue) -> Value {
+    Value::string(match queue {
+        PassQueue::Main => "main",
+        PassQueue::Compute => "compute",
+        PassQueue::Transfer => "transfer",
+    })
+}
+
+fn usage_name(usage: &ResourceUsage) -> &'static str {
+    match usage {
+        ResourceUsage::Nothing => "nothing",
+        ResourceUsage::Present => "present",
+        ResourceUsage::Attachment(AttachmentType::Color) => "color_attachment",
+        ResourceUsage::Attachment(AttachmentType::Depth) => "depth_attachment",
+        ResourceUsage::Attachment(AttachmentType::Stencil) => "stencil_attachment",
+        ResourceUsage::Attachment(AttachmentType::DepthStencil(_)) => "depth_stencil_attachment",
+        ResourceUsage::Attachment(AttachmentType::Resolve(_)) => "resolve_attachment",
+        ResourceUsage::ShaderRead => "shader_read",
+        ResourceUsage::ShaderWrite => "shader_write",
+        ResourceUsage::UniformRead => "uniform_read",
+        ResourceUsage::VertexRead => "vertex_read",
+        ResourceUsage::IndexRead => "index_read",
+        ResourceUsage::IndirectRead => "indirect_read",
+        ResourceUsage::TransferRead => "transfer_read",
+        ResourceUsage::TransferWrite => "transfer_write",
+        ResourceUsage::AccelerationStructureRead => "acceleration_structure_read",
+        ResourceUsage::AccelerationStructureWrite => "acceleration_structure_write",
+        ResourceUsage::External(_) => "external",
+    }
+}
+
+fn flags_value(flags: impl Debug) -> Value {
+    match format!("{flags:?}") {
+        flags if flags.is_empty() => Value::string("NONE"),
+        flags => Value::String(flags),
+    }
+}
+
+fn layout_value(resource: &PassResource) -> Value {
+    match resource.resource.resource_type() {
+        ResourceType::Image => Value::string(format!("{:?}", resource.layout)),
+        ResourceType::Buffer | ResourceType::AccelerationStructure => Value::Null,
+    }
+}
+
+fn resource_value(resource: &PassResource) -> Value {
+    let virtual_resource = &resource.resource;
+    Value::Object(vec![
+        ("uid", Value::string(virtual_resource.uid())),
+        ("name", Value::string(virtual_resource.name())),
+        ("version", Value::Number(virtual_resource.version() as u64)),
+        (
+            "type",
+            Value::string(match virtual_resource.resource_type() {
+                ResourceType::Image => "image",
+                ResourceType::Buffer => "buffer",
+                ResourceType::AccelerationStructure => "acceleration_structure",
+            }),
+        ),
+        ("usage", Value::string(usage_name(&resource.usage))),
+        ("stage", flags_value(resource.stage)),
+        ("layout", layout_value(resource)),
+    ])
+}
+
+impl<D: ExecutionDomain, U, A: Allocator> GraphJson for BuiltPassGraph<'_, D, U, A> {
+    fn json(&self) -> Result<String> {
+        let graph = &self.task_graph().graph;
+        let mut passes = Vec::new();
+        let mut barriers = Vec::new();
+        for node in graph.node_indices() {
+            let queue = PassGraph::node_queue(graph, node);
+            match graph.node_weight(node).unwrap() {
+                Node::Task(task) => passes.push(Value::Object(vec![
+                    ("id", node_id(node)),
+                    ("name", Value::string(&task.identifier)),
+                    ("queue", queue_name(queue)),
+                    ("renderpass", Value::Bool(task.is_renderpass)),
+                    ("layer_count", Value::Number(task.layer_count as u64)),
+                    ("view_mask", Value::Number(task.view_mask as u64)),
+                    ("inputs", Value::Array(task.inputs.iter().map(resource_value).collect())),
+                    ("outputs", Value::Array(task.outputs.iter().map(resource_value).collect())),
+                ])),
+                Node::Barrier(barrier) => {
+                    let dst_resource = PassGraph::barrier_dst_resource(graph, node)?;
+                    barriers.push(Value::Object(vec![
+                        ("id", node_id(node)),
+                        ("queue", queue_name(queue)),
+                        ("resource", resource_value(&barrier.resource)),
+                        ("src_stage", flags_value(barrier.src_stage)),
+                        ("dst_stage", flags_value(barrier.dst_stage)),
+                        ("src_access", flags_value(barrier.src_access)),
+                        ("dst_access", flags_value(barrier.dst_access)),
+                        ("old_layout", layout_value(&barrier.resource)),
+                        ("new_layout", layout_value(dst_resource)),
+                        ("producers", neighbors(graph, node, Incoming)),
+                        ("consumers", neighbors(graph, node, Outgoing)),
+                    ]));
+                }
+                Node::_Unreachable(_) => {
+                    unreachable!()
+                }
+            }
+        }
+
+        let transients = self
+            .transients
+            .iter()
+            .map(|transient| {
+                Value::Object(vec![
+                    ("name", Value::string(transient.resource.name())),
+                    ("slot", Value::Number(transient.slot as u64)),
+                ])
+            })
+            .collect();
+
+        let document = Value::Object(vec![
+            ("passes", Value::Array(passes)),
+            ("barriers", Value::Array(barriers)),
+            ("transients", Value::Array(transients)),
+            (
+                "execution_order",
+                Value::Array(self.execution_order().into_iter().map(node_id).collect()),
+            ),
+        ]);
+
+        let mut out = String::new();
+        document.write(&mut out, 0)?;
+        Ok(out)
+    }
+}
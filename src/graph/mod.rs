@@ -7,8 +7,29 @@
 //!
 //! Actual resources need to be bound to each virtual resource before recording the graph into a command buffer.
 //! This is done using the [`PhysicalResourceBindings`](crate::PhysicalResourceBindings) struct.
+//! Missing or incompatible bindings are reported as errors before anything is recorded, see the [`validate`] module.
+//! Intermediate resources that only live for the duration of the graph can instead be declared as transient resources.
+//! These are created by the graph itself, and may share memory when their lifetimes do not overlap. See the [`transient`] module for more information.
+//! Individual mip levels and array layers of an image can be used as separate resources through [`VirtualResource::subresource`](crate::VirtualResource::subresource),
+//! for example to generate a mip chain in a sequence of passes.
+//!
+//! Images that need the result of the previous frame, for example for temporal anti-aliasing, can be declared as history images.
+//! These swap between two physical images every frame, and keep their contents and layout across frames. See the [`history`] module for more information.
+//!
+//! When building the graph, passes that do not contribute to its output are removed. Any resource that is used outside of the graph
+//! after it is submitted must be marked with [`PassGraph::export`](crate::PassGraph::export) to prevent its passes from being culled.
+//! Resources that persist across frames, like texture atlases, can be imported with [`PassGraph::import`](crate::PassGraph::import)
+//! to keep their contents and start in a known [`ResourceState`](resource::ResourceState), and exported with
+//! [`PassGraph::export_with_state`](crate::PassGraph::export_with_state) to be transitioned to a known state at the end of the graph.
+//!
+//! Graphs that are rebuilt every frame with the same structure can be built through a [`PassGraphCache`](crate::graph::cache::PassGraphCache)
+//! to skip most of the work of building them. See the [`cache`] module for more information.
+//!
+//! Reusable groups of passes can be written as a [`SubGraph`](sub_graph::SubGraph), which prefixes the names of its passes and internal resources
+//! so that it can be inserted into the same graph multiple times. See the [`sub_graph`] module for more information.
 //!
 //! Through the [`GraphViz`](task_graph::GraphViz) trait, it's possible to export a graphviz-compatible dot file to display the task graph.
+//! For tooling, the [`GraphJson`](json::GraphJson) trait exports a built graph including all barriers and its execution order as JSON.
 //!
 //! # Example
 //!
@@ -49,12 +70,67 @@
 //! let final_cmd = graph.record(cmd, &bindings, &mut pool, Some(debug_messenger))?
 //!                 .finish();
 //! ```
+//!
+//! Barriers between passes that are far apart can be recorded as split barriers using events, by enabling
+//! [`BuiltPassGraph::split_barriers()`](crate::graph::pass_graph::BuiltPassGraph::split_barriers) before recording.
+//!
+//! The GPU time of every pass can be measured by enabling a [`GraphProfiler`](profiler::GraphProfiler) with
+//! [`BuiltPassGraph::profile()`](crate::graph::pass_graph::BuiltPassGraph::profile). See the [`profiler`] module for more information.
+//!
+//! # Multiple queues
+//!
+//! Compute and transfer passes can be executed on a dedicated queue by setting their executor with
+//! [`PassBuilder::async_compute_fn()`](crate::PassBuilder::async_compute_fn) or [`PassBuilder::async_transfer_fn()`](crate::PassBuilder::async_transfer_fn).
+//! Recording the graph with [`BuiltPassGraph::record_multi_queue()`](crate::graph::pass_graph::BuiltPassGraph::record_multi_queue) splits it
+//! into a command buffer per queue, synchronized with semaphores and queue family ownership transfers. When recorded with
+//! [`BuiltPassGraph::record()`](crate::graph::pass_graph::BuiltPassGraph::record), these passes are recorded on the main queue instead.
+//! ```
+//! # use anyhow::Result;
+//! # use phobos::prelude::*;
+//! # use phobos::graph::pass_graph::BuiltPassGraph;
+//! # use phobos::pool::LocalPool;
+//! fn submit_frame(
+//!     exec: &ExecutionManager,
+//!     graph: &mut BuiltPassGraph<'_, domain::All>,
+//!     bindings: &PhysicalResourceBindings,
+//!     ifc: InFlightContext,
+//!     mut pool: LocalPool,
+//! ) -> Result<()> {
+//!     let mut batch = exec.start_submit_batch()?;
+//!     let recording = graph.record_multi_queue(exec, &mut batch, bindings, &mut pool, None, &mut ())?;
+//!     recording.submit_for_present(&mut batch, ifc, pool)?;
+//!     batch.finish()?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! # Parallel recording
+//!
+//! With the `rayon` feature enabled, `BuiltPassGraph::record_parallel()` records every pass to a secondary command buffer
+//! on the rayon thread pool, and executes these from the given command buffer in graph order. This requires all pass executors to be `Send`,
+//! so they must be set with [`PassBuilder::execute_send_fn()`](crate::PassBuilder::execute_send_fn) or one of the other `send` variants.
+//! Recording fails with [`Error::PassExecutorNotSend`](crate::Error::PassExecutorNotSend) otherwise.
+//! Worker threads allocate from the same [`ResourcePool`](crate::pool::ResourcePool), so the allocations of its allocator must be `Send` as well.
+//! Each pass receives its own clone of the user data, so changes made to it by a pass are not visible to other passes.
+//! ```
+//! use phobos::prelude::*;
+//!
+//! let cmd = graph.record_parallel(cmd, &bindings, &mut pool, None, &())?
+//!                .finish();
+//! ```
 
+pub mod cache;
+pub mod history;
+pub mod json;
 pub mod pass;
 pub mod pass_graph;
 pub mod physical_resource;
+pub mod profiler;
 pub mod record;
 pub mod resource;
+pub mod sub_graph;
+pub mod transient;
+pub mod validate;
 pub mod virtual_resource;
 
 pub(crate) mod task_graph;
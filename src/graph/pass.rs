@@ -69,25 +69,35 @@
 //!     .build();
 //! ```
 //!
+//! Copies, blits and clears are declared with [`PassBuilder::transfer_src`] and [`PassBuilder::transfer_dst`], which transition images to
+//! `VK_IMAGE_LAYOUT_TRANSFER_SRC_OPTIMAL` and `VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL`. For common operations, passes with a ready-made executor
+//! can be created with [`PassBuilder::blit`], [`PassBuilder::copy_buffer`] and [`PassBuilder::copy_buffer_to_image`].
+//!
 //! Binding physical resources and recording is covered under the [`graph`](crate::graph) module documentation.
 
 #[cfg(feature = "fsr2")]
 use anyhow::{anyhow, bail};
-use anyhow::Result;
+use anyhow::{ensure, Result};
 use ash::vk;
 
-use crate::{Allocator, DefaultAllocator, Error, PhysicalResourceBindings, VirtualResource};
+use crate::{
+    Allocator, DefaultAllocator, Error, GfxSupport, GraphicsCmdBuffer, ImageView,
+    PhysicalResourceBindings, TransferCmdBuffer, TransferSupport, VirtualResource,
+};
 #[cfg(feature = "fsr2")]
-use crate::{ComputeSupport, Device, ImageView};
+use crate::{ComputeSupport, Device};
 use crate::command_buffer::IncompleteCommandBuffer;
 #[cfg(feature = "fsr2")]
 use crate::fsr2::{Fsr2DispatchDescription, Fsr2DispatchResources};
 use crate::graph::pass_graph::PassResource;
 #[cfg(feature = "fsr2")]
 use crate::graph::physical_resource::PhysicalResource;
-use crate::graph::resource::{AttachmentType, ResourceUsage};
+use crate::graph::resource::{
+    AttachmentOps, AttachmentType, ResourceState, ResourceType, ResourceUsage,
+};
 use crate::pipeline::PipelineStage;
 use crate::pool::LocalPool;
+use crate::sync::domain;
 use crate::sync::domain::ExecutionDomain;
 use crate::util::to_vk::IntoVulkanType;
 
@@ -129,7 +139,136 @@ where
     }
 }
 
-pub(crate) type BoxedPassFn<'cb, D, U, A> = Box<dyn PassExecutor<D, U, A> + 'cb>;
+/// A boxed pass executor. Executors that are `Send` are kept separately, so passes using them can be recorded
+/// on other threads with `BuiltPassGraph::record_parallel()`.
+pub(crate) enum BoxedPassFn<'cb, D: ExecutionDomain, U, A: Allocator> {
+    Local(Box<dyn PassExecutor<D, U, A> + 'cb>),
+    Send(Box<dyn PassExecutor<D, U, A> + Send + 'cb>),
+}
+
+impl<'cb, D: ExecutionDomain, U, A: Allocator> BoxedPassFn<'cb, D, U, A> {
+    fn execute<'q>(
+        &mut self,
+        cmd: IncompleteCommandBuffer<'q, D, A>,
+        local_pool: &mut LocalPool<A>,
+        bindings: &PhysicalResourceBindings,
+        user_data: &mut U,
+    ) -> PassFnResult<'q, D, A> {
+        match self {
+            BoxedPassFn::Local(exec) => exec.execute(cmd, local_pool, bindings, user_data),
+            BoxedPassFn::Send(exec) => exec.execute(cmd, local_pool, bindings, user_data),
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    fn is_send(&self) -> bool {
+        matches!(self, BoxedPassFn::Send(_))
+    }
+}
+
+/// The queue a pass is executed on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PassQueue {
+    /// The queue of the execution domain of the pass graph.
+    Main,
+    /// A compute queue, used to run compute passes asynchronously.
+    Compute,
+    /// A transfer queue, used to run transfer passes asynchronously.
+    Transfer,
+}
+
+/// A pass executor together with the queue it needs to be executed on.
+pub(crate) enum QueuedPassFn<'cb, D: ExecutionDomain, U, A: Allocator> {
+    Main(BoxedPassFn<'cb, D, U, A>),
+    Compute(BoxedPassFn<'cb, domain::Compute, U, A>),
+    Transfer(BoxedPassFn<'cb, domain::Transfer, U, A>),
+}
+
+impl<'cb, D: ExecutionDomain, U, A: Allocator> QueuedPassFn<'cb, D, U, A> {
+    /// Get access to this executor that can be sent to another thread, or `None` if the executor is not `Send`.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn as_send(&mut self) -> Option<SendPassFn<'_, 'cb, D, U, A>> {
+        let is_send = match self {
+            QueuedPassFn::Main(exec) => exec.is_send(),
+            QueuedPassFn::Compute(exec) => exec.is_send(),
+            QueuedPassFn::Transfer(exec) => exec.is_send(),
+        };
+        is_send.then_some(SendPassFn(self))
+    }
+
+    /// Get the queue this executor must be executed on.
+    pub(crate) fn queue(&self) -> PassQueue {
+        match self {
+            QueuedPassFn::Main(_) => PassQueue::Main,
+            QueuedPassFn::Compute(_) => PassQueue::Compute,
+            QueuedPassFn::Transfer(_) => PassQueue::Transfer,
+        }
+    }
+
+    /// Record this pass to a command buffer over any domain.
+    /// # Errors
+    /// * Fails if the queue of `cmd` does not support the operations this pass needs.
+    /// * Fails if the pass executor fails.
+    pub(crate) fn execute<'q, C: ExecutionDomain>(
+        &mut self,
+        cmd: IncompleteCommandBuffer<'q, C, A>,
+        local_pool: &mut LocalPool<A>,
+        bindings: &PhysicalResourceBindings,
+        user_data: &mut U,
+    ) -> PassFnResult<'q, C, A> {
+        match self {
+            QueuedPassFn::Main(exec) => {
+                let cmd = cmd.into_domain::<D>(vk::QueueFlags::empty())?;
+                exec.execute(cmd, local_pool, bindings, user_data)?
+                    .into_domain(vk::QueueFlags::empty())
+            }
+            QueuedPassFn::Compute(exec) => {
+                let cmd = cmd.into_domain::<domain::Compute>(vk::QueueFlags::COMPUTE)?;
+                exec.execute(cmd, local_pool, bindings, user_data)?
+                    .into_domain(vk::QueueFlags::empty())
+            }
+            QueuedPassFn::Transfer(exec) => {
+                // Queues that support graphics or compute operations do not need to report transfer support.
+                let flags = cmd.queue_info().flags;
+                ensure!(
+                    flags.intersects(
+                        vk::QueueFlags::GRAPHICS
+                            | vk::QueueFlags::COMPUTE
+                            | vk::QueueFlags::TRANSFER
+                    ),
+                    "queue does not support transfer operations"
+                );
+                let cmd = cmd.into_domain::<domain::Transfer>(vk::QueueFlags::empty())?;
+                exec.execute(cmd, local_pool, bindings, user_data)?
+                    .into_domain(vk::QueueFlags::empty())
+            }
+        }
+    }
+}
+
+/// Mutable access to a pass executor that is `Send`, obtained through [`QueuedPassFn::as_send()`].
+#[cfg(feature = "rayon")]
+pub(crate) struct SendPassFn<'e, 'cb, D: ExecutionDomain, U, A: Allocator>(
+    &'e mut QueuedPassFn<'cb, D, U, A>,
+);
+
+// SAFETY: This is only created for executors stored as `BoxedPassFn::Send`, and the executor is the only thing `QueuedPassFn` holds.
+#[cfg(feature = "rayon")]
+unsafe impl<D: ExecutionDomain, U, A: Allocator> Send for SendPassFn<'_, '_, D, U, A> {}
+
+#[cfg(feature = "rayon")]
+impl<D: ExecutionDomain, U, A: Allocator> SendPassFn<'_, '_, D, U, A> {
+    /// Record this pass to a command buffer over any domain. See [`QueuedPassFn::execute()`].
+    pub(crate) fn execute<'q, C: ExecutionDomain>(
+        &mut self,
+        cmd: IncompleteCommandBuffer<'q, C, A>,
+        local_pool: &mut LocalPool<A>,
+        bindings: &PhysicalResourceBindings,
+        user_data: &mut U,
+    ) -> PassFnResult<'q, C, A> {
+        self.0.execute(cmd, local_pool, bindings, user_data)
+    }
+}
 
 /// An empty pass executor that does nothing
 pub struct EmptyPassExecutor;
@@ -144,6 +283,12 @@ impl EmptyPassExecutor {
     pub fn new_boxed() -> Box<Self> {
         Box::new(Self::new())
     }
+
+    /// Create a new boxed empty pass executor that can be stored in a pass.
+    pub(crate) fn new_pass_fn<'cb, D: ExecutionDomain, U, A: Allocator>(
+    ) -> BoxedPassFn<'cb, D, U, A> {
+        BoxedPassFn::Send(Self::new_boxed())
+    }
 }
 
 impl<D: ExecutionDomain, U, A: Allocator> PassExecutor<D, U, A> for EmptyPassExecutor {
@@ -168,8 +313,10 @@ pub struct Pass<'cb, D: ExecutionDomain, U = (), A: Allocator = DefaultAllocator
     pub(crate) inputs: Vec<PassResource>,
     pub(crate) outputs: Vec<PassResource>,
     #[derivative(Debug = "ignore")]
-    pub(crate) execute: BoxedPassFn<'cb, D, U, A>,
+    pub(crate) execute: QueuedPassFn<'cb, D, U, A>,
     pub(crate) is_renderpass: bool,
+    pub(crate) layer_count: u32,
+    pub(crate) view_mask: u32,
 }
 
 /// Represents a clear color for an attachment. The variant used should match
@@ -235,7 +382,7 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> Pass<'cb, D, U, A> {
         self.outputs
             .iter()
             .filter_map(|output| {
-                if resource.is_associated_with(&output.resource) {
+                if resource.is_same_subresource(&output.resource) {
                     Some(&output.resource)
                 } else {
                     None
@@ -248,6 +395,11 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> Pass<'cb, D, U, A> {
     pub fn name(&self) -> &str {
         &self.name
     }
+
+    /// Get the queue this pass will be executed on.
+    pub fn queue(&self) -> PassQueue {
+        self.execute.queue()
+    }
 }
 
 impl<'cb, D: ExecutionDomain, U, A: Allocator> PassBuilder<'cb, D, U, A> {
@@ -257,10 +409,12 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassBuilder<'cb, D, U, A> {
             inner: Pass {
                 name: name.into(),
                 color: None,
-                execute: EmptyPassExecutor::new_boxed(),
+                execute: QueuedPassFn::Main(EmptyPassExecutor::new_pass_fn()),
                 inputs: vec![],
                 outputs: vec![],
                 is_renderpass: false,
+                layer_count: 1,
+                view_mask: 0,
             },
         }
     }
@@ -271,10 +425,12 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassBuilder<'cb, D, U, A> {
             inner: Pass {
                 name: name.into(),
                 color: None,
-                execute: EmptyPassExecutor::new_boxed(),
+                execute: QueuedPassFn::Main(EmptyPassExecutor::new_pass_fn()),
                 inputs: vec![],
                 outputs: vec![],
                 is_renderpass: true,
+                layer_count: 1,
+                view_mask: 0,
             },
         }
     }
@@ -293,10 +449,40 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassBuilder<'cb, D, U, A> {
                 layout: vk::ImageLayout::PRESENT_SRC_KHR,
                 clear_value: None,
                 load_op: None,
+                store_op: None,
             }],
             outputs: vec![],
-            execute: EmptyPassExecutor::new_boxed(),
+            execute: QueuedPassFn::Main(EmptyPassExecutor::new_pass_fn()),
             is_renderpass: false,
+            layer_count: 1,
+            view_mask: 0,
+        }
+    }
+
+    /// Create a pass that transitions a resource to the state it is used in outside of the graph.
+    pub(crate) fn export(resource: &VirtualResource, state: ResourceState) -> Pass<'cb, D, U, A> {
+        Pass {
+            name: format!("_export_{}", resource.name()),
+            color: None,
+            inputs: vec![PassResource {
+                usage: ResourceUsage::External(state),
+                resource: resource.clone(),
+                stage: state.stage,
+                layout: match resource.resource_type() {
+                    ResourceType::Image => state.layout,
+                    ResourceType::Buffer | ResourceType::AccelerationStructure => {
+                        vk::ImageLayout::UNDEFINED
+                    }
+                },
+                clear_value: None,
+                load_op: None,
+                store_op: None,
+            }],
+            outputs: vec![],
+            execute: QueuedPassFn::Main(EmptyPassExecutor::new_pass_fn()),
+            is_renderpass: false,
+            layer_count: 1,
+            view_mask: 0,
         }
     }
 
@@ -307,6 +493,38 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassBuilder<'cb, D, U, A> {
         self
     }
 
+    /// Set the number of layers rendered to in this pass. Shaders can select the layer to render to with `gl_Layer`,
+    /// and load and store operations apply to every layer. Use [`vk::REMAINING_ARRAY_LAYERS`] to render to all layers of the
+    /// attachments. The image views bound to the attachments must include all layers that are rendered to.
+    /// # Errors
+    /// * Fails if this pass was not created using [`PassBuilder::render()`]
+    pub fn layer_count(mut self, count: u32) -> Result<Self> {
+        if !self.inner.is_renderpass {
+            return Err(Error::Uncategorized(
+                "Cannot set layer count of a pass that is not a renderpass",
+            )
+            .into());
+        }
+        self.inner.layer_count = count;
+        Ok(self)
+    }
+
+    /// Enable multiview rendering for this pass. Each set bit in `view_mask` is a layer of the attachments that is rendered to,
+    /// shaders can select per-view data with `gl_ViewIndex`. When the view mask is not zero, the layer count of the pass is ignored.
+    /// This requires the `multiview` feature to be enabled.
+    /// # Errors
+    /// * Fails if this pass was not created using [`PassBuilder::render()`]
+    pub fn view_mask(mut self, view_mask: u32) -> Result<Self> {
+        if !self.inner.is_renderpass {
+            return Err(Error::Uncategorized(
+                "Cannot set view mask of a pass that is not a renderpass",
+            )
+            .into());
+        }
+        self.inner.view_mask = view_mask;
+        Ok(self)
+    }
+
     /// Adds a color attachment to this pass. If [`vk::AttachmentLoadOp::CLEAR`] was specified, `clear` must not be None.
     /// # Errors
     /// * Fails if this pass was not created using [`PassBuilder::render()`]
@@ -340,6 +558,7 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassBuilder<'cb, D, U, A> {
             layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
             clear_value: None,
             load_op: None,
+            store_op: None,
         });
 
         self.inner.outputs.push(PassResource {
@@ -351,6 +570,7 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassBuilder<'cb, D, U, A> {
                 color: c,
             }),
             load_op: Some(op),
+            store_op: None,
         });
 
         Ok(self)
@@ -421,6 +641,7 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassBuilder<'cb, D, U, A> {
             layout: vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
             clear_value: None,
             load_op: None,
+            store_op: None,
         });
 
         self.inner.outputs.push(PassResource {
@@ -434,6 +655,138 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassBuilder<'cb, D, U, A> {
                 depth_stencil: c,
             }),
             load_op: Some(op),
+            store_op: None,
+        });
+
+        Ok(self)
+    }
+
+    /// Clear a stencil attachment with the specified clear value. The depth value of `clear` is ignored.
+    /// # Errors
+    /// * Fails if this pass was not created using [`PassBuilder::render()`]
+    pub fn clear_stencil_attachment(
+        self,
+        resource: &VirtualResource,
+        clear: ClearDepthStencil,
+    ) -> Result<Self> {
+        self.stencil_attachment(resource, AttachmentOps::CLEAR, Some(clear.into_vulkan()))
+    }
+
+    /// Load a stencil attachment
+    /// # Errors
+    /// * Fails if this pass was not created using [`PassBuilder::render()`]
+    pub fn load_stencil_attachment(self, resource: &VirtualResource) -> Result<Self> {
+        self.stencil_attachment(resource, AttachmentOps::LOAD, None)
+    }
+
+    /// Adds a stencil-only attachment to this pass. If [`vk::AttachmentLoadOp::CLEAR`] was specified, `clear` must not be None.
+    /// The attachment is used in [`vk::ImageLayout::STENCIL_ATTACHMENT_OPTIMAL`], which requires the
+    /// `separateDepthStencilLayouts` feature when the image also has a depth aspect.
+    /// # Errors
+    /// * Fails if this pass was not created using [`PassBuilder::render()`]
+    /// * Fails if the load op was [`vk::AttachmentLoadOp::CLEAR`], but `clear` was [`None`].
+    pub fn stencil_attachment(
+        self,
+        resource: &VirtualResource,
+        ops: AttachmentOps,
+        clear: Option<vk::ClearDepthStencilValue>,
+    ) -> Result<Self> {
+        self.depth_stencil_attachment_impl(
+            resource,
+            AttachmentType::Stencil,
+            ops,
+            vk::ImageLayout::STENCIL_ATTACHMENT_OPTIMAL,
+            clear,
+        )
+    }
+
+    /// Clear a combined depth-stencil attachment with the specified clear values
+    /// # Errors
+    /// * Fails if this pass was not created using [`PassBuilder::render()`]
+    pub fn clear_depth_stencil_attachment(
+        self,
+        resource: &VirtualResource,
+        clear: ClearDepthStencil,
+    ) -> Result<Self> {
+        self.depth_stencil_attachment(
+            resource,
+            AttachmentOps::CLEAR,
+            AttachmentOps::CLEAR,
+            Some(clear.into_vulkan()),
+        )
+    }
+
+    /// Load a combined depth-stencil attachment
+    /// # Errors
+    /// * Fails if this pass was not created using [`PassBuilder::render()`]
+    pub fn load_depth_stencil_attachment(self, resource: &VirtualResource) -> Result<Self> {
+        self.depth_stencil_attachment(resource, AttachmentOps::LOAD, AttachmentOps::LOAD, None)
+    }
+
+    /// Adds a combined depth-stencil attachment to this pass, with separate load and store operations for each aspect.
+    /// The image view bound to `resource` must include both the depth and stencil aspect.
+    /// If either load op is [`vk::AttachmentLoadOp::CLEAR`], `clear` must not be None.
+    /// # Errors
+    /// * Fails if this pass was not created using [`PassBuilder::render()`]
+    /// * Fails if either load op was [`vk::AttachmentLoadOp::CLEAR`], but `clear` was [`None`].
+    pub fn depth_stencil_attachment(
+        self,
+        resource: &VirtualResource,
+        depth: AttachmentOps,
+        stencil: AttachmentOps,
+        clear: Option<vk::ClearDepthStencilValue>,
+    ) -> Result<Self> {
+        if stencil.load_op == vk::AttachmentLoadOp::CLEAR && clear.is_none() {
+            return Err(anyhow::Error::from(Error::NoClearValue));
+        }
+        self.depth_stencil_attachment_impl(
+            resource,
+            AttachmentType::DepthStencil(stencil),
+            depth,
+            vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+            clear,
+        )
+    }
+
+    fn depth_stencil_attachment_impl(
+        mut self,
+        resource: &VirtualResource,
+        ty: AttachmentType,
+        ops: AttachmentOps,
+        layout: vk::ImageLayout,
+        clear: Option<vk::ClearDepthStencilValue>,
+    ) -> Result<Self> {
+        if !self.inner.is_renderpass {
+            return Err(Error::Uncategorized(
+                "Cannot attach stencil attachment to a pass that is not a renderpass",
+            )
+            .into());
+        }
+        if ops.load_op == vk::AttachmentLoadOp::CLEAR && clear.is_none() {
+            return Err(anyhow::Error::from(Error::NoClearValue));
+        }
+        self.inner.inputs.push(PassResource {
+            usage: ResourceUsage::Attachment(ty.clone()),
+            resource: resource.clone(),
+            // 'Load' operations on depth/stencil attachments happen in EARLY_FRAGMENT_TESTS.
+            stage: PipelineStage::EARLY_FRAGMENT_TESTS,
+            layout,
+            clear_value: None,
+            load_op: None,
+            store_op: None,
+        });
+
+        self.inner.outputs.push(PassResource {
+            usage: ResourceUsage::Attachment(ty),
+            resource: resource.upgrade(),
+            // Depth/stencil writes happen in LATE_FRAGMENT_TESTS.
+            stage: PipelineStage::LATE_FRAGMENT_TESTS,
+            layout,
+            clear_value: clear.map(|c| vk::ClearValue {
+                depth_stencil: c,
+            }),
+            load_op: Some(ops.load_op),
+            store_op: Some(ops.store_op),
         });
 
         Ok(self)
@@ -448,6 +801,7 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassBuilder<'cb, D, U, A> {
             layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
             clear_value: None,
             load_op: None,
+            store_op: None,
         });
 
         self.inner.outputs.push(PassResource {
@@ -457,29 +811,42 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassBuilder<'cb, D, U, A> {
             layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
             clear_value: None,
             load_op: Some(vk::AttachmentLoadOp::DONT_CARE),
+            store_op: None,
         });
 
         self
     }
 
-    /// Does a hardware MSAA resolve from `src` into `dst`, but for depth images
+    /// Does a hardware MSAA resolve from `src` into `dst`, but for depth images.
+    /// If `src` was declared as a combined depth-stencil attachment before this call, `dst` is used in the depth-stencil layout.
     pub fn resolve_depth(mut self, src: &VirtualResource, dst: &VirtualResource) -> Self {
+        let is_depth_stencil = self.inner.outputs.iter().any(|output| {
+            matches!(output.usage, ResourceUsage::Attachment(AttachmentType::DepthStencil(_)))
+                && output.resource.is_same_subresource(src)
+        });
+        let layout = if is_depth_stencil {
+            vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL
+        } else {
+            vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL
+        };
         self.inner.inputs.push(PassResource {
             usage: ResourceUsage::Attachment(AttachmentType::Resolve(src.clone())),
             resource: dst.clone(),
             stage: PipelineStage::COLOR_ATTACHMENT_OUTPUT, // RESOLVE is only for vkCmdResolve
-            layout: vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
+            layout,
             clear_value: None,
             load_op: None,
+            store_op: None,
         });
 
         self.inner.outputs.push(PassResource {
             usage: ResourceUsage::Attachment(AttachmentType::Resolve(src.clone())),
             resource: dst.upgrade(),
             stage: PipelineStage::COLOR_ATTACHMENT_OUTPUT, // RESOLVE is only for vkCmdResolve
-            layout: vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
+            layout,
             clear_value: None,
             load_op: Some(vk::AttachmentLoadOp::DONT_CARE),
+            store_op: None,
         });
 
         self
@@ -494,6 +861,7 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassBuilder<'cb, D, U, A> {
             layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
             clear_value: None,
             load_op: None,
+            store_op: None,
         });
         self
     }
@@ -507,6 +875,7 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassBuilder<'cb, D, U, A> {
             layout: vk::ImageLayout::GENERAL,
             clear_value: None,
             load_op: None,
+            store_op: None,
         });
         self.inner.outputs.push(PassResource {
             usage: ResourceUsage::ShaderWrite,
@@ -515,6 +884,7 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassBuilder<'cb, D, U, A> {
             layout: vk::ImageLayout::GENERAL,
             clear_value: None,
             load_op: None,
+            store_op: None,
         });
         self
     }
@@ -528,6 +898,153 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassBuilder<'cb, D, U, A> {
             layout: vk::ImageLayout::GENERAL,
             clear_value: None,
             load_op: None,
+            store_op: None,
+        });
+        self
+    }
+
+    /// Declare that a buffer will be used as a storage buffer that will be read from in the given pipeline stages.
+    pub fn read_storage_buffer(self, resource: &VirtualResource, stage: PipelineStage) -> Self {
+        self.read_buffer(resource, ResourceUsage::ShaderRead, stage)
+    }
+
+    /// Declare that a buffer will be used as a storage buffer that is written to in the given pipeline stages.
+    pub fn write_storage_buffer(
+        mut self,
+        resource: &VirtualResource,
+        stage: PipelineStage,
+    ) -> Self {
+        self.inner.inputs.push(PassResource {
+            usage: ResourceUsage::ShaderWrite,
+            resource: resource.clone(),
+            stage,
+            layout: vk::ImageLayout::UNDEFINED,
+            clear_value: None,
+            load_op: None,
+            store_op: None,
+        });
+        self.inner.outputs.push(PassResource {
+            usage: ResourceUsage::ShaderWrite,
+            resource: resource.upgrade(),
+            stage,
+            layout: vk::ImageLayout::UNDEFINED,
+            clear_value: None,
+            load_op: None,
+            store_op: None,
+        });
+        self
+    }
+
+    /// Declare that a buffer will be used as a uniform buffer in the given pipeline stages.
+    pub fn read_uniform_buffer(self, resource: &VirtualResource, stage: PipelineStage) -> Self {
+        self.read_buffer(resource, ResourceUsage::UniformRead, stage)
+    }
+
+    /// Declare that a buffer will be bound as a vertex buffer in this pass.
+    pub fn vertex_buffer(self, resource: &VirtualResource) -> Self {
+        self.read_buffer(resource, ResourceUsage::VertexRead, PipelineStage::VERTEX_ATTRIBUTE_INPUT)
+    }
+
+    /// Declare that a buffer will be bound as an index buffer in this pass.
+    pub fn index_buffer(self, resource: &VirtualResource) -> Self {
+        self.read_buffer(resource, ResourceUsage::IndexRead, PipelineStage::INDEX_INPUT)
+    }
+
+    /// Declare that a buffer will be used as the source of indirect draw or dispatch commands in this pass.
+    pub fn indirect_buffer(self, resource: &VirtualResource) -> Self {
+        self.read_buffer(resource, ResourceUsage::IndirectRead, PipelineStage::DRAW_INDIRECT)
+    }
+
+    /// Declare that an acceleration structure will be built or updated in this pass.
+    pub fn build_acceleration_structure(mut self, resource: &VirtualResource) -> Self {
+        let stage = PipelineStage::ACCELERATION_STRUCTURE_BUILD_KHR;
+        self.inner.inputs.push(PassResource {
+            usage: ResourceUsage::AccelerationStructureWrite,
+            resource: resource.clone(),
+            stage,
+            layout: vk::ImageLayout::UNDEFINED,
+            clear_value: None,
+            load_op: None,
+            store_op: None,
+        });
+        self.inner.outputs.push(PassResource {
+            usage: ResourceUsage::AccelerationStructureWrite,
+            resource: resource.upgrade(),
+            stage,
+            layout: vk::ImageLayout::UNDEFINED,
+            clear_value: None,
+            load_op: None,
+            store_op: None,
+        });
+        self
+    }
+
+    /// Declare that an acceleration structure will be read in the given pipeline stages. This is
+    /// [`PipelineStage::RAY_TRACING_SHADER_KHR`] for tracing rays, or [`PipelineStage::ACCELERATION_STRUCTURE_BUILD_KHR`]
+    /// for a bottom level acceleration structure used to build a top level one.
+    pub fn read_acceleration_structure(
+        self,
+        resource: &VirtualResource,
+        stage: PipelineStage,
+    ) -> Self {
+        self.read_buffer(resource, ResourceUsage::AccelerationStructureRead, stage)
+    }
+
+    /// Declare that a resource will be read by transfer commands in this pass, for example as the source of a copy or blit.
+    /// Images are transitioned to [`vk::ImageLayout::TRANSFER_SRC_OPTIMAL`].
+    pub fn transfer_src(mut self, resource: &VirtualResource) -> Self {
+        self.inner.inputs.push(PassResource {
+            usage: ResourceUsage::TransferRead,
+            resource: resource.clone(),
+            stage: PipelineStage::TRANSFER,
+            layout: transfer_layout(resource, vk::ImageLayout::TRANSFER_SRC_OPTIMAL),
+            clear_value: None,
+            load_op: None,
+            store_op: None,
+        });
+        self
+    }
+
+    /// Declare that a resource will be written by transfer commands in this pass, for example as the destination of a copy, blit or clear.
+    /// Images are transitioned to [`vk::ImageLayout::TRANSFER_DST_OPTIMAL`].
+    pub fn transfer_dst(mut self, resource: &VirtualResource) -> Self {
+        let layout = transfer_layout(resource, vk::ImageLayout::TRANSFER_DST_OPTIMAL);
+        self.inner.inputs.push(PassResource {
+            usage: ResourceUsage::TransferWrite,
+            resource: resource.clone(),
+            stage: PipelineStage::TRANSFER,
+            layout,
+            clear_value: None,
+            load_op: None,
+            store_op: None,
+        });
+        self.inner.outputs.push(PassResource {
+            usage: ResourceUsage::TransferWrite,
+            resource: resource.upgrade(),
+            stage: PipelineStage::TRANSFER,
+            layout,
+            clear_value: None,
+            load_op: None,
+            store_op: None,
+        });
+        self
+    }
+
+    fn read_buffer(
+        mut self,
+        resource: &VirtualResource,
+        usage: ResourceUsage,
+        stage: PipelineStage,
+    ) -> Self {
+        // Buffers and acceleration structures have no layout, so we leave it undefined. Barriers over them will never use it.
+        self.inner.inputs.push(PassResource {
+            usage,
+            resource: resource.clone(),
+            stage,
+            layout: vk::ImageLayout::UNDEFINED,
+            clear_value: None,
+            load_op: None,
+            store_op: None,
         });
         self
     }
@@ -546,7 +1063,14 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassBuilder<'cb, D, U, A> {
 
     /// Set the executor to be called when recording this pass.
     pub fn executor(mut self, exec: impl PassExecutor<D, U, A> + 'cb) -> Self {
-        self.inner.execute = Box::new(exec);
+        self.inner.execute = QueuedPassFn::Main(BoxedPassFn::Local(Box::new(exec)));
+        self
+    }
+
+    /// Set an executor that is `Send` to be called when recording this pass. Unlike [`PassBuilder::executor()`], this allows
+    /// recording the pass on another thread with `BuiltPassGraph::record_parallel()`.
+    pub fn send_executor(mut self, exec: impl PassExecutor<D, U, A> + Send + 'cb) -> Self {
+        self.inner.execute = QueuedPassFn::Main(BoxedPassFn::Send(Box::new(exec)));
         self
     }
 
@@ -561,7 +1085,87 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassBuilder<'cb, D, U, A> {
                 &mut U,
             ) -> PassFnResult<'q, D, A>
             + 'cb, {
-        self.inner.execute = Box::new(exec);
+        self.inner.execute = QueuedPassFn::Main(BoxedPassFn::Local(Box::new(exec)));
+        self
+    }
+
+    /// Set the executor to be called when recording this pass, from a function that is `Send`.
+    /// Unlike [`PassBuilder::execute_fn()`], this allows recording the pass on another thread with `BuiltPassGraph::record_parallel()`.
+    pub fn execute_send_fn<F>(mut self, exec: F) -> Self
+    where
+        F: for<'q> FnMut(
+                IncompleteCommandBuffer<'q, D, A>,
+                &mut LocalPool<A>,
+                &PhysicalResourceBindings,
+                &mut U,
+            ) -> PassFnResult<'q, D, A>
+            + Send
+            + 'cb, {
+        self.inner.execute = QueuedPassFn::Main(BoxedPassFn::Send(Box::new(exec)));
+        self
+    }
+
+    /// Set the executor to be called when recording this pass, and execute this pass on a compute queue.
+    /// When the graph is recorded using [`BuiltPassGraph::record_multi_queue()`](crate::graph::pass_graph::BuiltPassGraph::record_multi_queue),
+    /// this pass can overlap with passes on the main queue. Render passes cannot be executed on a compute queue.
+    pub fn async_compute_fn<F>(mut self, exec: F) -> Self
+    where
+        F: for<'q> FnMut(
+                IncompleteCommandBuffer<'q, domain::Compute, A>,
+                &mut LocalPool<A>,
+                &PhysicalResourceBindings,
+                &mut U,
+            ) -> PassFnResult<'q, domain::Compute, A>
+            + 'cb, {
+        self.inner.execute = QueuedPassFn::Compute(BoxedPassFn::Local(Box::new(exec)));
+        self
+    }
+
+    /// Same as [`PassBuilder::async_compute_fn()`], but from a function that is `Send`, so the pass can also be
+    /// recorded on another thread with `BuiltPassGraph::record_parallel()`.
+    pub fn async_compute_send_fn<F>(mut self, exec: F) -> Self
+    where
+        F: for<'q> FnMut(
+                IncompleteCommandBuffer<'q, domain::Compute, A>,
+                &mut LocalPool<A>,
+                &PhysicalResourceBindings,
+                &mut U,
+            ) -> PassFnResult<'q, domain::Compute, A>
+            + Send
+            + 'cb, {
+        self.inner.execute = QueuedPassFn::Compute(BoxedPassFn::Send(Box::new(exec)));
+        self
+    }
+
+    /// Set the executor to be called when recording this pass, and execute this pass on a transfer queue.
+    /// When the graph is recorded using [`BuiltPassGraph::record_multi_queue()`](crate::graph::pass_graph::BuiltPassGraph::record_multi_queue),
+    /// this pass can overlap with passes on the main queue. Render passes cannot be executed on a transfer queue.
+    pub fn async_transfer_fn<F>(mut self, exec: F) -> Self
+    where
+        F: for<'q> FnMut(
+                IncompleteCommandBuffer<'q, domain::Transfer, A>,
+                &mut LocalPool<A>,
+                &PhysicalResourceBindings,
+                &mut U,
+            ) -> PassFnResult<'q, domain::Transfer, A>
+            + 'cb, {
+        self.inner.execute = QueuedPassFn::Transfer(BoxedPassFn::Local(Box::new(exec)));
+        self
+    }
+
+    /// Same as [`PassBuilder::async_transfer_fn()`], but from a function that is `Send`, so the pass can also be
+    /// recorded on another thread with `BuiltPassGraph::record_parallel()`.
+    pub fn async_transfer_send_fn<F>(mut self, exec: F) -> Self
+    where
+        F: for<'q> FnMut(
+                IncompleteCommandBuffer<'q, domain::Transfer, A>,
+                &mut LocalPool<A>,
+                &PhysicalResourceBindings,
+                &mut U,
+            ) -> PassFnResult<'q, domain::Transfer, A>
+            + Send
+            + 'cb, {
+        self.inner.execute = QueuedPassFn::Transfer(BoxedPassFn::Send(Box::new(exec)));
         self
     }
 
@@ -571,6 +1175,92 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassBuilder<'cb, D, U, A> {
     }
 }
 
+/// Get the layout a resource used by transfer commands should be in. Only images have a layout, so this is undefined for other resources.
+fn transfer_layout(resource: &VirtualResource, layout: vk::ImageLayout) -> vk::ImageLayout {
+    match resource.resource_type() {
+        ResourceType::Image => layout,
+        ResourceType::Buffer | ResourceType::AccelerationStructure => vk::ImageLayout::UNDEFINED,
+    }
+}
+
+impl<'cb, D: ExecutionDomain + TransferSupport, U, A: Allocator> PassBuilder<'cb, D, U, A> {
+    /// Create a pass that copies the entire `src` buffer to the `dst` buffer.
+    /// The new version of `dst` can be obtained with [`Pass::output()`].
+    pub fn copy_buffer(
+        name: impl Into<String>,
+        src: &VirtualResource,
+        dst: &VirtualResource,
+    ) -> Pass<'cb, D, U, A> {
+        let (src_resource, dst_resource) = (src.clone(), dst.clone());
+        PassBuilder::new(name)
+            .transfer_src(src)
+            .transfer_dst(dst)
+            .execute_send_fn(move |cmd, _, bindings, _| {
+                let src = bindings.resolve_buffer(&src_resource)?;
+                let dst = bindings.resolve_buffer(&dst_resource)?;
+                cmd.copy_buffer(src, dst)
+            })
+            .build()
+    }
+
+    /// Create a pass that copies the `src` buffer to the `dst` image. The buffer must be tightly packed and hold the
+    /// entire image view.
+    /// The new version of `dst` can be obtained with [`Pass::output()`].
+    pub fn copy_buffer_to_image(
+        name: impl Into<String>,
+        src: &VirtualResource,
+        dst: &VirtualResource,
+    ) -> Pass<'cb, D, U, A> {
+        let (src_resource, dst_resource) = (src.clone(), dst.clone());
+        PassBuilder::new(name)
+            .transfer_src(src)
+            .transfer_dst(dst)
+            .execute_send_fn(move |cmd, _, bindings, _| {
+                let src = bindings.resolve_buffer(&src_resource)?;
+                let dst = bindings.resolve_image(&dst_resource)?;
+                cmd.copy_buffer_to_image(src, dst)
+            })
+            .build()
+    }
+}
+
+impl<'cb, D: ExecutionDomain + GfxSupport, U, A: Allocator> PassBuilder<'cb, D, U, A> {
+    /// Create a pass that blits the entire `src` image to the entire `dst` image, scaling it with `filter` if the sizes differ.
+    /// The new version of `dst` can be obtained with [`Pass::output()`].
+    /// # Example
+    /// ```
+    /// use phobos::prelude::*;
+    ///
+    /// // Blit an offscreen image to the swapchain, and present it.
+    /// let blit = PassBuilder::blit("blit", &offscreen, &swapchain, vk::Filter::LINEAR);
+    /// let present = PassBuilder::present("present", blit.output(&swapchain).unwrap());
+    /// ```
+    pub fn blit(
+        name: impl Into<String>,
+        src: &VirtualResource,
+        dst: &VirtualResource,
+        filter: vk::Filter,
+    ) -> Pass<'cb, D, U, A> {
+        let (src_resource, dst_resource) = (src.clone(), dst.clone());
+        PassBuilder::new(name)
+            .transfer_src(src)
+            .transfer_dst(dst)
+            .execute_send_fn(move |cmd, _, bindings, _| {
+                let src = bindings.resolve_image(&src_resource)?;
+                let dst = bindings.resolve_image(&dst_resource)?;
+                let extent = |image: &ImageView| vk::Offset3D {
+                    x: image.width() as i32,
+                    y: image.height() as i32,
+                    z: image.depth() as i32,
+                };
+                let src_offsets = [vk::Offset3D::default(), extent(src)];
+                let dst_offsets = [vk::Offset3D::default(), extent(dst)];
+                Ok(cmd.blit_image(src, dst, &src_offsets, &dst_offsets, filter))
+            })
+            .build()
+    }
+}
+
 /// Holds virtual resources needed to declare a FSR2 dispatch.
 #[cfg(feature = "fsr2")]
 #[derive(Debug, Clone)]
@@ -654,7 +1344,7 @@ impl<'cb, D: ExecutionDomain + ComputeSupport, U, A: Allocator> PassBuilder<'cb,
                 PipelineStage::COMPUTE_SHADER,
             )
             .write_storage_image(&resources.output, PipelineStage::COMPUTE_SHADER)
-            .execute_fn(move |cmd, _, bindings, _| {
+            .execute_send_fn(move |cmd, _, bindings, _| {
                 let resolved_resources = resources.resolve(bindings)?;
                 let mut fsr2 = device.fsr2_context();
                 fsr2.dispatch(&descr, &resolved_resources, &cmd)?;
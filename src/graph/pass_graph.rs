@@ -1,7 +1,7 @@
 //! The pass graph module holds the render graph implementation.
 
 use std::collections::hash_map::{DefaultHasher, Entry};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Display, Formatter};
 use std::hash::{Hash, Hasher};
 use std::ops::{Deref, DerefMut};
@@ -9,14 +9,23 @@ use std::ops::{Deref, DerefMut};
 use anyhow::Result;
 use ash::vk;
 use petgraph::{Direction, Graph};
+use petgraph::algo::has_path_connecting;
 use petgraph::dot::Dot;
 use petgraph::graph::NodeIndex;
 use petgraph::prelude::EdgeRef;
 
 use crate::{Allocator, DefaultAllocator, Error};
-use crate::graph::pass::{BoxedPassFn, EmptyPassExecutor, Pass};
-use crate::graph::resource::ResourceUsage;
+use crate::graph::cache::{
+    CompiledNode, CompiledPassGraph, GraphStructure, PassGraphCache, PassStructure,
+    ResourceStructure,
+};
+use crate::graph::pass::{EmptyPassExecutor, Pass, PassBuilder, PassQueue, QueuedPassFn};
+use crate::graph::profiler::GraphProfiler;
+use crate::graph::resource::{ResourceState, ResourceType, ResourceUsage};
 use crate::graph::task_graph::{Barrier, Node, Resource, Task, TaskGraph};
+use crate::graph::transient::{
+    TransientBufferInfo, TransientImageInfo, TransientResource, TransientResourceInfo,
+};
 use crate::graph::virtual_resource::{HashedResource, VirtualResource};
 use crate::pipeline::PipelineStage;
 use crate::sync::domain::ExecutionDomain;
@@ -32,6 +41,7 @@ pub struct PassResource {
     #[derivative(Debug = "ignore")]
     pub(crate) clear_value: Option<vk::ClearValue>,
     pub(crate) load_op: Option<vk::AttachmentLoadOp>,
+    pub(crate) store_op: Option<vk::AttachmentStoreOp>,
 }
 
 /// GPU barrier in a task graph. Directly translates to `vkCmdPipelineBarrier()`.
@@ -50,8 +60,12 @@ pub struct PassNode<'cb, R: Resource, D: ExecutionDomain, U = (), A: Allocator =
     pub(crate) color: Option<[f32; 4]>,
     pub(crate) inputs: Vec<R>,
     pub(crate) outputs: Vec<R>,
-    pub(crate) execute: BoxedPassFn<'cb, D, U, A>,
+    pub(crate) execute: QueuedPassFn<'cb, D, U, A>,
     pub(crate) is_renderpass: bool,
+    pub(crate) layer_count: u32,
+    pub(crate) view_mask: u32,
+    /// Position of this pass in the order passes were added to the graph. The source node has index 0.
+    pub(crate) index: usize,
 }
 
 pub(crate) type PassGraphInner<'cb, D, U, A> = Graph<
@@ -59,7 +73,8 @@ pub(crate) type PassGraphInner<'cb, D, U, A> = Graph<
     <PassResource as Resource>::Uid,
 >;
 
-/// Pass graph, used for synchronizing resources over a single queue.
+/// Pass graph, used for synchronizing resources over the main queue of the execution domain `D`, and optionally
+/// over async compute and transfer queues.
 pub struct PassGraph<'cb, D: ExecutionDomain, U = (), A: Allocator = DefaultAllocator> {
     pub(crate) graph:
     TaskGraph<PassResource, PassResourceBarrier, PassNode<'cb, PassResource, D, U, A>>,
@@ -69,11 +84,32 @@ pub struct PassGraph<'cb, D: ExecutionDomain, U = (), A: Allocator = DefaultAllo
     source: NodeIndex,
     swapchain_final: VirtualResource,
     last_usages: HashMap<String, (usize, PipelineStage)>,
+    pub(crate) transients: Vec<TransientResource>,
+    exports: Vec<VirtualResource>,
+    /// Names of all sub-graphs added to this graph.
+    pub(crate) subgraphs: HashSet<String>,
+    pub(crate) histories: Vec<VirtualResource>,
+    imports: Vec<(VirtualResource, ResourceState)>,
+}
+
+/// A part of a pass graph that is recorded into a single command buffer on a single queue.
+#[derive(Debug, Clone)]
+pub(crate) struct QueueSegment {
+    pub(crate) queue: PassQueue,
+    pub(crate) nodes: Vec<NodeIndex>,
+    /// Segments that must complete before this segment can start, together with the stage that waits on them.
+    pub(crate) waits: Vec<(usize, PipelineStage)>,
+    /// Barriers on other queues that consume resources produced in this segment.
+    pub(crate) releases: Vec<NodeIndex>,
 }
 
 /// A completely built pass graph, ready for recording.
 pub struct BuiltPassGraph<'cb, D: ExecutionDomain, U = (), A: Allocator = DefaultAllocator> {
     graph: PassGraph<'cb, D, U, A>,
+    /// Minimum number of passes between a barrier and the pass that waits on it to record it as a split barrier.
+    pub(crate) split_distance: Option<usize>,
+    /// Profiler that measures the GPU time of every pass when the graph is recorded.
+    pub(crate) profiler: Option<GraphProfiler>,
 }
 
 impl<'cb, D: ExecutionDomain, U, A: Allocator> Deref for BuiltPassGraph<'cb, D, U, A> {
@@ -93,11 +129,74 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> DerefMut for BuiltPassGraph<'cb,
     }
 }
 
+impl<'cb, D: ExecutionDomain, U, A: Allocator> BuiltPassGraph<'cb, D, U, A> {
+    /// Record barriers as split barriers when at least `min_distance` passes are recorded between the barrier and the
+    /// first pass that waits on it. A split barrier signals an event with `vkCmdSetEvent2` right after the passes it waits on,
+    /// and waits on it with `vkCmdWaitEvents2` right before the pass that needs it, so the passes in between can overlap with it.
+    /// Events are taken from the [`ResourcePool`](crate::pool::ResourcePool) of the local pool the graph is recorded with.
+    ///
+    /// This applies to [`RecordGraphToCommandBuffer::record()`](crate::graph::record::RecordGraphToCommandBuffer::record) and
+    /// `BuiltPassGraph::record_parallel()`. Graphs recorded on multiple queues always use pipeline barriers.
+    /// Command buffers for queues without graphics or compute support cannot use events, so they always use pipeline barriers too.
+    pub fn split_barriers(mut self, min_distance: usize) -> Self {
+        self.split_distance = Some(min_distance);
+        self
+    }
+
+    /// Measure the GPU time of every pass with `profiler` when this graph is recorded. Timestamps are written before and after
+    /// every pass, and read back by the profiler a few frames later. See the [`profiler`](crate::graph::profiler) module
+    /// documentation for more information.
+    pub fn profile(mut self, profiler: &GraphProfiler) -> Self {
+        self.profiler = Some(profiler.clone());
+        self
+    }
+
+    // Strip all pass executors from this graph, so its structure can be stored in a cache.
+    // `structure` is the structure of the graph before it was built.
+    fn compile(&self, structure: GraphStructure) -> CompiledPassGraph {
+        let graph = self.graph.graph.graph.map(
+            |_, node| match node {
+                Node::Task(task) => {
+                    let occurrence = structure.passes[..task.index]
+                        .iter()
+                        .filter(|pass| pass.name == task.identifier)
+                        .count();
+                    CompiledNode::Task(task.identifier.clone(), occurrence)
+                }
+                Node::Barrier(barrier) => CompiledNode::Barrier(barrier.clone()),
+                Node::_Unreachable(_) => {
+                    unreachable!()
+                }
+            },
+            |_, edge| *edge,
+        );
+        let Node::Task(source) = self.graph.graph.graph.node_weight(self.graph.source).unwrap() else { panic!("Graph does not have a source node"); };
+        CompiledPassGraph {
+            structure,
+            graph,
+            source_outputs: source.outputs.clone(),
+            transients: self.graph.transients.clone(),
+        }
+    }
+}
+
 impl PassResource {
     /// Get the virtual resource associated with this pass resource.
     pub fn virtual_resource(&self) -> &VirtualResource {
         &self.resource
     }
+
+    /// Get everything about this resource that affects how a graph is built.
+    fn structure(&self) -> ResourceStructure {
+        ResourceStructure {
+            usage: self.usage.clone(),
+            resource: self.resource.clone(),
+            stage: self.stage,
+            layout: self.layout,
+            load_op: self.load_op,
+            store_op: self.store_op,
+        }
+    }
 }
 
 impl Barrier<PassResource> for PassResourceBarrier {
@@ -174,6 +273,11 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassGraph<'cb, D, U, A> {
             source: NodeIndex::default(),
             swapchain_final: VirtualResource::final_image("swapchain"),
             last_usages: Default::default(),
+            transients: Vec::new(),
+            exports: Vec::new(),
+            subgraphs: HashSet::new(),
+            histories: Vec::new(),
+            imports: Vec::new(),
         };
 
         // insert dummy 'source' node. This node produces all initial inputs and is used for start of frame sync.
@@ -184,8 +288,11 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassGraph<'cb, D, U, A> {
                 color: None,
                 inputs: vec![],
                 outputs: vec![],
-                execute: EmptyPassExecutor::new_boxed(),
+                execute: QueuedPassFn::Main(EmptyPassExecutor::new_pass_fn()),
                 is_renderpass: false,
+                layer_count: 1,
+                view_mask: 0,
+                index: 0,
             })
             .unwrap();
         graph.source = graph.graph.graph.node_indices().next().unwrap();
@@ -195,7 +302,17 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassGraph<'cb, D, U, A> {
     /// Add a pass to a task graph. To obtain a pass, use the [`PassBuilder`](crate::graph::pass::PassBuilder)
     /// # Errors
     /// - Fails if adding the pass results in a cyclic dependency in the graph.
+    /// - Fails if the pass is a render pass that is not executed on the main queue.
+    /// - Fails with [`Error::OverlappingSubresources`] if the pass uses a part of an image that overlaps with a different part of it
+    ///   used in the graph, see [`VirtualResource::subresource()`].
     pub fn add_pass(mut self, pass: Pass<'cb, D, U, A>) -> Result<Self> {
+        if pass.is_renderpass && pass.queue() != PassQueue::Main {
+            return Err(
+                Error::Uncategorized("Render passes must be executed on the main queue").into()
+            );
+        }
+        self.check_overlapping_subresources(&pass)?;
+
         {
             // Before adding this pass, we need to add every initial input (one with no '+' signs in its uid) to the output of the source node.
             // Note that we dont actually fill the pipeline stages yet, we do that later
@@ -209,6 +326,7 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassGraph<'cb, D, U, A> {
                         layout: vk::ImageLayout::UNDEFINED,
                         clear_value: None,
                         load_op: None,
+                        store_op: None,
                     })
                 }
             }
@@ -222,6 +340,7 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassGraph<'cb, D, U, A> {
         //    self.update_last_usage(&output.resource, output.stage)?;
         //}
 
+        let index = self.graph.graph.node_count();
         self.graph.add_task(PassNode {
             identifier: pass.name,
             color: pass.color,
@@ -229,25 +348,234 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassGraph<'cb, D, U, A> {
             outputs: pass.outputs,
             execute: pass.execute,
             is_renderpass: pass.is_renderpass,
+            layer_count: pass.layer_count,
+            view_mask: pass.view_mask,
+            index,
         })?;
 
         Ok(self)
     }
 
+    // Overlapping parts of an image are tracked as separate resources, so no dependencies or barriers would be created between them.
+    fn check_overlapping_subresources(&self, pass: &Pass<'cb, D, U, A>) -> Result<()> {
+        let used = self
+            .graph
+            .graph
+            .node_weights()
+            .filter_map(|node| match node {
+                Node::Task(task) => Some(task.inputs.iter().chain(task.outputs.iter())),
+                _ => None,
+            })
+            .flatten()
+            .chain(pass.inputs.iter())
+            .chain(pass.outputs.iter())
+            .collect::<Vec<_>>();
+        for resource in pass.inputs.iter().chain(pass.outputs.iter()) {
+            if let Some(other) = used
+                .iter()
+                .find(|other| resource.resource.overlaps(&other.resource))
+            {
+                return Err(Error::OverlappingSubresources {
+                    pass: pass.name.clone(),
+                    resource: resource.resource.uid(),
+                    other: other.resource.uid(),
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Declare a transient image. This image will be created by the graph when it is recorded, so it does not
+    /// need to be bound in the [`PhysicalResourceBindings`](crate::PhysicalResourceBindings).
+    /// Transient images whose lifetimes do not overlap may share memory.
+    /// See the [`transient`](crate::graph::transient) module documentation for more information.
+    /// # Errors
+    /// * Fails if `resource` is not an image resource.
+    /// * Fails if `resource` was already declared as a transient resource.
+    pub fn transient_image(
+        self,
+        resource: &VirtualResource,
+        info: TransientImageInfo,
+    ) -> Result<Self> {
+        if resource.resource_type() != ResourceType::Image {
+            return Err(Error::Uncategorized("Transient image must be an image resource").into());
+        }
+        self.add_transient(resource, TransientResourceInfo::Image(info))
+    }
+
+    /// Declare a transient buffer. This buffer will be created by the graph when it is recorded, so it does not
+    /// need to be bound in the [`PhysicalResourceBindings`](crate::PhysicalResourceBindings).
+    /// Transient buffers whose lifetimes do not overlap may share memory.
+    /// See the [`transient`](crate::graph::transient) module documentation for more information.
+    /// # Errors
+    /// * Fails if `resource` is not a buffer resource.
+    /// * Fails if `resource` was already declared as a transient resource.
+    pub fn transient_buffer(
+        self,
+        resource: &VirtualResource,
+        info: TransientBufferInfo,
+    ) -> Result<Self> {
+        if resource.resource_type() != ResourceType::Buffer {
+            return Err(Error::Uncategorized("Transient buffer must be a buffer resource").into());
+        }
+        self.add_transient(resource, TransientResourceInfo::Buffer(info))
+    }
+
+    /// Mark a resource as exported. Exported resources are used outside of the graph, for example in a later frame
+    /// or by another submission. Passes that write to an exported resource are never culled when building the graph.
+    /// Note that the swapchain is always considered to be exported.
+    pub fn export(mut self, resource: &VirtualResource) -> Self {
+        self.exports.push(resource.clone());
+        self
+    }
+
+    /// Mark a resource as exported, and transition it to `state` at the end of the graph. `resource` must be the final version
+    /// of the resource in the graph, just like the swapchain for the [present pass](crate::PassBuilder::present).
+    /// If the state names another queue family, the graph records the release half of an ownership transfer for images,
+    /// and the acquire half must be recorded on that queue.
+    /// # Errors
+    /// * Fails if `resource` was already exported with a final state.
+    /// * Fails if the state names a queue family, but `resource` is not an image.
+    pub fn export_with_state(
+        self,
+        resource: &VirtualResource,
+        state: ResourceState,
+    ) -> Result<Self> {
+        check_ownership_transfer(resource, &state)?;
+        let pass = PassBuilder::export(resource, state);
+        let exists = self
+            .graph
+            .graph
+            .node_weights()
+            .any(|node| matches!(node, Node::Task(task) if task.identifier == pass.name));
+        if exists {
+            return Err(
+                Error::Uncategorized("Resource was already exported with a final state").into()
+            );
+        }
+        self.export(resource).add_pass(pass)
+    }
+
+    /// Import a resource that is already in use when the graph starts. Instead of discarding its contents,
+    /// the graph transitions it from `state` before its first use. If the state names another queue family, the graph records
+    /// the acquire half of an ownership transfer for images, and the release half must be recorded on that queue before the graph is submitted.
+    /// # Errors
+    /// * Fails if `resource` is not the initial version of a resource.
+    /// * Fails if `resource` was already imported, or declared as a transient resource.
+    /// * Fails if the state names a queue family, but `resource` is not an image.
+    pub fn import(mut self, resource: &VirtualResource, state: ResourceState) -> Result<Self> {
+        if !resource.is_source() {
+            return Err(Error::Uncategorized(
+                "Imported resource must be the initial version of a resource",
+            )
+            .into());
+        }
+        check_ownership_transfer(resource, &state)?;
+        if self
+            .imports
+            .iter()
+            .any(|(import, _)| import.is_associated_with(resource))
+        {
+            return Err(Error::Uncategorized("Resource was already imported").into());
+        }
+        if self
+            .transients
+            .iter()
+            .any(|transient| transient.resource.is_associated_with(resource))
+        {
+            return Err(Error::Uncategorized("Transient resources cannot be imported").into());
+        }
+        self.imports.push((resource.clone(), state));
+        Ok(self)
+    }
+
     /// Builds the task graph so it can be recorded into a command buffer.
+    ///
+    /// Passes that do not contribute to the output of the graph are culled. The output of the graph is made up of all
+    /// [exported](PassGraph::export) resources (including the swapchain), and all passes without any outputs, such
+    /// as the [present pass](crate::PassBuilder::present).
+    ///
+    /// The initial version of each resource starts with undefined contents, except for [imported](PassGraph::import) resources
+    /// and [history images](PassGraph::history_image).
     /// # Errors
     /// * Fails if there are multiple usages of the same resource, which makes it impossible to
     ///   construct an unambiguous graph.
     pub fn build(mut self) -> Result<BuiltPassGraph<'cb, D, U, A>> {
+        self.cull_dead_passes()?;
         self.set_source_stages()?;
+        self.set_history_sources()?;
         self.graph.create_barrier_nodes();
         self.merge_identical_barriers()?;
+        self.alias_transients()?;
 
         Ok(BuiltPassGraph {
             graph: self,
+            split_distance: None,
+            profiler: None,
         })
     }
 
+    /// Builds the task graph, reusing the result of building a graph with the same structure if it is stored in `cache`.
+    /// Otherwise, the graph is built as in [`PassGraph::build()`] and the result is stored in the cache.
+    /// See the [`cache`](crate::graph::cache) module documentation for more information.
+    /// # Errors
+    /// * Fails if the graph could not be built, see [`PassGraph::build()`].
+    pub fn build_cached(self, cache: &mut PassGraphCache) -> Result<BuiltPassGraph<'cb, D, U, A>> {
+        let structure = self.structure();
+        let hash = Self::hash_structure(&structure);
+        if let Some(compiled) = cache.get(hash, &structure) {
+            return self.build_from_compiled(compiled);
+        }
+
+        let built = self.build()?;
+        cache.insert(hash, built.compile(structure));
+        Ok(built)
+    }
+
+    /// Get a hash of the structure of this graph. This includes all passes with their names, queues and resource usages,
+    /// transient resources, imported and exported resources and history images, but not pass executors or clear values.
+    /// Two graphs with the same structure are built into the same graph.
+    pub fn structure_hash(&self) -> u64 {
+        Self::hash_structure(&self.structure())
+    }
+
+    fn hash_structure(structure: &GraphStructure) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        structure.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    // Get everything about this graph that affects how it is built.
+    fn structure(&self) -> GraphStructure {
+        let passes = self
+            .graph
+            .graph
+            .node_weights()
+            .filter_map(|node| match node {
+                Node::Task(task) => Some(PassStructure {
+                    name: task.identifier.clone(),
+                    queue: task.execute.queue(),
+                    is_renderpass: task.is_renderpass,
+                    inputs: task.inputs.iter().map(PassResource::structure).collect(),
+                    outputs: task.outputs.iter().map(PassResource::structure).collect(),
+                }),
+                _ => None,
+            })
+            .collect();
+        GraphStructure {
+            passes,
+            transients: self
+                .transients
+                .iter()
+                .map(|transient| (transient.resource.clone(), transient.info))
+                .collect(),
+            exports: self.exports.clone(),
+            histories: self.histories.clone(),
+            imports: self.imports.clone(),
+        }
+    }
+
     /// Returns the internal task graph structure, useful for creating debug visualizations.
     pub fn task_graph(
         &self,
@@ -262,17 +590,43 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassGraph<'cb, D, U, A> {
     }
 
     /// Get the source node of the graph.
-    #[allow(dead_code)]
     pub(crate) fn source(&self) -> NodeIndex {
         self.source
     }
 
+    fn add_transient(
+        mut self,
+        resource: &VirtualResource,
+        info: TransientResourceInfo,
+    ) -> Result<Self> {
+        if self
+            .transients
+            .iter()
+            .any(|transient| transient.resource.is_associated_with(resource))
+        {
+            return Err(Error::Uncategorized("Resource was already declared as transient").into());
+        }
+        if self
+            .imports
+            .iter()
+            .any(|(import, _)| import.is_associated_with(resource))
+        {
+            return Err(Error::Uncategorized("Imported resources cannot be transient").into());
+        }
+        self.transients.push(TransientResource {
+            resource: resource.clone(),
+            info,
+            slot: 0,
+        });
+        Ok(self)
+    }
+
     fn update_last_usage(
         &mut self,
         resource: &VirtualResource,
         stage: PipelineStage,
     ) -> Result<()> {
-        let entry = self.last_usages.entry(resource.name().to_owned());
+        let entry = self.last_usages.entry(resource.binding_key());
         match entry {
             Entry::Occupied(mut entry) => {
                 let version = resource.version();
@@ -334,18 +688,251 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassGraph<'cb, D, U, A> {
             .unwrap())
     }
 
-    /// Set source barrier stages to the *last* usage in the frame, for cross-frame sync
+    /// Get the queue a node in the graph is executed on. Barriers are executed on the queue of the pass that
+    /// consumes their resource.
+    pub(crate) fn node_queue(graph: &PassGraphInner<D, U, A>, node: NodeIndex) -> PassQueue {
+        match graph.node_weight(node).unwrap() {
+            Node::Task(task) => task.execute.queue(),
+            Node::Barrier(_) => graph
+                .neighbors_directed(node, Direction::Outgoing)
+                .next()
+                .map(|consumer| Self::node_queue(graph, consumer))
+                .unwrap_or(PassQueue::Main),
+            Node::_Unreachable(_) => {
+                unreachable!()
+            }
+        }
+    }
+
+    /// Returns true if any pass in this graph is executed on a queue other than the main queue.
+    pub fn is_multi_queue(&self) -> bool {
+        self.graph
+            .graph
+            .node_indices()
+            .any(|node| Self::node_queue(&self.graph.graph, node) != PassQueue::Main)
+    }
+
+    /// Split the graph into segments that can each be recorded into a single command buffer and submitted to a single queue.
+    /// The segments are returned in an order in which they can be submitted.
+    pub(crate) fn queue_schedule(&self) -> Result<Vec<QueueSegment>> {
+        let graph = &self.graph.graph;
+        let order = petgraph::algo::toposort(graph, None).map_err(|_| Error::GraphHasCycle)?;
+
+        let mut segments: Vec<QueueSegment> = Vec::new();
+        let mut segment_of: HashMap<NodeIndex, usize> = HashMap::new();
+        // The segment on each queue that nodes are currently being added to.
+        let mut open: HashMap<PassQueue, usize> = HashMap::new();
+        for node in order {
+            // The source node does not record any commands.
+            if node == self.source {
+                continue;
+            }
+            let queue = Self::node_queue(graph, node);
+            // Find all dependencies of this node that are executed on another queue. The segments these are in
+            // must be submitted before we can wait on them, so they are closed.
+            let dependencies = graph
+                .neighbors_directed(node, Direction::Incoming)
+                .filter_map(|parent| segment_of.get(&parent).map(|segment| (parent, *segment)))
+                .filter(|(_, segment)| segments[*segment].queue != queue)
+                .collect::<Vec<_>>();
+            for (_, segment) in &dependencies {
+                open.retain(|_, open| open != segment);
+            }
+
+            // Semaphore waits happen at the start of a submission, so a new segment is needed if there are any dependencies.
+            let segment = match open.get(&queue) {
+                Some(segment) if dependencies.is_empty() => *segment,
+                _ => {
+                    segments.push(QueueSegment {
+                        queue,
+                        nodes: vec![],
+                        waits: vec![],
+                        releases: vec![],
+                    });
+                    open.insert(queue, segments.len() - 1);
+                    segments.len() - 1
+                }
+            };
+
+            let stage = match graph.node_weight(node).unwrap() {
+                Node::Barrier(barrier) => barrier.dst_stage,
+                _ => PipelineStage::ALL_COMMANDS,
+            };
+            for (parent, dependency) in dependencies {
+                match segments[segment]
+                    .waits
+                    .iter_mut()
+                    .find(|(segment, _)| *segment == dependency)
+                {
+                    Some((_, wait_stage)) => *wait_stage |= stage,
+                    None => segments[segment].waits.push((dependency, stage)),
+                }
+                // The producer of a resource may need to release ownership of it to the queue of this barrier.
+                // Barriers on the initial version of a resource discard its contents, so these never need a release.
+                if let (Node::Task(_), Node::Barrier(barrier)) =
+                    (graph.node_weight(parent).unwrap(), graph.node_weight(node).unwrap())
+                {
+                    if !barrier.resource.resource.is_source() {
+                        segments[dependency].releases.push(node);
+                    }
+                }
+            }
+
+            segments[segment].nodes.push(node);
+            segment_of.insert(node, segment);
+        }
+
+        Ok(segments)
+    }
+
+    // Build this graph by taking the structure of a previously built graph, and moving the passes of this graph into it.
+    // The structure of both graphs must be identical.
+    fn build_from_compiled(
+        mut self,
+        compiled: &CompiledPassGraph,
+    ) -> Result<BuiltPassGraph<'cb, D, U, A>> {
+        // Passes are looked up by their name. Passes with the same name are told apart by the order they were added in,
+        // which is the order of the nodes before building.
+        let (nodes, _) = std::mem::take(&mut self.graph.graph).into_nodes_edges();
+        let mut occurrences = HashMap::new();
+        let mut tasks = HashMap::new();
+        for node in nodes {
+            let Node::Task(task) = node.weight else { continue; };
+            let occurrence = occurrences.entry(task.identifier.clone()).or_insert(0);
+            tasks.insert((task.identifier.clone(), *occurrence), task);
+            *occurrence += 1;
+        }
+
+        let mut graph = PassGraphInner::with_capacity(
+            compiled.graph.node_count(),
+            compiled.graph.edge_count(),
+        );
+        for node in compiled.graph.node_weights() {
+            match node {
+                CompiledNode::Task(name, occurrence) => {
+                    let task = tasks
+                        .remove(&(name.clone(), *occurrence))
+                        .ok_or(Error::NodeNotFound)?;
+                    graph.add_node(Node::Task(task));
+                }
+                CompiledNode::Barrier(barrier) => {
+                    graph.add_node(Node::Barrier(barrier.clone()));
+                }
+            }
+        }
+        for edge in compiled.graph.raw_edges() {
+            graph.add_edge(edge.source(), edge.target(), edge.weight);
+        }
+
+        let Some(Node::Task(source)) = graph.node_weight_mut(self.source) else { return Err(Error::NodeNotFound.into()); };
+        source.outputs = compiled.source_outputs.clone();
+        self.graph.graph = graph;
+        self.transients = compiled.transients.clone();
+
+        Ok(BuiltPassGraph {
+            graph: self,
+            split_distance: None,
+            profiler: None,
+        })
+    }
+
+    fn is_exported(&self, resource: &VirtualResource) -> bool {
+        resource.is_associated_with(&self.swapchain_final)
+            || self
+                .exports
+                .iter()
+                .any(|export| export.is_associated_with(resource))
+    }
+
+    // Pass in the build step that removes all passes that do not contribute to the output of the graph.
+    // This must happen before any barriers are created, so barriers for culled passes are never created.
+    fn cull_dead_passes(&mut self) -> Result<()> {
+        let graph = &self.graph.graph;
+        // Every pass that writes to an exported resource, or has no outputs at all is considered to have side effects.
+        let mut stack = graph
+            .node_indices()
+            .filter(|node| match graph.node_weight(*node).unwrap() {
+                Node::Task(task) => {
+                    *node != self.source
+                        && (task.outputs.is_empty()
+                            || task
+                                .outputs
+                                .iter()
+                                .any(|output| self.is_exported(&output.resource)))
+                }
+                _ => false,
+            })
+            .collect::<Vec<_>>();
+
+        // Walk back from these passes, every pass we find contributes to the output of the graph.
+        let mut alive = HashSet::new();
+        alive.insert(self.source);
+        while let Some(node) = stack.pop() {
+            if alive.insert(node) {
+                stack.extend(graph.neighbors_directed(node, Direction::Incoming));
+            }
+        }
+
+        if alive.len() == graph.node_count() {
+            return Ok(());
+        }
+
+        self.graph
+            .graph
+            .retain_nodes(|_, node| alive.contains(&node));
+
+        // Remove all inputs of culled passes from the source node, and recompute the last usage of each resource.
+        let inputs = self
+            .graph
+            .graph
+            .node_indices()
+            .filter(|node| *node != self.source)
+            .filter_map(|node| match self.graph.graph.node_weight(node).unwrap() {
+                Node::Task(task) => Some(task.inputs.clone()),
+                _ => None,
+            })
+            .flatten()
+            .collect::<Vec<_>>();
+        let Node::Task(source) = self.graph.graph.node_weight_mut(self.source).unwrap() else { panic!("Graph does not have a source node"); };
+        source
+            .outputs
+            .retain(|output| inputs.iter().any(|input| input.resource == output.resource));
+        self.transients.retain(|transient| {
+            inputs
+                .iter()
+                .any(|input| input.resource.is_associated_with(&transient.resource))
+        });
+        self.last_usages.clear();
+        for input in &inputs {
+            self.update_last_usage(&input.resource, input.stage)?;
+        }
+
+        Ok(())
+    }
+
+    /// Set source barrier stages to the *last* usage in the frame, for cross-frame sync.
+    /// Imported resources start in the state they were imported with instead.
     fn set_source_stages(&mut self) -> Result<()> {
         let Node::Task(source) = self.graph.graph.node_weight_mut(self.source).unwrap() else { panic!("Graph does not have a source node"); };
         // For each output, look for the last usage of this resource in the frame.
         for output in &mut source.outputs {
-            if output
+            let import = self
+                .imports
+                .iter()
+                .find(|(import, _)| import.is_associated_with(&output.resource));
+            if let Some((_, state)) = import {
+                output.usage = ResourceUsage::External(*state);
+                output.stage = state.stage;
+                if output.resource.resource_type() == ResourceType::Image {
+                    output.layout = state.layout;
+                }
+            } else if output
                 .resource
                 .is_associated_with(&self.swapchain_final)
             {
                 output.stage = PipelineStage::COLOR_ATTACHMENT_OUTPUT;
             } else {
-                let (_, stage) = self.last_usages.get(output.resource.name()).unwrap();
+                let (_, stage) = self.last_usages.get(&output.resource.binding_key()).unwrap();
                 output.stage = *stage;
             }
         }
@@ -375,6 +962,10 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassGraph<'cb, D, U, A> {
                 }
                 let other_resource = Self::barrier_dst_resource(graph, other_node)?;
                 let other_usage = &other_resource.usage;
+                // Barriers for passes on different queues are recorded on different queues, so they cannot be merged.
+                if Self::node_queue(graph, other_node) != Self::node_queue(graph, node) {
+                    continue;
+                }
                 if other_barrier.resource.uid() == barrier.resource.uid() {
                     if !other_usage.is_read() && !dst_usage.is_read() && other_usage != &dst_usage {
                         return Err(anyhow::Error::from(Error::IllegalTaskGraph));
@@ -408,6 +999,132 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> PassGraph<'cb, D, U, A> {
 
         Ok(())
     }
+
+    // Pass in the build step where transient resources are assigned a memory slot. Transient resources with
+    // non-overlapping lifetimes can share a slot, and thus memory.
+    fn alias_transients(&mut self) -> Result<()> {
+        if self.transients.is_empty() {
+            return Ok(());
+        }
+
+        let source = self.source;
+        let graph: &mut Graph<_, _> = &mut self.graph.graph;
+        let order = petgraph::algo::toposort(&*graph, None).map_err(|_| Error::GraphHasCycle)?;
+
+        // The lifetime of a transient resource is defined by the barriers that initialize it at the start of the frame,
+        // and all tasks that use it. Any other user of the resource is always a descendant of one of these barriers.
+        struct Lifetime {
+            first_use: usize,
+            init_barriers: Vec<NodeIndex>,
+            users: Vec<NodeIndex>,
+            stage: PipelineStage,
+            access: vk::AccessFlags2,
+        }
+
+        let lifetimes = self
+            .transients
+            .iter()
+            .map(|transient| {
+                let mut lifetime = Lifetime {
+                    first_use: usize::MAX,
+                    init_barriers: vec![],
+                    users: vec![],
+                    stage: PipelineStage::NONE,
+                    access: vk::AccessFlags2::NONE,
+                };
+                for (position, node) in order.iter().enumerate() {
+                    match graph.node_weight(*node).unwrap() {
+                        Node::Task(task) if *node != source => {
+                            let usages = task
+                                .inputs
+                                .iter()
+                                .chain(task.outputs.iter())
+                                .filter(|usage| {
+                                    usage.resource.is_associated_with(&transient.resource)
+                                })
+                                .collect::<Vec<_>>();
+                            if usages.is_empty() {
+                                continue;
+                            }
+                            lifetime.users.push(*node);
+                            for usage in usages {
+                                lifetime.stage |= usage.stage;
+                                lifetime.access |= usage.usage.access();
+                            }
+                        }
+                        Node::Barrier(barrier) => {
+                            let resource = &barrier.resource.resource;
+                            if resource.is_source()
+                                && resource.is_associated_with(&transient.resource)
+                            {
+                                lifetime.first_use = lifetime.first_use.min(position);
+                                lifetime.init_barriers.push(*node);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                lifetime
+            })
+            .collect::<Vec<_>>();
+
+        let mut by_first_use = (0..self.transients.len()).collect::<Vec<_>>();
+        by_first_use.sort_by_key(|index| lifetimes[*index].first_use);
+
+        // Each slot holds the transient resource that most recently started using it.
+        let mut slots: Vec<usize> = Vec::new();
+        for index in by_first_use {
+            let lifetime = &lifetimes[index];
+            // A slot can be reused if every user of its current occupant can be ordered before the new resource is initialized.
+            // This is only possible if none of these users depend on the new resource.
+            let slot = slots.iter().position(|occupant| {
+                self.transients[*occupant]
+                    .info
+                    .can_alias(&self.transients[index].info)
+                    && lifetimes[*occupant].users.iter().all(|user| {
+                        lifetime
+                            .init_barriers
+                            .iter()
+                            .all(|barrier| !has_path_connecting(&*graph, *barrier, *user, None))
+                    })
+            });
+
+            match slot {
+                None => {
+                    self.transients[index].slot = slots.len();
+                    slots.push(index);
+                }
+                Some(slot) => {
+                    let previous = &lifetimes[slots[slot]];
+                    // Make sure the memory is no longer in use by the previous occupant when this resource is initialized.
+                    for barrier_node in &lifetime.init_barriers {
+                        let Node::Barrier(barrier) = graph.node_weight_mut(*barrier_node).unwrap() else { unreachable!() };
+                        barrier.src_stage |= previous.stage;
+                        barrier.src_access |= previous.access;
+                        let uid = barrier.resource.uid();
+                        for user in &previous.users {
+                            graph.update_edge(*user, *barrier_node, uid);
+                        }
+                    }
+                    self.transients[index].slot = slot;
+                    slots[slot] = index;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Queue family ownership transfers are only recorded for images, so reject states that name a queue family for other resources.
+fn check_ownership_transfer(resource: &VirtualResource, state: &ResourceState) -> Result<()> {
+    if state.queue_family.is_some() && resource.resource_type() != ResourceType::Image {
+        return Err(Error::Uncategorized(
+            "Queue family ownership transfers are only supported for images",
+        )
+        .into());
+    }
+    Ok(())
 }
 
 /// Trait that is implemented for the task graph to help with debugging and visualizing the graph.
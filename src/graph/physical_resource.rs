@@ -1,10 +1,12 @@
 //! Provides utilities for binding physical resources to virtual resources
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
 
 use anyhow::Result;
+use ash::vk;
 
-use crate::{BufferView, Error, ImageView, VirtualResource};
+use crate::{AccelerationStructure, BufferView, Error, HistoryImage, ImageView, VirtualResource};
 
 /// Describes any physical resource handle on the GPU.
 #[derive(Debug, Clone)]
@@ -13,6 +15,8 @@ pub enum PhysicalResource {
     Image(ImageView),
     /// A buffer resource
     Buffer(BufferView),
+    /// An acceleration structure resource
+    AccelerationStructure(vk::AccelerationStructureKHR),
 }
 
 /// Stores bindings from virtual resources to physical resources.
@@ -29,9 +33,13 @@ pub enum PhysicalResource {
 /// // ... Later, lookup the physical image handle from a virtual resource handle
 /// let view = bindings.resolve(&resource).ok_or_else(|| Error::NoResourceBound)?;
 /// ```
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct PhysicalResourceBindings {
     bindings: HashMap<String, PhysicalResource>,
+    /// History resources that do not hold the contents of a previous frame yet.
+    discarded_history: HashSet<String>,
+    /// The layout the images bound to history resources were left in by the last frame that used them.
+    history_layouts: HashMap<String, Arc<Mutex<vk::ImageLayout>>>,
 }
 
 impl PhysicalResourceBindings {
@@ -46,12 +54,43 @@ impl PhysicalResourceBindings {
             .insert(name.into(), PhysicalResource::Image(image.clone()));
     }
 
+    /// Bind a history image to the virtual resource `name` and its [history](VirtualResource::history). The current image is bound to `name`,
+    /// and the previous image to its history. See the [`history`](crate::graph::history) module documentation for more information.
+    pub fn bind_history_image(&mut self, name: impl Into<String>, image: &HistoryImage) {
+        let resource = VirtualResource::image(name);
+        let history = resource.history().binding_key();
+        self.bind_image(resource.binding_key(), image.current());
+        self.bind_image(history.clone(), image.previous());
+        self.history_layouts
+            .insert(resource.binding_key(), image.current_layout().clone());
+        self.history_layouts
+            .insert(history.clone(), image.previous_layout().clone());
+        if image.has_history() {
+            self.discarded_history.remove(&history);
+        } else {
+            self.discarded_history.insert(history);
+        }
+    }
+
     /// Bind a buffer to all virtual resources with this name as their uid.
     pub fn bind_buffer(&mut self, name: impl Into<String>, buffer: &BufferView) {
         self.bindings
             .insert(name.into(), PhysicalResource::Buffer(*buffer));
     }
 
+    /// Bind an acceleration structure to all virtual resources with this name as their uid.
+    /// The acceleration structure must stay alive while the graph is executing.
+    pub fn bind_acceleration_structure(
+        &mut self,
+        name: impl Into<String>,
+        accel: &AccelerationStructure,
+    ) {
+        self.bindings.insert(
+            name.into(),
+            PhysicalResource::AccelerationStructure(unsafe { accel.handle() }),
+        );
+    }
+
     /// Alias a resource by giving it an alternative name
     pub fn alias(&mut self, new_name: impl Into<String>, resource: &str) -> Result<()> {
         self.bindings.insert(
@@ -64,8 +103,71 @@ impl PhysicalResourceBindings {
         Ok(())
     }
 
+    /// Returns true if this resource is the history of a history image that does not hold the contents of a previous frame yet.
+    pub(crate) fn is_discarded_history(&self, resource: &VirtualResource) -> bool {
+        self.discarded_history.contains(&resource.binding_key())
+    }
+
+    /// Get the layout the image bound to this history resource was left in by the previous frame, or `None` if the
+    /// resource is not bound to a history image.
+    pub(crate) fn history_layout(
+        &self,
+        resource: &VirtualResource,
+    ) -> Result<Option<vk::ImageLayout>> {
+        match self.history_layouts.get(&resource.binding_key()) {
+            None => Ok(None),
+            Some(layout) => Ok(Some(*layout.lock().map_err(|_| Error::PoisonError)?)),
+        }
+    }
+
+    /// Store the layout the image bound to this history resource is left in at the end of the frame.
+    pub(crate) fn set_history_layout(
+        &self,
+        resource: &VirtualResource,
+        layout: vk::ImageLayout,
+    ) -> Result<()> {
+        if let Some(stored) = self.history_layouts.get(&resource.binding_key()) {
+            *stored.lock().map_err(|_| Error::PoisonError)? = layout;
+        }
+        Ok(())
+    }
+
     /// Resolve a virtual resource to a physical resource. Returns `None` if the resource was not found.
+    /// Subresources of a virtual resource resolve to the view created for them while recording the graph.
     pub fn resolve(&self, resource: &VirtualResource) -> Option<&PhysicalResource> {
-        self.bindings.get(resource.name())
+        self.bindings.get(&resource.binding_key())
+    }
+
+    /// Resolve a virtual resource to an image view.
+    /// # Errors
+    /// * Fails with [`Error::NoResourceBound`] if no image was bound to this resource.
+    pub fn resolve_image(&self, resource: &VirtualResource) -> Result<&ImageView> {
+        match self.resolve(resource) {
+            Some(PhysicalResource::Image(image)) => Ok(image),
+            _ => Err(Error::NoResourceBound(resource.uid()).into()),
+        }
+    }
+
+    /// Resolve a virtual resource to a buffer view.
+    /// # Errors
+    /// * Fails with [`Error::NoResourceBound`] if no buffer was bound to this resource.
+    pub fn resolve_buffer(&self, resource: &VirtualResource) -> Result<&BufferView> {
+        match self.resolve(resource) {
+            Some(PhysicalResource::Buffer(buffer)) => Ok(buffer),
+            _ => Err(Error::NoResourceBound(resource.uid()).into()),
+        }
+    }
+
+    /// Resolve a virtual resource to an acceleration structure handle.
+    /// # Errors
+    /// * Fails with [`Error::NoResourceBound`] if no acceleration structure was bound to this resource.
+    pub fn resolve_acceleration_structure(
+        &self,
+        resource: &VirtualResource,
+    ) -> Result<vk::AccelerationStructureKHR> {
+        match self.resolve(resource) {
+            Some(PhysicalResource::AccelerationStructure(accel)) => Ok(*accel),
+            _ => Err(Error::NoResourceBound(resource.uid()).into()),
+        }
     }
 }
@@ -0,0 +1,204 @@
+//! GPU timing of the passes in a pass graph.
+//!
+//! A [`GraphProfiler`] writes a timestamp before and after every pass when a graph is recorded, and reads these back
+//! to obtain the time each pass took on the GPU. To avoid stalling the CPU, results are only read once they are available.
+//! The profiler keeps a separate query pool for each of the last few frames, so the results reported by
+//! [`GraphProfiler::pass_times()`] lag a few frames behind the frame that is currently being recorded.
+//! If the results of a frame are still not available when its query pool is needed again, the frame is assumed to never have
+//! been submitted, and its results are dropped.
+//!
+//! The profiler is enabled for a graph with [`BuiltPassGraph::profile()`](crate::graph::pass_graph::BuiltPassGraph::profile),
+//! and must be kept alive across frames.
+//!
+//! # Example
+//!
+//! ```
+//! use phobos::prelude::*;
+//!
+//! // Created once, and kept alive across frames.
+//! let profiler = GraphProfiler::new(device.clone(), 3);
+//!
+//! // Every frame:
+//! let mut graph = graph.build()?.profile(&profiler);
+//! let cmd = graph.record(cmd, &bindings, &mut pool, None, &mut ())?;
+//! for (pass, time) in profiler.pass_times()? {
+//!     println!("{pass}: {time:?}");
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::{Allocator, Device, Error, IncompleteCommandBuffer, PipelineStage};
+use crate::query_pool::{QueryPool, QueryPoolCreateInfo, TimestampQuery};
+use crate::sync::domain::ExecutionDomain;
+
+/// The timestamps written while recording a single frame.
+#[derive(Default)]
+struct ProfiledFrame {
+    queries: Option<QueryPool<TimestampQuery>>,
+    capacity: u32,
+    /// Every timed pass, together with the query index of its begin and end timestamps.
+    passes: Vec<(String, u32, u32)>,
+}
+
+struct GraphProfilerInner {
+    device: Device,
+    frames: Vec<ProfiledFrame>,
+    current: usize,
+    times: HashMap<String, Duration>,
+}
+
+/// Measures the GPU time of every pass in the graphs it is enabled for. See the [`profiler`](crate::graph::profiler)
+/// module documentation for more information.
+/// All internal state is wrapped in an `Arc<Mutex<GraphProfilerInner>>`, so this struct is `Clone`, `Send` and `Sync`.
+#[derive(Clone)]
+pub struct GraphProfiler {
+    inner: Arc<Mutex<GraphProfilerInner>>,
+}
+
+impl GraphProfilerInner {
+    /// Read back the timestamps of a frame if they are available, and replace the pass times with them.
+    fn collect(&mut self, index: usize) -> Result<()> {
+        let frame = &mut self.frames[index];
+        let Some(queries) = &mut frame.queries else { return Ok(()); };
+        let Some(count) = frame.passes.iter().map(|(_, _, end)| end + 1).max() else { return Ok(()); };
+        let Some(timestamps) = queries.try_results(0, count)? else { return Ok(()); };
+        self.times = frame
+            .passes
+            .drain(..)
+            .map(|(name, begin, end)| {
+                let begin = timestamps[begin as usize].nanoseconds();
+                let end = timestamps[end as usize].nanoseconds();
+                (name, Duration::from_nanos(end.saturating_sub(begin)))
+            })
+            .collect();
+        Ok(())
+    }
+
+    /// Read back all finished frames, and prepare the oldest frame to be recorded with `passes` passes.
+    fn begin_frame(&mut self, passes: usize) -> Result<()> {
+        // Collect the oldest frame first, so the newest results are kept.
+        for offset in 1..=self.frames.len() {
+            self.collect((self.current + offset) % self.frames.len())?;
+        }
+        self.current = (self.current + 1) % self.frames.len();
+        let frame = &mut self.frames[self.current];
+        // Any submitted frame using this query pool has finished by now, so timestamps that were not read back
+        // belong to a frame that was never submitted. Drop them, so the query pool can be reused.
+        frame.passes.clear();
+        let count = (passes * 2) as u32;
+        match &mut frame.queries {
+            Some(queries) if frame.capacity >= count => queries.reset(),
+            _ => {
+                frame.queries = Some(QueryPool::new(
+                    self.device.clone(),
+                    QueryPoolCreateInfo {
+                        count,
+                        statistic_flags: None,
+                    },
+                )?);
+                frame.capacity = count;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write a timestamp to the current frame, and return its query index.
+    fn write_timestamp<'q, D: ExecutionDomain, A: Allocator>(
+        &mut self,
+        cmd: IncompleteCommandBuffer<'q, D, A>,
+        stage: PipelineStage,
+    ) -> Result<(IncompleteCommandBuffer<'q, D, A>, Option<u32>)> {
+        let frame = &mut self.frames[self.current];
+        match &mut frame.queries {
+            Some(queries) if cmd.timestamp_valid_bits() != 0 => {
+                let cmd = cmd.write_timestamp(queries, stage)?;
+                Ok((cmd, Some(queries.current())))
+            }
+            _ => Ok((cmd, None)),
+        }
+    }
+}
+
+impl GraphProfiler {
+    /// Create a new graph profiler. Results are read back after at most `frames` frames,
+    /// so this should be larger than the number of frames in flight.
+    pub fn new(device: Device, frames: usize) -> Self {
+        let inner = GraphProfilerInner {
+            device,
+            frames: (0..frames.max(1))
+                .map(|_| ProfiledFrame::default())
+                .collect(),
+            current: 0,
+            times: HashMap::new(),
+        };
+        Self {
+            inner: Arc::new(Mutex::new(inner)),
+        }
+    }
+
+    /// Get the GPU time of every pass in the most recent frame for which results are available, keyed by pass name.
+    /// # Errors
+    /// * Fails if the internal mutex was poisoned.
+    pub fn pass_times(&self) -> Result<HashMap<String, Duration>> {
+        Ok(self
+            .inner
+            .lock()
+            .map_err(|_| Error::PoisonError)?
+            .times
+            .clone())
+    }
+
+    /// Get the GPU time of a single pass in the most recent frame for which results are available.
+    /// # Errors
+    /// * Fails if the internal mutex was poisoned.
+    pub fn pass_time(&self, name: &str) -> Result<Option<Duration>> {
+        Ok(self
+            .inner
+            .lock()
+            .map_err(|_| Error::PoisonError)?
+            .times
+            .get(name)
+            .copied())
+    }
+
+    /// Start profiling a new frame of a graph with `passes` passes.
+    pub(crate) fn begin_frame(&self, passes: usize) -> Result<()> {
+        self.inner
+            .lock()
+            .map_err(|_| Error::PoisonError)?
+            .begin_frame(passes)
+    }
+
+    /// Record a pass using `record`, surrounded by timestamps. If the current frame is not profiled, or the queue of the
+    /// command buffer does not support timestamps, the pass is recorded without them.
+    pub(crate) fn time_pass<'q, D: ExecutionDomain, A: Allocator>(
+        &self,
+        name: &str,
+        cmd: IncompleteCommandBuffer<'q, D, A>,
+        record: impl FnOnce(
+            IncompleteCommandBuffer<'q, D, A>,
+        ) -> Result<IncompleteCommandBuffer<'q, D, A>>,
+    ) -> Result<IncompleteCommandBuffer<'q, D, A>> {
+        let (cmd, begin) = self
+            .inner
+            .lock()
+            .map_err(|_| Error::PoisonError)?
+            .write_timestamp(cmd, PipelineStage::TOP_OF_PIPE)?;
+        let cmd = record(cmd)?;
+        let Some(begin) = begin else { return Ok(cmd); };
+        let mut inner = self.inner.lock().map_err(|_| Error::PoisonError)?;
+        let (cmd, end) = inner.write_timestamp(cmd, PipelineStage::BOTTOM_OF_PIPE)?;
+        if let Some(end) = end {
+            let current = inner.current;
+            inner.frames[current]
+                .passes
+                .push((name.to_owned(), begin, end));
+        }
+        Ok(cmd)
+    }
+}
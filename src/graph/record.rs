@@ -1,26 +1,42 @@
 //! Provides methods to record a pass graph to a command buffer
 
-use std::collections::HashSet;
+use std::borrow::Cow;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::ffi::CString;
 use std::sync::Arc;
+#[cfg(feature = "rayon")]
+use std::sync::Mutex;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use ash::vk;
 use petgraph::{Incoming, Outgoing};
 use petgraph::graph::NodeIndex;
 use petgraph::visit::EdgeRef;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 
 use crate::{
-    Allocator, BufferView, DebugMessenger, Error, ImageView, PassGraph, PhysicalResourceBindings,
+    Allocator, DebugMessenger, Device, Error, Event, ExecutionManager, ImageView, InFlightContext,
+    IncompleteCmdBuffer, PassGraph, PhysicalResourceBindings, PipelineStage, VirtualResource,
 };
 use crate::command_buffer::IncompleteCommandBuffer;
+#[cfg(feature = "rayon")]
+use crate::command_buffer::{SecondaryCommandBuffer, SecondaryRenderingInfo};
 use crate::command_buffer::state::{RenderingAttachmentInfo, RenderingInfo};
-use crate::graph::pass_graph::{BuiltPassGraph, PassNode, PassResource, PassResourceBarrier};
+use crate::graph::pass::PassQueue;
+use crate::graph::pass_graph::{
+    BuiltPassGraph, PassNode, PassResource, PassResourceBarrier, QueueSegment,
+};
 use crate::graph::physical_resource::PhysicalResource;
-use crate::graph::resource::{AttachmentType, ResourceUsage};
+use crate::graph::resource::{AttachmentType, ResourceState, ResourceUsage};
 use crate::graph::task_graph::Node;
-use crate::pool::LocalPool;
+use crate::graph::transient::allocate_transients;
+use crate::pool::{LocalPool, Poolable, Pooled};
+use crate::resource::image::format_aspects;
+use crate::sync::domain;
 use crate::sync::domain::ExecutionDomain;
+use crate::sync::submit_batch::{SubmitBatch, SubmitHandle};
 
 /// Implement this on a type to be able to record this type to a command buffer.
 pub trait RecordGraphToCommandBuffer<D: ExecutionDomain, U, A: Allocator> {
@@ -40,6 +56,48 @@ pub trait RecordGraphToCommandBuffer<D: ExecutionDomain, U, A: Allocator> {
         Self: Sized;
 }
 
+/// The last part of a pass graph recorded with [`BuiltPassGraph::record_multi_queue()`]. This holds the command buffer
+/// for the main queue, which must be submitted after all other submits of the graph.
+#[derive(Debug)]
+pub struct MultiQueueRecording<'q, D: ExecutionDomain, A: Allocator> {
+    cmd: IncompleteCommandBuffer<'q, D, A>,
+    wait_submits: Vec<SubmitHandle>,
+    wait_stages: Vec<PipelineStage>,
+}
+
+impl<'q, D: ExecutionDomain + 'static, A: Allocator> MultiQueueRecording<'q, D, A> {
+    /// Record additional commands to the main queue command buffer, after all passes in the graph.
+    pub fn record_commands(
+        mut self,
+        f: impl FnOnce(IncompleteCommandBuffer<'q, D, A>) -> Result<IncompleteCommandBuffer<'q, D, A>>,
+    ) -> Result<Self> {
+        self.cmd = f(self.cmd)?;
+        Ok(self)
+    }
+
+    /// Finish the command buffer and submit it as the frame commands, waiting on all submits on other queues it depends on.
+    /// See also [`SubmitBatch::submit_for_present_after()`].
+    pub fn submit_for_present(
+        self,
+        batch: &mut SubmitBatch<D, A>,
+        ifc: InFlightContext,
+        pool: LocalPool<A>,
+    ) -> Result<SubmitHandle> {
+        batch.submit_for_present_after(
+            self.cmd.finish()?,
+            ifc,
+            pool,
+            &self.wait_submits,
+            &self.wait_stages,
+        )
+    }
+
+    /// Finish the command buffer and submit it, waiting on all submits on other queues it depends on.
+    pub fn submit(self, batch: &mut SubmitBatch<D, A>) -> Result<SubmitHandle> {
+        batch.submit_on(self.cmd.finish()?, &self.wait_submits, &self.wait_stages)
+    }
+}
+
 // Traversal
 // =============
 // Algorithm as follows:
@@ -81,7 +139,7 @@ fn insert_in_active_set<D: ExecutionDomain, U, A: Allocator>(
     node: NodeIndex,
     graph: &PassGraph<'_, D, U, A>,
     active: &mut HashSet<NodeIndex>,
-    children: &mut HashSet<NodeIndex>,
+    children: &mut BTreeSet<NodeIndex>,
 ) {
     children.remove(&node);
     active.insert(node);
@@ -90,112 +148,231 @@ fn insert_in_active_set<D: ExecutionDomain, U, A: Allocator>(
     }
 }
 
+impl<'cb, D: ExecutionDomain, U, A: Allocator> BuiltPassGraph<'cb, D, U, A> {
+    /// Get the order in which the nodes of this graph are recorded by [`RecordGraphToCommandBuffer::record()`].
+    /// Nodes that can be recorded at the same time are ordered by their index, so this order is deterministic.
+    pub(crate) fn execution_order(&self) -> Vec<NodeIndex> {
+        let mut order = Vec::with_capacity(self.num_nodes());
+        let mut active = HashSet::new();
+        let mut children = BTreeSet::new();
+        for start in self.graph.sources() {
+            insert_in_active_set(start, self, &mut active, &mut children);
+            order.push(start);
+        }
+
+        while active.len() != self.num_nodes() {
+            // If all parents of a child node are in the active set, it can be recorded.
+            let recorded_nodes = children
+                .iter()
+                .filter(|&&child| parents!(child, self).all(|parent| active.contains(&parent)))
+                .copied()
+                .collect::<Vec<_>>();
+            // Now we swap all recorded nodes to the active set
+            for node in recorded_nodes {
+                insert_in_active_set(node, self, &mut active, &mut children);
+                order.push(node);
+            }
+        }
+
+        order
+    }
+
+    /// Create all transient resources in the graph, and a view for every image subresource used in the graph.
+    /// Returns `bindings` with a binding for each of these added, or `bindings` itself if there are none.
+    fn bind_resources<'b>(
+        &self,
+        device: &Device,
+        local_pool: &mut LocalPool<A>,
+        bindings: &'b PhysicalResourceBindings,
+    ) -> Result<Cow<'b, PhysicalResourceBindings>> {
+        let mut bindings = Cow::Borrowed(bindings);
+        if !self.transients.is_empty() {
+            bindings =
+                Cow::Owned(allocate_transients(device, &self.transients, local_pool, &bindings)?);
+        }
+
+        let subresources = self
+            .task_graph()
+            .graph
+            .node_weights()
+            .filter_map(|node| match node {
+                Node::Task(task) => Some(task.inputs.iter().chain(task.outputs.iter())),
+                _ => None,
+            })
+            .flatten()
+            .filter_map(|resource| {
+                let range = resource.resource.subresource_range()?;
+                Some((resource.resource.binding_key(), (resource.resource.name(), range)))
+            })
+            .collect::<BTreeMap<_, _>>();
+        for (key, (name, range)) in subresources {
+            let parent = VirtualResource::image(name);
+            let Some(PhysicalResource::Image(image)) = bindings.resolve(&parent) else {
+                return Err(Error::NoResourceBound(name.to_owned()).into());
+            };
+            let view = image.subresource_view(&range)?;
+            bindings.to_mut().bind_image(key, &view);
+            local_pool.image_views.push(view);
+        }
+        Ok(bindings)
+    }
+
+    /// Start a new frame in the profiler of this graph, if profiling is enabled.
+    fn begin_profiling(&self) -> Result<()> {
+        let Some(profiler) = &self.profiler else { return Ok(()); };
+        let passes = self
+            .task_graph()
+            .graph
+            .node_indices()
+            .filter(|node| *node != self.source())
+            .filter(|node| {
+                matches!(self.task_graph().graph.node_weight(*node), Some(Node::Task(_)))
+            })
+            .count();
+        profiler.begin_frame(passes)
+    }
+}
+
+/// Resolve an image used by a pass. Since the graph is validated before recording, this only fails if the bindings
+/// were modified by a pass.
+fn resolve_image<'b, D: ExecutionDomain, U, A: Allocator>(
+    pass: &PassNode<PassResource, D, U, A>,
+    bindings: &'b PhysicalResourceBindings,
+    resource: &PassResource,
+) -> Result<&'b ImageView> {
+    match bindings.resolve(&resource.resource) {
+        Some(PhysicalResource::Image(image)) => Ok(image),
+        _ => Err(Error::MissingBinding {
+            pass: pass.identifier.clone(),
+            resource: resource.resource.uid(),
+        }
+        .into()),
+    }
+}
+
+/// Find the resolve attachment of an attachment, together with the layout the graph transitions it to.
 fn find_resolve_attachment<D: ExecutionDomain, U, A: Allocator>(
     pass: &PassNode<PassResource, D, U, A>,
     bindings: &PhysicalResourceBindings,
     resource: &PassResource,
-) -> Option<ImageView> {
+) -> Result<Option<(ImageView, vk::ImageLayout)>> {
     pass.outputs
         .iter()
         .find(|output| match &output.usage {
             ResourceUsage::Attachment(AttachmentType::Resolve(resolve)) => {
-                resource.resource.is_associated_with(resolve)
+                resource.resource.is_same_subresource(resolve)
             }
             _ => false,
         })
         .map(|resolve| {
-            let Some(PhysicalResource::Image(image)) = bindings.resolve(&resolve.resource) else {
-                // TODO: handle or report this error better
-                panic!("No resource bound");
-            };
-            image
+            resolve_image(pass, bindings, resolve).map(|image| (image.clone(), resolve.layout))
         })
-        .cloned()
+        .transpose()
 }
 
 fn color_attachments<D: ExecutionDomain, U, A: Allocator>(
     pass: &PassNode<PassResource, D, U, A>,
     bindings: &PhysicalResourceBindings,
 ) -> Result<Vec<RenderingAttachmentInfo>> {
-    Ok(pass
-        .outputs
+    pass.outputs
         .iter()
-        .filter_map(|resource| -> Option<RenderingAttachmentInfo> {
-            if !matches!(resource.usage, ResourceUsage::Attachment(AttachmentType::Color)) {
-                return None;
-            }
-            let Some(PhysicalResource::Image(image)) = bindings.resolve(&resource.resource) else {
-                // TODO: handle or report this error better
-                panic!("No resource bound");
-            };
-            let resolve = find_resolve_attachment(pass, bindings, resource);
+        .filter(|resource| {
+            matches!(resource.usage, ResourceUsage::Attachment(AttachmentType::Color))
+        })
+        .map(|resource| -> Result<RenderingAttachmentInfo> {
+            let image = resolve_image(pass, bindings, resource)?;
+            let resolve = find_resolve_attachment(pass, bindings, resource)?;
             // Attachment should always have a load op set, or our library is bugged
             let info = RenderingAttachmentInfo {
                 image_view: image.clone(),
                 image_layout: resource.layout,
                 resolve_mode: resolve.is_some().then_some(vk::ResolveModeFlags::AVERAGE),
-                resolve_image_layout: resolve
-                    .is_some()
-                    .then_some(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL),
-                resolve_image_view: resolve,
+                resolve_image_layout: resolve.as_ref().map(|(_, layout)| *layout),
+                resolve_image_view: resolve.map(|(image, _)| image),
                 load_op: resource.load_op.unwrap(),
-                store_op: vk::AttachmentStoreOp::STORE,
+                store_op: resource.store_op.unwrap_or(vk::AttachmentStoreOp::STORE),
                 clear_value: resource.clear_value.unwrap_or(vk::ClearValue::default()),
             };
-            Some(info)
+            Ok(info)
         })
-        .collect())
+        .collect()
 }
 
 fn depth_attachment<D: ExecutionDomain, U, A: Allocator>(
     pass: &PassNode<PassResource, D, U, A>,
     bindings: &PhysicalResourceBindings,
-) -> Option<RenderingAttachmentInfo> {
+) -> Result<Option<RenderingAttachmentInfo>> {
     pass.outputs
         .iter()
-        .filter_map(|resource| -> Option<RenderingAttachmentInfo> {
-            if resource.layout != vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL {
-                return None;
-            }
-
-            let Some(PhysicalResource::Image(image)) = bindings.resolve(&resource.resource) else {
-                // TODO: handle or report this error better
-                panic!("No resource bound");
-            };
-
-            let resolve = find_resolve_attachment(pass, bindings, resource);
+        .find(|resource| matches!(&resource.usage, ResourceUsage::Attachment(ty) if ty.is_depth()))
+        .map(|resource| -> Result<RenderingAttachmentInfo> {
+            let image = resolve_image(pass, bindings, resource)?;
+            let resolve = find_resolve_attachment(pass, bindings, resource)?;
 
             let info = RenderingAttachmentInfo {
                 image_view: image.clone(),
                 image_layout: resource.layout,
                 resolve_mode: resolve.is_some().then_some(vk::ResolveModeFlags::AVERAGE),
-                resolve_image_layout: resolve
-                    .is_some()
-                    .then_some(vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL),
-                resolve_image_view: resolve,
+                resolve_image_layout: resolve.as_ref().map(|(_, layout)| *layout),
+                resolve_image_view: resolve.map(|(image, _)| image),
                 load_op: resource.load_op.unwrap(),
-                store_op: vk::AttachmentStoreOp::STORE,
+                store_op: resource.store_op.unwrap_or(vk::AttachmentStoreOp::STORE),
                 clear_value: resource.clear_value.unwrap_or(vk::ClearValue::default()),
             };
-            Some(info)
+            Ok(info)
         })
-        .next()
+        .transpose()
+}
+
+fn stencil_attachment<D: ExecutionDomain, U, A: Allocator>(
+    pass: &PassNode<PassResource, D, U, A>,
+    bindings: &PhysicalResourceBindings,
+) -> Result<Option<RenderingAttachmentInfo>> {
+    pass.outputs
+        .iter()
+        .find_map(|resource| -> Option<Result<RenderingAttachmentInfo>> {
+            // The ops stored in a combined depth-stencil resource are those of the depth aspect.
+            let (load_op, store_op) = match &resource.usage {
+                ResourceUsage::Attachment(AttachmentType::Stencil) => (
+                    resource.load_op.unwrap(),
+                    resource.store_op.unwrap_or(vk::AttachmentStoreOp::STORE),
+                ),
+                ResourceUsage::Attachment(AttachmentType::DepthStencil(ops)) => {
+                    (ops.load_op, ops.store_op)
+                }
+                _ => return None,
+            };
+
+            let image = match resolve_image(pass, bindings, resource) {
+                Ok(image) => image,
+                Err(error) => return Some(Err(error)),
+            };
+
+            Some(Ok(RenderingAttachmentInfo {
+                image_view: image.clone(),
+                image_layout: resource.layout,
+                resolve_mode: None,
+                resolve_image_layout: None,
+                resolve_image_view: None,
+                load_op,
+                store_op,
+                clear_value: resource.clear_value.unwrap_or(vk::ClearValue::default()),
+            }))
+        })
+        .transpose()
 }
 
 fn render_area<D: ExecutionDomain, U, A: Allocator>(
     pass: &PassNode<PassResource, D, U, A>,
     bindings: &PhysicalResourceBindings,
-) -> vk::Rect2D {
+) -> Result<vk::Rect2D> {
     let resource = pass
         .outputs
         .iter()
         .find(|resource| matches!(resource.usage, ResourceUsage::Attachment(_)))
-        .unwrap();
-    let Some(PhysicalResource::Image(image)) = bindings.resolve(&resource.resource) else {
-        // TODO: handle or report this error better
-        panic!("No resource bound");
-    };
-    vk::Rect2D {
+        .ok_or(Error::Uncategorized("Render pass has no attachments"))?;
+    let image = resolve_image(pass, bindings, resource)?;
+    Ok(vk::Rect2D {
         offset: vk::Offset2D {
             x: 0,
             y: 0,
@@ -205,15 +382,50 @@ fn render_area<D: ExecutionDomain, U, A: Allocator>(
             width: image.width(),
             height: image.height(),
         },
+    })
+}
+
+fn layer_count<D: ExecutionDomain, U, A: Allocator>(
+    pass: &PassNode<PassResource, D, U, A>,
+    bindings: &PhysicalResourceBindings,
+) -> u32 {
+    if pass.layer_count != vk::REMAINING_ARRAY_LAYERS {
+        return pass.layer_count;
     }
+    // Render to as many layers as all attachments have.
+    pass.outputs
+        .iter()
+        .filter(|resource| matches!(resource.usage, ResourceUsage::Attachment(_)))
+        .filter_map(|resource| match bindings.resolve(&resource.resource) {
+            Some(PhysicalResource::Image(image)) => Some(image.layer_count()),
+            _ => None,
+        })
+        .min()
+        .unwrap_or(1)
+}
+
+fn rendering_info<D: ExecutionDomain, U, A: Allocator>(
+    pass: &PassNode<PassResource, D, U, A>,
+    bindings: &PhysicalResourceBindings,
+    flags: vk::RenderingFlags,
+) -> Result<RenderingInfo> {
+    Ok(RenderingInfo {
+        flags,
+        render_area: render_area(pass, bindings)?,
+        layer_count: layer_count(pass, bindings),
+        view_mask: pass.view_mask,
+        color_attachments: color_attachments(pass, bindings)?,
+        depth_attachment: depth_attachment(pass, bindings)?,
+        stencil_attachment: stencil_attachment(pass, bindings)?,
+    })
 }
 
 #[cfg(feature = "debug-markers")]
-fn annotate_pass<'q, D: ExecutionDomain, U, A: Allocator>(
+fn annotate_pass<'q, C: ExecutionDomain, D: ExecutionDomain, U, A: Allocator>(
     pass: &PassNode<PassResource, D, U, A>,
     debug: &Arc<DebugMessenger>,
-    cmd: IncompleteCommandBuffer<'q, D, A>,
-) -> Result<IncompleteCommandBuffer<'q, D, A>> {
+    cmd: IncompleteCommandBuffer<'q, C, A>,
+) -> Result<IncompleteCommandBuffer<'q, C, A>> {
     let name = CString::new(pass.identifier.clone())?;
     let label = vk::DebugUtilsLabelEXT {
         s_type: vk::StructureType::DEBUG_UTILS_LABEL_EXT,
@@ -225,43 +437,38 @@ fn annotate_pass<'q, D: ExecutionDomain, U, A: Allocator>(
 }
 
 #[cfg(not(feature = "debug-markers"))]
-fn annotate_pass<D: ExecutionDomain, A: Allocator>(
-    _: &PassNode<PassResource, D>,
+fn annotate_pass<'q, C: ExecutionDomain, D: ExecutionDomain, U, A: Allocator>(
+    _: &PassNode<PassResource, D, U, A>,
     _: &Arc<DebugMessenger>,
-    cmd: IncompleteCommandBuffer<D, A>,
-) -> Result<IncompleteCommandBuffer<D, A>> {
+    cmd: IncompleteCommandBuffer<'q, C, A>,
+) -> Result<IncompleteCommandBuffer<'q, C, A>> {
     Ok(cmd)
 }
 
-fn record_pass<'q, D: ExecutionDomain, U, A: Allocator>(
+fn record_pass<'q, C: ExecutionDomain, D: ExecutionDomain, U, A: Allocator>(
     pass: &mut PassNode<'_, PassResource, D, U, A>,
     bindings: &PhysicalResourceBindings,
     local_pool: &mut LocalPool<A>,
-    mut cmd: IncompleteCommandBuffer<'q, D, A>,
+    mut cmd: IncompleteCommandBuffer<'q, C, A>,
     debug: Option<Arc<DebugMessenger>>,
     user_data: &mut U,
-) -> Result<IncompleteCommandBuffer<'q, D, A>> {
+) -> Result<IncompleteCommandBuffer<'q, C, A>> {
     if let Some(debug) = debug.clone() {
         cmd = annotate_pass(pass, &debug, cmd)?;
     }
 
     if pass.is_renderpass {
-        let info = RenderingInfo {
-            flags: Default::default(),
-            render_area: render_area(pass, bindings),
-            layer_count: 1, // TODO: Multilayer rendering fix
-            view_mask: 0,
-            color_attachments: color_attachments(pass, bindings)?,
-            depth_attachment: depth_attachment(pass, bindings),
-            stencil_attachment: None, // TODO: Stencil
-        };
-        cmd = cmd.begin_rendering(&info);
+        let info = rendering_info(pass, bindings, vk::RenderingFlags::empty())?;
+        cmd = cmd.cmd_begin_rendering(&info);
     }
 
-    cmd = pass.execute.execute(cmd, local_pool, bindings, user_data)?;
+    cmd = pass
+        .execute
+        .execute(cmd, local_pool, bindings, user_data)
+        .with_context(|| format!("Failed to record pass `{}`", pass.identifier))?;
 
     if pass.is_renderpass {
-        cmd = cmd.end_rendering()
+        cmd = cmd.cmd_end_rendering()
     }
 
     if let Some(debug) = debug {
@@ -273,110 +480,433 @@ fn record_pass<'q, D: ExecutionDomain, U, A: Allocator>(
     Ok(cmd)
 }
 
-fn record_image_barrier<'q, D: ExecutionDomain, A: Allocator>(
+/// Describes how a barrier is recorded when the resource it acts on is used on multiple queues.
+#[derive(Debug, Copy, Clone)]
+enum BarrierKind {
+    /// A barrier between two passes on the same queue.
+    Local,
+    /// The barrier on the consuming queue of a resource that was produced on another queue.
+    /// This is recorded after waiting on a semaphore in the barrier's destination stage.
+    Acquire {
+        src_family: u32,
+        dst_family: u32,
+    },
+    /// The barrier on the producing queue of a resource that is consumed on another queue.
+    /// This is only needed when a queue family ownership transfer happens.
+    Release {
+        src_family: u32,
+        dst_family: u32,
+    },
+}
+
+impl BarrierKind {
+    /// Returns the queue families for a queue family ownership transfer, if one is needed for this image.
+    fn ownership_transfer(&self, image: &ImageView) -> Option<(u32, u32)> {
+        match *self {
+            BarrierKind::Local => None,
+            BarrierKind::Acquire {
+                src_family,
+                dst_family,
+            }
+            | BarrierKind::Release {
+                src_family,
+                dst_family,
+            } => (src_family != dst_family && image.sharing_mode() == vk::SharingMode::EXCLUSIVE)
+                .then_some((src_family, dst_family)),
+        }
+    }
+
+    /// Turn a local barrier into one half of a queue family ownership transfer if it moves an image between the graph
+    /// and a queue family outside of it, as declared when importing or exporting the image.
+    fn with_external_transfer(
+        self,
+        barrier: &PassResourceBarrier,
+        dst_resource: &PassResource,
+        image: &ImageView,
+        family: u32,
+    ) -> Self {
+        let BarrierKind::Local = self else { return self; };
+        if image.sharing_mode() != vk::SharingMode::EXCLUSIVE {
+            return self;
+        }
+        let external_family = |usage: &ResourceUsage| match usage {
+            ResourceUsage::External(ResourceState {
+                queue_family: Some(external),
+                ..
+            }) if *external != family => Some(*external),
+            _ => None,
+        };
+        if let Some(src_family) = external_family(&barrier.resource.usage) {
+            BarrierKind::Acquire {
+                src_family,
+                dst_family: family,
+            }
+        } else if let Some(dst_family) = external_family(&dst_resource.usage) {
+            BarrierKind::Release {
+                src_family: family,
+                dst_family,
+            }
+        } else {
+            self
+        }
+    }
+}
+
+/// Get the subresource range of an image that a barrier from `old_layout` to `new_layout` must cover.
+/// Layouts for both aspects of a depth-stencil image must be applied to both aspects, even when the image
+/// is bound through a view with only one of them. Layouts for a single aspect only apply to the aspect of the view.
+fn barrier_subresource_range(
+    image: &ImageView,
+    old_layout: vk::ImageLayout,
+    new_layout: vk::ImageLayout,
+) -> vk::ImageSubresourceRange {
+    let mut range = image.subresource_range();
+    let has_stencil = format_aspects(image.format())
+        == vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL;
+    let is_combined = |layout: vk::ImageLayout| {
+        matches!(
+            layout,
+            vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL
+                | vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL
+                | vk::ImageLayout::DEPTH_READ_ONLY_STENCIL_ATTACHMENT_OPTIMAL
+                | vk::ImageLayout::DEPTH_ATTACHMENT_STENCIL_READ_ONLY_OPTIMAL
+        )
+    };
+    let is_single_aspect = |layout: vk::ImageLayout| {
+        matches!(
+            layout,
+            vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL
+                | vk::ImageLayout::DEPTH_READ_ONLY_OPTIMAL
+                | vk::ImageLayout::STENCIL_ATTACHMENT_OPTIMAL
+                | vk::ImageLayout::STENCIL_READ_ONLY_OPTIMAL
+        )
+    };
+    if has_stencil
+        && (is_combined(old_layout) || is_combined(new_layout))
+        && !is_single_aspect(old_layout)
+        && !is_single_aspect(new_layout)
+    {
+        range.aspect_mask = vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL;
+    }
+    range
+}
+
+/// A barrier over a single resource, in the form it is passed to Vulkan.
+enum GraphBarrier {
+    Image(vk::ImageMemoryBarrier2),
+    Memory(vk::MemoryBarrier2),
+}
+
+impl GraphBarrier {
+    /// Get the dependency info containing this barrier. The returned struct points into `self`.
+    fn dependency(&self, flags: vk::DependencyFlags) -> vk::DependencyInfo {
+        let mut dependency = vk::DependencyInfo {
+            dependency_flags: flags,
+            ..Default::default()
+        };
+        match self {
+            GraphBarrier::Image(barrier) => {
+                dependency.image_memory_barrier_count = 1;
+                dependency.p_image_memory_barriers = barrier;
+            }
+            GraphBarrier::Memory(barrier) => {
+                dependency.memory_barrier_count = 1;
+                dependency.p_memory_barriers = barrier;
+            }
+        }
+        dependency
+    }
+
+    /// Returns true if this barrier is part of a queue family ownership transfer.
+    fn is_ownership_transfer(&self) -> bool {
+        match self {
+            GraphBarrier::Image(barrier) => {
+                barrier.src_queue_family_index != barrier.dst_queue_family_index
+            }
+            GraphBarrier::Memory(_) => false,
+        }
+    }
+}
+
+/// Get the barrier over an image, or `None` if no barrier is needed.
+fn image_barrier(
     barrier: &PassResourceBarrier,
     image: &ImageView,
     dst_resource: &PassResource,
-    cmd: IncompleteCommandBuffer<'q, D, A>,
-) -> Result<IncompleteCommandBuffer<'q, D, A>> {
+    kind: BarrierKind,
+) -> Option<GraphBarrier> {
     // Image layouts:
     // barrier.resource has information on srcLayout
     // dst_resource(barrier) has information on dstLayout
 
-    let vk_barrier = vk::ImageMemoryBarrier2 {
+    let (src_family, dst_family) = kind
+        .ownership_transfer(image)
+        .unwrap_or((vk::QUEUE_FAMILY_IGNORED, vk::QUEUE_FAMILY_IGNORED));
+    let (src_stage, src_access, dst_stage, dst_access) = match kind {
+        BarrierKind::Local => {
+            (barrier.src_stage, barrier.src_access, barrier.dst_stage, barrier.dst_access)
+        }
+        // The semaphore wait already made all writes available, so we only need to chain to the wait stage.
+        BarrierKind::Acquire {
+            ..
+        } => (barrier.dst_stage, vk::AccessFlags2::NONE, barrier.dst_stage, barrier.dst_access),
+        BarrierKind::Release {
+            ..
+        } => {
+            // Without an ownership transfer, the semaphore is enough to synchronize with the other queue.
+            if src_family == vk::QUEUE_FAMILY_IGNORED {
+                return None;
+            }
+            (
+                barrier.src_stage,
+                barrier.src_access,
+                PipelineStage::NONE,
+                vk::AccessFlags2::NONE,
+            )
+        }
+    };
+
+    Some(GraphBarrier::Image(vk::ImageMemoryBarrier2 {
         s_type: vk::StructureType::IMAGE_MEMORY_BARRIER_2,
         p_next: std::ptr::null(),
-        src_stage_mask: barrier.src_stage,
-        src_access_mask: barrier.src_access,
-        dst_stage_mask: barrier.dst_stage,
-        dst_access_mask: barrier.dst_access,
+        src_stage_mask: src_stage,
+        src_access_mask: src_access,
+        dst_stage_mask: dst_stage,
+        dst_access_mask: dst_access,
         old_layout: barrier.resource.layout,
         new_layout: dst_resource.layout,
-        src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
-        dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+        src_queue_family_index: src_family,
+        dst_queue_family_index: dst_family,
         image: unsafe { image.image() },
-        subresource_range: image.subresource_range(),
-    };
+        subresource_range: barrier_subresource_range(
+            image,
+            barrier.resource.layout,
+            dst_resource.layout,
+        ),
+    }))
+}
 
-    let dependency = vk::DependencyInfo {
-        s_type: vk::StructureType::DEPENDENCY_INFO,
-        p_next: std::ptr::null(),
-        dependency_flags: vk::DependencyFlags::BY_REGION,
-        memory_barrier_count: 0,
-        p_memory_barriers: std::ptr::null(),
-        buffer_memory_barrier_count: 0,
-        p_buffer_memory_barriers: std::ptr::null(),
-        image_memory_barrier_count: 1,
-        p_image_memory_barriers: &vk_barrier,
+/// Get the barrier over a buffer or acceleration structure, or `None` if no barrier is needed.
+fn memory_barrier(barrier: &PassResourceBarrier, kind: BarrierKind) -> Option<GraphBarrier> {
+    // Buffers are always created with concurrent sharing mode when multiple queues are used, so they never need an
+    // ownership transfer. When they are used across queues, the semaphore already made all writes available.
+    // Acceleration structures live in buffers, so the same applies to them.
+    let (src_stage, src_access) = match kind {
+        BarrierKind::Local => (barrier.src_stage, barrier.src_access),
+        BarrierKind::Acquire {
+            ..
+        } => (barrier.dst_stage, vk::AccessFlags2::NONE),
+        BarrierKind::Release {
+            ..
+        } => return None,
     };
 
-    Ok(cmd.pipeline_barrier(&dependency))
-}
-
-fn record_buffer_barrier<'q, D: ExecutionDomain, A: Allocator>(
-    barrier: &PassResourceBarrier,
-    _buffer: &BufferView,
-    _dst_resource: &PassResource,
-    cmd: IncompleteCommandBuffer<'q, D, A>,
-) -> Result<IncompleteCommandBuffer<'q, D, A>> {
     // Since every driver implements buffer barriers as global memory barriers, we will do the same.
-    let vk_barrier = vk::MemoryBarrier2 {
+    Some(GraphBarrier::Memory(vk::MemoryBarrier2 {
         s_type: vk::StructureType::MEMORY_BARRIER_2,
         p_next: std::ptr::null(),
-        src_stage_mask: barrier.src_stage,
-        src_access_mask: barrier.src_access,
+        src_stage_mask: src_stage,
+        src_access_mask: src_access,
         dst_stage_mask: barrier.dst_stage,
         dst_access_mask: barrier.dst_access,
-    };
-
-    let dependency = vk::DependencyInfo {
-        s_type: vk::StructureType::DEPENDENCY_INFO,
-        p_next: std::ptr::null(),
-        dependency_flags: vk::DependencyFlags::BY_REGION,
-        memory_barrier_count: 1,
-        p_memory_barriers: &vk_barrier,
-        buffer_memory_barrier_count: 0,
-        p_buffer_memory_barriers: std::ptr::null(),
-        image_memory_barrier_count: 0,
-        p_image_memory_barriers: std::ptr::null(),
-    };
+    }))
+}
 
-    Ok(cmd.pipeline_barrier(&dependency))
+/// Get the barrier for a barrier node in the graph, or `None` if no barrier is needed.
+/// `family` is the queue family the barrier is recorded on.
+fn resolve_barrier(
+    barrier: &PassResourceBarrier,
+    dst_resource: &PassResource,
+    bindings: &PhysicalResourceBindings,
+    kind: BarrierKind,
+    family: u32,
+) -> Result<Option<GraphBarrier>> {
+    let physical_resource = bindings.resolve(&barrier.resource.resource);
+    let Some(resource) = physical_resource else { return Err(anyhow::Error::from(Error::NoResourceBound(barrier.resource.resource.uid().to_owned()))) };
+    Ok(match resource {
+        // Without a previous frame, the history of an image has no contents to preserve.
+        PhysicalResource::Image(image)
+            if barrier.resource.resource.is_source()
+                && bindings.is_discarded_history(&barrier.resource.resource) =>
+        {
+            let mut barrier = barrier.clone();
+            barrier.resource.layout = vk::ImageLayout::UNDEFINED;
+            image_barrier(&barrier, image, dst_resource, kind)
+        }
+        // The history of an image starts in the layout the previous frame left it in.
+        PhysicalResource::Image(image) if barrier.resource.resource.is_source() => {
+            let mut barrier = barrier.clone();
+            if let Some(layout) = bindings.history_layout(&barrier.resource.resource)? {
+                barrier.resource.layout = layout;
+            }
+            let kind = kind.with_external_transfer(&barrier, dst_resource, image, family);
+            image_barrier(&barrier, image, dst_resource, kind)
+        }
+        PhysicalResource::Image(image) => {
+            let kind = kind.with_external_transfer(barrier, dst_resource, image, family);
+            image_barrier(barrier, image, dst_resource, kind)
+        }
+        PhysicalResource::Buffer(_) | PhysicalResource::AccelerationStructure(_) => {
+            memory_barrier(barrier, kind)
+        }
+    })
 }
 
 fn record_barrier<'q, D: ExecutionDomain, A: Allocator>(
     barrier: &PassResourceBarrier,
     dst_resource: &PassResource,
     bindings: &PhysicalResourceBindings,
+    kind: BarrierKind,
     cmd: IncompleteCommandBuffer<'q, D, A>,
 ) -> Result<IncompleteCommandBuffer<'q, D, A>> {
-    let physical_resource = bindings.resolve(&barrier.resource.resource);
-    let Some(resource) = physical_resource else { return Err(anyhow::Error::from(Error::NoResourceBound(barrier.resource.resource.uid().to_owned()))) };
-    match resource {
-        PhysicalResource::Image(image) => record_image_barrier(barrier, image, dst_resource, cmd),
-        PhysicalResource::Buffer(buffer) => {
-            record_buffer_barrier(barrier, buffer, dst_resource, cmd)
+    let family = cmd.queue_info().family_index;
+    match resolve_barrier(barrier, dst_resource, bindings, kind, family)? {
+        Some(barrier) => {
+            Ok(cmd.pipeline_barrier(&barrier.dependency(vk::DependencyFlags::BY_REGION)))
+        }
+        None => Ok(cmd),
+    }
+}
+
+/// Tracks the split barriers of a graph while it is recorded. The first half of a split barrier is recorded where
+/// the barrier would normally be recorded, and the second half right before the first pass that depends on it.
+#[derive(Default)]
+struct SplitBarriers {
+    /// The first pass that depends on each split barrier.
+    consumers: HashMap<NodeIndex, NodeIndex>,
+    /// Signaled split barriers, stored by the pass that waits on them.
+    pending: HashMap<NodeIndex, Vec<(Pooled<Event>, GraphBarrier)>>,
+}
+
+impl SplitBarriers {
+    /// Find all barriers in `order` that should be split. `queue_flags` are the capabilities of the queue the graph is recorded for.
+    /// Events can only be signaled and waited on with graphics or compute support, so on other queues no barriers are split
+    /// and all of them are recorded as regular pipeline barriers.
+    fn new<D: ExecutionDomain, U, A: Allocator>(
+        graph: &BuiltPassGraph<'_, D, U, A>,
+        order: &[NodeIndex],
+        queue_flags: vk::QueueFlags,
+    ) -> Self {
+        let Some(min_distance) = graph.split_distance else { return Self::default(); };
+        if !queue_flags.intersects(vk::QueueFlags::GRAPHICS | vk::QueueFlags::COMPUTE) {
+            return Self::default();
+        }
+        let graph = &graph.task_graph().graph;
+        let positions = order
+            .iter()
+            .enumerate()
+            .map(|(position, node)| (*node, position))
+            .collect::<HashMap<_, _>>();
+        // The number of passes before each position in the execution order.
+        let passes_before = order
+            .iter()
+            .scan(0, |count, node| {
+                let before = *count;
+                if let Node::Task(_) = graph.node_weight(*node).unwrap() {
+                    *count += 1;
+                }
+                Some(before)
+            })
+            .collect::<Vec<_>>();
+
+        let mut consumers = HashMap::new();
+        for (position, node) in order.iter().enumerate() {
+            let Node::Barrier(_) = graph.node_weight(*node).unwrap() else { continue; };
+            let Some(consumer) = graph
+                .neighbors_directed(*node, Outgoing)
+                .min_by_key(|consumer| positions[consumer])
+            else {
+                continue;
+            };
+            if passes_before[positions[&consumer]] - passes_before[position] >= min_distance {
+                consumers.insert(*node, consumer);
+            }
+        }
+
+        SplitBarriers {
+            consumers,
+            pending: HashMap::new(),
         }
     }
+
+    /// Returns true if this node is a split barrier.
+    fn is_split(&self, node: NodeIndex) -> bool {
+        self.consumers.contains_key(&node)
+    }
+
+    /// Record the first half of a split barrier by signaling an event. Barriers that transfer queue family ownership
+    /// are recorded as a regular pipeline barrier instead.
+    fn signal<'q, D: ExecutionDomain, U, A: Allocator>(
+        &mut self,
+        graph: &BuiltPassGraph<'_, D, U, A>,
+        node: NodeIndex,
+        bindings: &PhysicalResourceBindings,
+        local_pool: &mut LocalPool<A>,
+        cmd: IncompleteCommandBuffer<'q, D, A>,
+    ) -> Result<IncompleteCommandBuffer<'q, D, A>> {
+        let graph = &graph.task_graph().graph;
+        let Node::Barrier(barrier) = graph.node_weight(node).unwrap() else { return Err(Error::NodeNotFound.into()); };
+        let dst_resource = PassGraph::barrier_dst_resource(graph, node)?;
+        let family = cmd.queue_info().family_index;
+        let Some(barrier) =
+            resolve_barrier(barrier, dst_resource, bindings, BarrierKind::Local, family)?
+        else {
+            return Ok(cmd);
+        };
+        if barrier.is_ownership_transfer() {
+            return Ok(cmd.pipeline_barrier(&barrier.dependency(vk::DependencyFlags::BY_REGION)));
+        }
+        let event = Event::new_in_pool(&local_pool.pool.events, &())?;
+        // The dependency flags of vkCmdSetEvent2 must be empty.
+        let cmd = cmd.set_event(&event, &barrier.dependency(vk::DependencyFlags::empty()));
+        self.pending
+            .entry(self.consumers[&node])
+            .or_default()
+            .push((event, barrier));
+        Ok(cmd)
+    }
+
+    /// Record the second half of all split barriers that `node` depends on.
+    fn wait<'q, D: ExecutionDomain, A: Allocator>(
+        &mut self,
+        node: NodeIndex,
+        local_pool: &mut LocalPool<A>,
+        mut cmd: IncompleteCommandBuffer<'q, D, A>,
+    ) -> IncompleteCommandBuffer<'q, D, A> {
+        for (event, barrier) in self.pending.remove(&node).unwrap_or_default() {
+            cmd = cmd.wait_event(&event, &barrier.dependency(vk::DependencyFlags::empty()));
+            // The event must stay alive until the command buffer has finished executing.
+            local_pool.events.push(event);
+        }
+        cmd
+    }
 }
 
-fn record_node<'q, D: ExecutionDomain, U, A: Allocator>(
+#[allow(clippy::too_many_arguments)]
+fn record_node<'q, C: ExecutionDomain, D: ExecutionDomain, U, A: Allocator>(
     graph: &mut BuiltPassGraph<'_, D, U, A>,
     node: NodeIndex,
     bindings: &PhysicalResourceBindings,
     local_pool: &mut LocalPool<A>,
-    cmd: IncompleteCommandBuffer<'q, D, A>,
+    kind: BarrierKind,
+    cmd: IncompleteCommandBuffer<'q, C, A>,
     debug: Option<Arc<DebugMessenger>>,
     user_data: &mut U,
-) -> Result<IncompleteCommandBuffer<'q, D, A>> {
+) -> Result<IncompleteCommandBuffer<'q, C, A>> {
+    let profiler = graph.profiler.clone().filter(|_| node != graph.source());
     let graph = &mut graph.graph.graph;
     let dst_resource_res = PassGraph::barrier_dst_resource(graph, node).cloned();
     let weight = graph.node_weight_mut(node).unwrap();
     match weight {
-        Node::Task(pass) => record_pass(pass, bindings, local_pool, cmd, debug, user_data),
+        Node::Task(pass) => match profiler {
+            None => record_pass(pass, bindings, local_pool, cmd, debug, user_data),
+            Some(profiler) => profiler.time_pass(&pass.identifier.clone(), cmd, |cmd| {
+                record_pass(pass, bindings, local_pool, cmd, debug, user_data)
+            }),
+        },
         Node::Barrier(barrier) => {
             // Find destination resource in graph
-            record_barrier(barrier, &dst_resource_res?, bindings, cmd)
+            record_barrier(barrier, &dst_resource_res?, bindings, kind, cmd)
         }
         Node::_Unreachable(_) => {
             unreachable!()
@@ -388,6 +918,7 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> RecordGraphToCommandBuffer<D, U,
     for BuiltPassGraph<'cb, D, U, A>
 {
     /// Record the render graph to the command buffer. This will pass `user_data` along to every pass executor in the graph.
+    /// Passes for other queues are recorded to this command buffer as well, as long as its queue supports them.
     fn record<'q>(
         &mut self,
         mut cmd: IncompleteCommandBuffer<'q, D, A>,
@@ -398,40 +929,422 @@ impl<'cb, D: ExecutionDomain, U, A: Allocator> RecordGraphToCommandBuffer<D, U,
     ) -> Result<IncompleteCommandBuffer<'q, D, A>>
     where
         Self: Sized, {
-        let mut active = HashSet::new();
-        let mut children = HashSet::new();
-        for start in self.graph.sources() {
-            insert_in_active_set(start, self, &mut active, &mut children);
+        // Report problems with the bindings before creating anything.
+        self.validate(bindings)?;
+        // Create all transient resources and subresource views, and add them to the bindings.
+        let bindings = self.bind_resources(cmd.device(), local_pool, bindings)?;
+        let bindings = bindings.as_ref();
+        self.begin_profiling()?;
+
+        let order = self.execution_order();
+        let mut split = SplitBarriers::new(self, &order, cmd.queue_info().flags);
+        for node in order {
+            cmd = split.wait(node, local_pool, cmd);
+            if split.is_split(node) {
+                cmd = split.signal(self, node, bindings, local_pool, cmd)?;
+                continue;
+            }
+            cmd = record_node(
+                self,
+                node,
+                bindings,
+                local_pool,
+                BarrierKind::Local,
+                cmd,
+                debug.clone(),
+                user_data,
+            )?;
         }
-        // Record each initial active node.
-        for node in &active {
-            cmd = record_node(self, *node, bindings, local_pool, cmd, debug.clone(), user_data)?;
+
+        self.store_history_layouts(bindings)?;
+        Ok(cmd)
+    }
+}
+
+fn queue_family<D: ExecutionDomain, A: Allocator>(
+    exec: &ExecutionManager<A>,
+    queue: PassQueue,
+) -> Result<u32> {
+    let queue = match queue {
+        PassQueue::Main => exec.get_queue::<D>(),
+        PassQueue::Compute => exec.get_queue::<domain::Compute>(),
+        PassQueue::Transfer => exec.get_queue::<domain::Transfer>(),
+    };
+    Ok(queue.ok_or(Error::NoCapableQueue)?.info().family_index)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn record_segment<'q, C: ExecutionDomain, D: ExecutionDomain, U, A: Allocator>(
+    graph: &mut BuiltPassGraph<'_, D, U, A>,
+    segment: &QueueSegment,
+    families: &HashMap<PassQueue, u32>,
+    bindings: &PhysicalResourceBindings,
+    local_pool: &mut LocalPool<A>,
+    mut cmd: IncompleteCommandBuffer<'q, C, A>,
+    debug: Option<Arc<DebugMessenger>>,
+    user_data: &mut U,
+) -> Result<IncompleteCommandBuffer<'q, C, A>> {
+    let family = *families.get(&segment.queue).unwrap();
+    for node in &segment.nodes {
+        // If a barrier depends on a pass on another queue, it acquires the resource from that queue.
+        // Barriers on the initial version of a resource discard its contents, so ownership does not need to be transferred.
+        let discards = match graph.task_graph().graph.node_weight(*node).unwrap() {
+            Node::Barrier(barrier) => barrier.resource.resource.is_source(),
+            _ => false,
+        };
+        let producer = graph
+            .task_graph()
+            .graph
+            .neighbors_directed(*node, Incoming)
+            .filter(|parent| *parent != graph.source())
+            .map(|parent| PassGraph::node_queue(&graph.task_graph().graph, parent))
+            .find(|queue| *queue != segment.queue);
+        let kind = match producer {
+            None => BarrierKind::Local,
+            Some(_) if discards => BarrierKind::Acquire {
+                src_family: family,
+                dst_family: family,
+            },
+            Some(queue) => BarrierKind::Acquire {
+                src_family: *families.get(&queue).unwrap(),
+                dst_family: family,
+            },
+        };
+        cmd = record_node(graph, *node, bindings, local_pool, kind, cmd, debug.clone(), user_data)?;
+    }
+
+    // Release all resources that are consumed on another queue
+    for node in &segment.releases {
+        let queue = PassGraph::node_queue(&graph.task_graph().graph, *node);
+        let kind = BarrierKind::Release {
+            src_family: family,
+            dst_family: *families.get(&queue).unwrap(),
+        };
+        cmd = record_node(graph, *node, bindings, local_pool, kind, cmd, debug.clone(), user_data)?;
+    }
+
+    Ok(cmd)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn submit_segment<C: ExecutionDomain + 'static, D: ExecutionDomain + 'static, U, A: Allocator>(
+    graph: &mut BuiltPassGraph<'_, D, U, A>,
+    exec: &ExecutionManager<A>,
+    batch: &mut SubmitBatch<D, A>,
+    segment: &QueueSegment,
+    handles: &[SubmitHandle],
+    families: &HashMap<PassQueue, u32>,
+    bindings: &PhysicalResourceBindings,
+    local_pool: &mut LocalPool<A>,
+    debug: Option<Arc<DebugMessenger>>,
+    user_data: &mut U,
+) -> Result<SubmitHandle> {
+    let cmd = exec.allocate_command_buffer::<C>()?;
+    let cmd =
+        record_segment(graph, segment, families, bindings, local_pool, cmd, debug, user_data)?;
+    let (wait_submits, wait_stages): (Vec<_>, Vec<_>) = segment
+        .waits
+        .iter()
+        .map(|(segment, stage)| (handles[*segment], *stage))
+        .unzip();
+    batch.submit_on(cmd.finish()?, &wait_submits, &wait_stages)
+}
+
+impl<'cb, D: ExecutionDomain + 'static, U, A: Allocator> BuiltPassGraph<'cb, D, U, A> {
+    /// Record the render graph to multiple queues. Passes created with [`PassBuilder::async_compute_fn()`](crate::PassBuilder::async_compute_fn)
+    /// or [`PassBuilder::async_transfer_fn()`](crate::PassBuilder::async_transfer_fn) are recorded to command buffers on their own queue,
+    /// so they can overlap with passes on the main queue. These command buffers are submitted to `batch`, together with semaphores and
+    /// queue family ownership transfers for every resource that is used on multiple queues.
+    ///
+    /// The command buffer for the last part of the graph on the main queue is returned, and must be submitted last using
+    /// [`MultiQueueRecording::submit_for_present()`] or [`MultiQueueRecording::submit()`].
+    /// Note that a resource can only be consumed on one queue other than the queue it was produced on.
+    /// # Errors
+    /// - This function can error if a virtual resource used in the graph is lacking an physical binding.
+    /// - This function can error if there is no queue available for a pass in the graph.
+    pub fn record_multi_queue<'q>(
+        &mut self,
+        exec: &'q ExecutionManager<A>,
+        batch: &mut SubmitBatch<D, A>,
+        bindings: &PhysicalResourceBindings,
+        local_pool: &mut LocalPool<A>,
+        debug: Option<Arc<DebugMessenger>>,
+        user_data: &mut U,
+    ) -> Result<MultiQueueRecording<'q, D, A>> {
+        let segments = self.queue_schedule()?;
+        let mut families = HashMap::new();
+        families.insert(PassQueue::Main, queue_family::<D, A>(exec, PassQueue::Main)?);
+        for segment in &segments {
+            if let Entry::Vacant(entry) = families.entry(segment.queue) {
+                entry.insert(queue_family::<D, A>(exec, segment.queue)?);
+            }
         }
 
-        while active.len() != self.num_nodes() {
-            // For each node that is a child of an active node
-            let mut recorded_nodes = Vec::new();
-            for child in &children {
-                // If all parents of this child node are in the active set, record it.
-                if parents!(child, self).all(|parent| active.contains(&parent)) {
-                    cmd = record_node(
-                        self,
-                        *child,
-                        bindings,
-                        local_pool,
-                        cmd,
-                        debug.clone(),
-                        user_data,
+        // Report problems with the bindings before creating anything.
+        self.validate(bindings)?;
+        // Create all transient resources and subresource views, and add them to the bindings.
+        let bindings = self.bind_resources(exec.device(), local_pool, bindings)?;
+        let bindings = bindings.as_ref();
+        self.begin_profiling()?;
+
+        // The last segment on the main queue is returned to the caller. If the graph does not end on the main queue,
+        // an empty command buffer is returned instead.
+        let (last, submitted) = match segments.split_last() {
+            Some((last, submitted)) if last.queue == PassQueue::Main => (Some(last), submitted),
+            _ => (None, segments.as_slice()),
+        };
+
+        let mut handles = Vec::with_capacity(segments.len());
+        for segment in submitted {
+            let handle = match segment.queue {
+                PassQueue::Main => submit_segment::<D, D, U, A>(
+                    self,
+                    exec,
+                    batch,
+                    segment,
+                    &handles,
+                    &families,
+                    bindings,
+                    local_pool,
+                    debug.clone(),
+                    user_data,
+                )?,
+                PassQueue::Compute => submit_segment::<domain::Compute, D, U, A>(
+                    self,
+                    exec,
+                    batch,
+                    segment,
+                    &handles,
+                    &families,
+                    bindings,
+                    local_pool,
+                    debug.clone(),
+                    user_data,
+                )?,
+                PassQueue::Transfer => submit_segment::<domain::Transfer, D, U, A>(
+                    self,
+                    exec,
+                    batch,
+                    segment,
+                    &handles,
+                    &families,
+                    bindings,
+                    local_pool,
+                    debug.clone(),
+                    user_data,
+                )?,
+            };
+            handles.push(handle);
+        }
+
+        let cmd = exec.allocate_command_buffer::<D>()?;
+        let recording = match last {
+            None => MultiQueueRecording {
+                cmd,
+                wait_submits: vec![],
+                wait_stages: vec![],
+            },
+            Some(last) => {
+                let cmd = record_segment(
+                    self, last, &families, bindings, local_pool, cmd, debug, user_data,
+                )?;
+                let (wait_submits, wait_stages) = last
+                    .waits
+                    .iter()
+                    .map(|(segment, stage)| (handles[*segment], *stage))
+                    .unzip();
+                MultiQueueRecording {
+                    cmd,
+                    wait_submits,
+                    wait_stages,
+                }
+            }
+        };
+        self.store_history_layouts(bindings)?;
+        Ok(recording)
+    }
+}
+
+/// The local pool of a worker thread, and the passes it recorded to secondary command buffers.
+#[cfg(feature = "rayon")]
+type WorkerRecording<D, A> = (LocalPool<A>, Vec<(NodeIndex, SecondaryCommandBuffer<D>)>);
+
+#[cfg(feature = "rayon")]
+fn record_secondary_pass<'q, D: ExecutionDomain, U, A: Allocator>(
+    pass: &PassNode<PassResource, D, U, A>,
+    info: Option<&RenderingInfo>,
+    secondary: SecondaryCommandBuffer<D>,
+    mut cmd: IncompleteCommandBuffer<'q, D, A>,
+    debug: Option<Arc<DebugMessenger>>,
+) -> Result<IncompleteCommandBuffer<'q, D, A>> {
+    if let Some(debug) = debug.clone() {
+        cmd = annotate_pass(pass, &debug, cmd)?;
+    }
+
+    if let Some(info) = info {
+        cmd = cmd.cmd_begin_rendering(info);
+    }
+
+    cmd = cmd.execute_commands(std::slice::from_ref(&secondary));
+
+    if info.is_some() {
+        cmd = cmd.cmd_end_rendering();
+    }
+
+    if let Some(debug) = debug {
+        if cfg!(feature = "debug-markers") {
+            cmd = cmd.end_label(&debug);
+        }
+    }
+
+    Ok(cmd)
+}
+
+#[cfg(feature = "rayon")]
+impl<'cb, D, U, A> BuiltPassGraph<'cb, D, U, A>
+where
+    D: ExecutionDomain + Send + 'static,
+    U: Clone + Sync,
+    A: Allocator + 'static,
+    A::Allocation: Send,
+{
+    /// Record the render graph to the command buffer, recording the passes on the rayon thread pool.
+    /// Every pass is recorded to its own secondary command buffer by a worker thread. These are then executed
+    /// from `cmd` in the same order [`RecordGraphToCommandBuffer::record()`] would record the passes, with all barriers recorded in between.
+    ///
+    /// Since passes can be recorded at the same time, each pass receives its own clone of `user_data`, and each worker thread
+    /// uses its own [`LocalPool`]. These are kept alive by `local_pool`.
+    /// # Errors
+    /// - This function can error if a virtual resource used in the graph is lacking an physical binding.
+    /// - This function can error if a pass executor fails.
+    pub fn record_parallel<'q>(
+        &mut self,
+        mut cmd: IncompleteCommandBuffer<'q, D, A>,
+        bindings: &PhysicalResourceBindings,
+        local_pool: &mut LocalPool<A>,
+        debug: Option<Arc<DebugMessenger>>,
+        user_data: &U,
+    ) -> Result<IncompleteCommandBuffer<'q, D, A>> {
+        // Report problems with the bindings before creating anything.
+        self.validate(bindings)?;
+        // Create all transient resources and subresource views, and add them to the bindings.
+        let bindings = self.bind_resources(cmd.device(), local_pool, bindings)?;
+        let bindings = bindings.as_ref();
+        self.begin_profiling()?;
+
+        // Worker threads need the attachments of a render pass to continue it, so look these up first.
+        let mut rendering = HashMap::new();
+        for node in self.task_graph().graph.node_indices() {
+            let Node::Task(pass) = self.task_graph().graph.node_weight(node).unwrap() else { continue; };
+            if pass.is_renderpass {
+                let info = rendering_info(
+                    pass,
+                    bindings,
+                    vk::RenderingFlags::CONTENTS_SECONDARY_COMMAND_BUFFERS,
+                )?;
+                rendering.insert(node, info);
+            }
+        }
+
+        let source = self.source();
+        let passes = self
+            .graph
+            .graph
+            .node_weights_mut()
+            .enumerate()
+            .filter_map(|(index, node)| match node {
+                Node::Task(pass) if NodeIndex::new(index) != source => {
+                    Some((NodeIndex::new(index), pass))
+                }
+                _ => None,
+            })
+            .map(|(node, pass)| match pass.execute.as_send() {
+                Some(execute) => Ok((node, pass.identifier.as_str(), execute)),
+                None => Err(Error::PassExecutorNotSend(pass.identifier.clone())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let workers = rayon::current_num_threads().min(passes.len());
+        let passes = Mutex::new(passes.into_iter());
+
+        let device = cmd.device().clone();
+        let queue_info = *cmd.queue_info();
+        let timestamp_valid_bits = cmd.timestamp_valid_bits();
+        let pool = &local_pool.pool;
+        let rendering = &rendering;
+        // Each worker takes passes until there are none left, so the amount of command pools and local pools is bounded by the thread count.
+        let recorded = (0..workers)
+            .into_par_iter()
+            .map(|_| -> Result<WorkerRecording<D, A>> {
+                let mut worker_pool = LocalPool::new(pool.clone())?;
+                let mut recorded = Vec::new();
+                loop {
+                    let next = passes.lock().map_err(|_| Error::PoisonError)?.next();
+                    let Some((node, identifier, mut execute)) = next else { break; };
+                    let handle = worker_pool
+                        .command_pool(&device, queue_info.family_index)?
+                        .allocate(vk::CommandBufferLevel::SECONDARY)?;
+                    let cmd = IncompleteCommandBuffer::<D, A>::new_secondary(
+                        device.clone(),
+                        handle,
+                        queue_info,
+                        timestamp_valid_bits,
+                        rendering
+                            .get(&node)
+                            .map(SecondaryRenderingInfo::from)
+                            .as_ref(),
+                        pool.pipelines.clone(),
+                        pool.descriptors.clone(),
                     )?;
-                    recorded_nodes.push(*child);
+                    let mut user_data = user_data.clone();
+                    let cmd = execute
+                        .execute(cmd, &mut worker_pool, bindings, &mut user_data)
+                        .with_context(|| format!("Failed to record pass `{identifier}`"))?;
+                    recorded.push((node, cmd.finish_secondary()?));
                 }
+                Ok((worker_pool, recorded))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut secondaries = HashMap::new();
+        for (worker_pool, recorded) in recorded {
+            local_pool.keep_alive(worker_pool);
+            secondaries.extend(recorded);
+        }
+
+        let graph = &self.task_graph().graph;
+        let order = self.execution_order();
+        let mut split = SplitBarriers::new(self, &order, cmd.queue_info().flags);
+        for node in order {
+            if node == source {
+                continue;
             }
-            // Now we swap all recorded nodes to the active set
-            for node in recorded_nodes {
-                insert_in_active_set(node, self, &mut active, &mut children);
+            cmd = split.wait(node, local_pool, cmd);
+            if split.is_split(node) {
+                cmd = split.signal(self, node, bindings, local_pool, cmd)?;
+                continue;
+            }
+            match graph.node_weight(node).unwrap() {
+                Node::Task(pass) => {
+                    let secondary = secondaries.remove(&node).unwrap();
+                    let info = rendering.get(&node);
+                    cmd = match &self.profiler {
+                        None => record_secondary_pass(pass, info, secondary, cmd, debug.clone())?,
+                        Some(profiler) => profiler.time_pass(&pass.identifier, cmd, |cmd| {
+                            record_secondary_pass(pass, info, secondary, cmd, debug.clone())
+                        })?,
+                    };
+                }
+                Node::Barrier(barrier) => {
+                    let dst_resource = PassGraph::barrier_dst_resource(graph, node)?;
+                    cmd = record_barrier(barrier, dst_resource, bindings, BarrierKind::Local, cmd)?;
+                }
+                Node::_Unreachable(_) => {
+                    unreachable!()
+                }
             }
         }
 
+        self.store_history_layouts(bindings)?;
         Ok(cmd)
     }
 }
@@ -3,6 +3,19 @@
 use ash::vk;
 
 use crate::graph::virtual_resource::VirtualResource;
+use crate::pipeline::PipelineStage;
+
+/// All access flags that write to a resource.
+pub(crate) const WRITE_ACCESS: vk::AccessFlags2 = vk::AccessFlags2::from_raw(
+    vk::AccessFlags2::SHADER_WRITE.as_raw()
+        | vk::AccessFlags2::SHADER_STORAGE_WRITE.as_raw()
+        | vk::AccessFlags2::COLOR_ATTACHMENT_WRITE.as_raw()
+        | vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE.as_raw()
+        | vk::AccessFlags2::TRANSFER_WRITE.as_raw()
+        | vk::AccessFlags2::HOST_WRITE.as_raw()
+        | vk::AccessFlags2::MEMORY_WRITE.as_raw()
+        | vk::AccessFlags2::ACCELERATION_STRUCTURE_WRITE_KHR.as_raw(),
+);
 
 /// Type of a resource in the pass graph.
 #[derive(Debug, Default, Copy, Clone, Hash, PartialEq, Eq)]
@@ -12,18 +25,70 @@ pub enum ResourceType {
     Image,
     /// Buffer resource
     Buffer,
+    /// Acceleration structure resource
+    AccelerationStructure,
+}
+
+/// Load and store operations for a single aspect of an attachment.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct AttachmentOps {
+    /// Operation used to initialize the attachment at the start of the pass.
+    pub load_op: vk::AttachmentLoadOp,
+    /// Operation used to write the attachment to memory at the end of the pass.
+    pub store_op: vk::AttachmentStoreOp,
+}
+
+impl AttachmentOps {
+    /// Load the previous contents of the attachment, and store the result.
+    pub const LOAD: Self = Self {
+        load_op: vk::AttachmentLoadOp::LOAD,
+        store_op: vk::AttachmentStoreOp::STORE,
+    };
+    /// Clear the attachment, and store the result.
+    pub const CLEAR: Self = Self {
+        load_op: vk::AttachmentLoadOp::CLEAR,
+        store_op: vk::AttachmentStoreOp::STORE,
+    };
+}
+
+/// State of a resource outside of a pass graph. This is used to [import](crate::PassGraph::import) resources that are
+/// already in use when the graph starts, and to [export](crate::PassGraph::export_with_state) resources in a known state
+/// when the graph ends.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ResourceState {
+    /// Layout of the image. This is ignored for buffers and acceleration structures.
+    pub layout: vk::ImageLayout,
+    /// Pipeline stages the resource is used in outside of the graph.
+    pub stage: PipelineStage,
+    /// Memory accesses to the resource outside of the graph.
+    pub access: vk::AccessFlags2,
+    /// Queue family that owns the resource outside of the graph. If this is not the queue family the graph is recorded on,
+    /// the graph records its half of a queue family ownership transfer for images with exclusive sharing mode.
+    /// The other half must be recorded by the user. If this is `None`, no ownership transfer is done.
+    /// This must be `None` for buffers and acceleration structures.
+    pub queue_family: Option<u32>,
 }
 
-#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Hash)]
 pub(crate) enum AttachmentType {
     #[default]
     Color,
     Depth,
+    Stencil,
+    /// Combined depth and stencil attachment. The load and store operations of the pass resource apply to the depth aspect.
+    DepthStencil(AttachmentOps),
     Resolve(VirtualResource),
 }
 
+impl AttachmentType {
+    /// Whether this attachment is used as the depth attachment of a render pass.
+    pub fn is_depth(&self) -> bool {
+        matches!(self, AttachmentType::Depth | AttachmentType::DepthStencil(_))
+    }
+}
+
 /// Resource usage in a task graph.
-#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Hash)]
 #[allow(dead_code)]
 pub(crate) enum ResourceUsage {
     #[default]
@@ -32,6 +97,16 @@ pub(crate) enum ResourceUsage {
     Attachment(AttachmentType),
     ShaderRead,
     ShaderWrite,
+    UniformRead,
+    VertexRead,
+    IndexRead,
+    IndirectRead,
+    TransferRead,
+    TransferWrite,
+    AccelerationStructureRead,
+    AccelerationStructureWrite,
+    /// The resource is used outside of the graph.
+    External(ResourceState),
 }
 
 impl ResourceUsage {
@@ -43,14 +118,27 @@ impl ResourceUsage {
             ResourceUsage::Attachment(AttachmentType::Color) => {
                 vk::AccessFlags2::COLOR_ATTACHMENT_WRITE
             }
-            ResourceUsage::Attachment(AttachmentType::Depth) => {
-                vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE
-            }
+            ResourceUsage::Attachment(
+                AttachmentType::Depth | AttachmentType::Stencil | AttachmentType::DepthStencil(_),
+            ) => vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE,
             ResourceUsage::Attachment(AttachmentType::Resolve(_)) => {
                 vk::AccessFlags2::COLOR_ATTACHMENT_WRITE
             }
             ResourceUsage::ShaderRead => vk::AccessFlags2::SHADER_READ,
             ResourceUsage::ShaderWrite => vk::AccessFlags2::SHADER_WRITE,
+            ResourceUsage::UniformRead => vk::AccessFlags2::UNIFORM_READ,
+            ResourceUsage::VertexRead => vk::AccessFlags2::VERTEX_ATTRIBUTE_READ,
+            ResourceUsage::IndexRead => vk::AccessFlags2::INDEX_READ,
+            ResourceUsage::IndirectRead => vk::AccessFlags2::INDIRECT_COMMAND_READ,
+            ResourceUsage::TransferRead => vk::AccessFlags2::TRANSFER_READ,
+            ResourceUsage::TransferWrite => vk::AccessFlags2::TRANSFER_WRITE,
+            ResourceUsage::AccelerationStructureRead => {
+                vk::AccessFlags2::ACCELERATION_STRUCTURE_READ_KHR
+            }
+            ResourceUsage::AccelerationStructureWrite => {
+                vk::AccessFlags2::ACCELERATION_STRUCTURE_WRITE_KHR
+            }
+            ResourceUsage::External(state) => state.access,
         }
     }
 
@@ -62,6 +150,15 @@ impl ResourceUsage {
             ResourceUsage::Attachment(_) => false,
             ResourceUsage::ShaderRead => true,
             ResourceUsage::ShaderWrite => false,
+            ResourceUsage::UniformRead => true,
+            ResourceUsage::VertexRead => true,
+            ResourceUsage::IndexRead => true,
+            ResourceUsage::IndirectRead => true,
+            ResourceUsage::TransferRead => true,
+            ResourceUsage::TransferWrite => false,
+            ResourceUsage::AccelerationStructureRead => true,
+            ResourceUsage::AccelerationStructureWrite => false,
+            ResourceUsage::External(state) => !state.access.intersects(WRITE_ACCESS),
         }
     }
 }
@@ -0,0 +1,289 @@
+//! Reusable fragments of a pass graph.
+//!
+//! A [`SubGraph`] is a group of passes together with the transient resources they use, that can be inserted into a
+//! [`PassGraph`] with [`PassGraph::add_subgraph()`]. Every sub-graph has a name, which is used to prefix the names of its passes
+//! and of the resources created through it. This way, the same fragment can be inserted multiple times into a graph without
+//! the names of its internal resources colliding.
+//!
+//! A fragment is usually written as a function that takes the resources it reads from and writes to as arguments.
+//! These resources are shared with the rest of the graph, so their names are not prefixed. Instead, they must be imported into
+//! the sub-graph with [`SubGraph::import()`]. Adding a pass that uses a resource that was neither created through the sub-graph
+//! nor imported into it fails with [`Error::UnprefixedSubGraphResource`], since its name could collide with the same resource in
+//! another copy of the fragment.
+//!
+//! # Example
+//!
+//! ```
+//! use phobos::prelude::*;
+//!
+//! fn bloom<'cb>(sub: SubGraph<'cb, domain::All>, input: &VirtualResource, output: &VirtualResource) -> Result<SubGraph<'cb, domain::All>> {
+//!     let sub = sub.import(input).import(output);
+//!     // This resource is named `bloom_a/blur` or `bloom_b/blur`, depending on the sub-graph.
+//!     let blur = sub.image("blur");
+//!     let blur_pass = PassBuilder::render("blur")
+//!         .sample_image(input, PipelineStage::FRAGMENT_SHADER)
+//!         .clear_color_attachment(&blur, ClearColor::Float([0.0; 4]))?
+//!         .build();
+//!     let composite_pass = PassBuilder::render("composite")
+//!         .sample_image(blur_pass.output(&blur).unwrap(), PipelineStage::FRAGMENT_SHADER)
+//!         .load_color_attachment(output)?
+//!         .build();
+//!     let info = TransientImageInfo {
+//!         width: 1920,
+//!         height: 1080,
+//!         format: vk::Format::R16G16B16A16_SFLOAT,
+//!         usage: vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::SAMPLED,
+//!         samples: vk::SampleCountFlags::TYPE_1,
+//!         aspect: vk::ImageAspectFlags::COLOR,
+//!     };
+//!     sub.transient_image(&blur, info)?
+//!         .add_pass(blur_pass)?
+//!         .add_pass(composite_pass)
+//! }
+//!
+//! let bloom_a = bloom(SubGraph::new("bloom_a"), &hdr_a, &swapchain)?;
+//! let swapchain = bloom_a.output(&swapchain).unwrap();
+//! let bloom_b = bloom(SubGraph::new("bloom_b"), &hdr_b, &swapchain)?;
+//! let graph = PassGraph::<domain::All>::new()
+//!     .add_subgraph(bloom_a)?
+//!     .add_subgraph(bloom_b)?;
+//! ```
+
+use anyhow::Result;
+
+use crate::{Allocator, DefaultAllocator, Error, Pass, PassGraph, PassQueue, VirtualResource};
+use crate::graph::resource::ResourceType;
+use crate::graph::transient::{TransientBufferInfo, TransientImageInfo, TransientResourceInfo};
+use crate::sync::domain::ExecutionDomain;
+
+/// A named group of passes that can be inserted into a pass graph. See the [module-level documentation](crate::graph::sub_graph)
+/// for more information.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct SubGraph<'cb, D: ExecutionDomain, U = (), A: Allocator = DefaultAllocator> {
+    name: String,
+    #[derivative(Debug = "ignore")]
+    passes: Vec<Pass<'cb, D, U, A>>,
+    transients: Vec<(VirtualResource, TransientResourceInfo)>,
+    exports: Vec<VirtualResource>,
+    /// Resources shared with the rest of the graph.
+    imports: Vec<VirtualResource>,
+    /// Names of this sub-graph and all sub-graphs nested in it.
+    names: Vec<String>,
+}
+
+impl<'cb, D: ExecutionDomain, U, A: Allocator> SubGraph<'cb, D, U, A> {
+    /// Create a new, empty sub-graph. The name of the sub-graph is used as a prefix for the names of its passes and resources,
+    /// so it must be unique within the graph it is inserted into.
+    pub fn new(name: impl Into<String>) -> Self {
+        let name = name.into();
+        SubGraph {
+            names: vec![name.clone()],
+            name,
+            passes: vec![],
+            transients: vec![],
+            exports: vec![],
+            imports: vec![],
+        }
+    }
+
+    /// Get the name of this sub-graph.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the name of a resource or pass inside this sub-graph.
+    fn prefixed(&self, name: &str) -> String {
+        format!("{}/{}", self.name, name)
+    }
+
+    /// Whether a resource was created through this sub-graph or one nested in it.
+    fn is_internal(&self, resource: &VirtualResource) -> bool {
+        resource.name().starts_with(&self.prefixed(""))
+    }
+
+    /// Check that a resource was created through this sub-graph or imported into it.
+    fn check_resource(&self, resource: &VirtualResource) -> Result<()> {
+        // History resources are named after the image they are the history of.
+        let name = resource
+            .name()
+            .strip_suffix("@history")
+            .unwrap_or(resource.name());
+        if self.is_internal(resource) || self.imports.iter().any(|import| import.name() == name) {
+            Ok(())
+        } else {
+            Err(Error::UnprefixedSubGraphResource {
+                subgraph: self.name.clone(),
+                resource: resource.name().to_owned(),
+            }
+            .into())
+        }
+    }
+
+    /// Import a resource that is shared with the rest of the graph, so passes in this sub-graph can use it.
+    /// Its name is not prefixed.
+    pub fn import(mut self, resource: &VirtualResource) -> Self {
+        self.imports.push(resource.clone());
+        self
+    }
+
+    /// Create an image resource that is internal to this sub-graph. Its name is prefixed with the name of the sub-graph.
+    pub fn image(&self, name: &str) -> VirtualResource {
+        VirtualResource::image(self.prefixed(name))
+    }
+
+    /// Create a buffer resource that is internal to this sub-graph. Its name is prefixed with the name of the sub-graph.
+    pub fn buffer(&self, name: &str) -> VirtualResource {
+        VirtualResource::buffer(self.prefixed(name))
+    }
+
+    /// Create an acceleration structure resource that is internal to this sub-graph. Its name is prefixed with the name of the sub-graph.
+    pub fn acceleration_structure(&self, name: &str) -> VirtualResource {
+        VirtualResource::acceleration_structure(self.prefixed(name))
+    }
+
+    /// Create an empty sub-graph to be nested inside this sub-graph with [`SubGraph::add_subgraph()`].
+    /// Its name is prefixed with the name of this sub-graph.
+    pub fn subgraph(&self, name: &str) -> Self {
+        SubGraph::new(self.prefixed(name))
+    }
+
+    /// Add a pass to this sub-graph. The name of the pass is prefixed with the name of the sub-graph.
+    /// # Errors
+    /// - Fails if the pass is a render pass that is not executed on the main queue.
+    /// - Fails if the pass uses a resource that was neither created through this sub-graph nor imported into it.
+    pub fn add_pass(mut self, mut pass: Pass<'cb, D, U, A>) -> Result<Self> {
+        if pass.is_renderpass && pass.queue() != PassQueue::Main {
+            return Err(
+                Error::Uncategorized("Render passes must be executed on the main queue").into()
+            );
+        }
+        for resource in pass.inputs.iter().chain(pass.outputs.iter()) {
+            self.check_resource(&resource.resource)?;
+        }
+        pass.name = self.prefixed(&pass.name);
+        self.passes.push(pass);
+        Ok(self)
+    }
+
+    /// Add all passes and resources of a nested sub-graph to this sub-graph. The nested sub-graph should be created with [`SubGraph::subgraph()`].
+    /// # Errors
+    /// - Fails if a sub-graph with the same name was already added to this sub-graph.
+    /// - Fails if the nested sub-graph imports a resource that was neither created through this sub-graph nor imported into it.
+    pub fn add_subgraph(mut self, subgraph: SubGraph<'cb, D, U, A>) -> Result<Self> {
+        if let Some(name) = subgraph.names.iter().find(|name| self.names.contains(name)) {
+            return Err(Error::DuplicateSubGraph(name.clone()).into());
+        }
+        for resource in &subgraph.imports {
+            self.check_resource(resource)?;
+        }
+        self.names.extend(subgraph.names);
+        self.passes.extend(subgraph.passes);
+        self.transients.extend(subgraph.transients);
+        self.exports.extend(subgraph.exports);
+        Ok(self)
+    }
+
+    /// Declare a transient image used by this sub-graph. See also [`PassGraph::transient_image()`].
+    /// # Errors
+    /// - Fails if the resource is not an image resource.
+    /// - Fails if the resource was already declared as transient.
+    /// - Fails if the resource was not created through this sub-graph.
+    pub fn transient_image(
+        self,
+        resource: &VirtualResource,
+        info: TransientImageInfo,
+    ) -> Result<Self> {
+        if resource.resource_type() != ResourceType::Image {
+            return Err(Error::Uncategorized("Transient image must be an image resource").into());
+        }
+        self.add_transient(resource, TransientResourceInfo::Image(info))
+    }
+
+    /// Declare a transient buffer used by this sub-graph. See also [`PassGraph::transient_buffer()`].
+    /// # Errors
+    /// - Fails if the resource is not a buffer resource.
+    /// - Fails if the resource was already declared as transient.
+    /// - Fails if the resource was not created through this sub-graph.
+    pub fn transient_buffer(
+        self,
+        resource: &VirtualResource,
+        info: TransientBufferInfo,
+    ) -> Result<Self> {
+        if resource.resource_type() != ResourceType::Buffer {
+            return Err(Error::Uncategorized("Transient buffer must be a buffer resource").into());
+        }
+        self.add_transient(resource, TransientResourceInfo::Buffer(info))
+    }
+
+    fn add_transient(
+        mut self,
+        resource: &VirtualResource,
+        info: TransientResourceInfo,
+    ) -> Result<Self> {
+        // Transient resources are always internal, so they must be created through the sub-graph.
+        if !self.is_internal(resource) {
+            return Err(Error::UnprefixedSubGraphResource {
+                subgraph: self.name.clone(),
+                resource: resource.name().to_owned(),
+            }
+            .into());
+        }
+        if self
+            .transients
+            .iter()
+            .any(|(transient, _)| transient.is_associated_with(resource))
+        {
+            return Err(Error::Uncategorized("Resource was already declared as transient").into());
+        }
+        self.transients.push((resource.clone(), info));
+        Ok(self)
+    }
+
+    /// Mark a resource as exported. See also [`PassGraph::export()`].
+    pub fn export(mut self, resource: &VirtualResource) -> Self {
+        self.exports.push(resource.clone());
+        self
+    }
+
+    /// Get the newest version of a resource written by a pass in this sub-graph, or `None` if no pass in the sub-graph writes to it.
+    /// Use this to access the output of a sub-graph from passes after it.
+    pub fn output(&self, resource: &VirtualResource) -> Option<&VirtualResource> {
+        self.passes
+            .iter()
+            .rev()
+            .find_map(|pass| pass.output(resource))
+    }
+}
+
+impl<'cb, D: ExecutionDomain, U, A: Allocator> PassGraph<'cb, D, U, A> {
+    /// Add all passes of a sub-graph to this graph, and declare its transient and exported resources.
+    /// See the [`sub_graph`](crate::graph::sub_graph) module documentation for more information.
+    /// # Errors
+    /// - Fails if a sub-graph with the same name was already added to this graph.
+    /// - Fails if adding one of the passes fails, see [`PassGraph::add_pass()`].
+    /// - Fails if declaring one of the transient resources fails, see [`PassGraph::transient_image()`].
+    pub fn add_subgraph(mut self, subgraph: SubGraph<'cb, D, U, A>) -> Result<Self> {
+        if let Some(name) = subgraph
+            .names
+            .iter()
+            .find(|name| self.subgraphs.contains(*name))
+        {
+            return Err(Error::DuplicateSubGraph(name.clone()).into());
+        }
+        self.subgraphs.extend(subgraph.names);
+        for (resource, info) in subgraph.transients {
+            self = match info {
+                TransientResourceInfo::Image(info) => self.transient_image(&resource, info)?,
+                TransientResourceInfo::Buffer(info) => self.transient_buffer(&resource, info)?,
+            };
+        }
+        for resource in &subgraph.exports {
+            self = self.export(resource);
+        }
+        for pass in subgraph.passes {
+            self = self.add_pass(pass)?;
+        }
+        Ok(self)
+    }
+}
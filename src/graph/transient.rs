@@ -0,0 +1,261 @@
+//! Transient resources are resources that are owned by the pass graph instead of by the user.
+//!
+//! Instead of binding a physical resource to each virtual resource in the graph, a virtual resource can be declared
+//! as transient through [`PassGraph::transient_image()`](crate::PassGraph::transient_image) or
+//! [`PassGraph::transient_buffer()`](crate::PassGraph::transient_buffer), together with a description of the resource.
+//! When the graph is built, the lifetime of each transient resource is computed from the task graph, and transient resources
+//! whose lifetimes do not overlap are assigned the same memory. The actual resources are created when the graph is recorded, and are kept
+//! alive by the [`LocalPool`] that was used for recording.
+//!
+//! # Example
+//!
+//! ```
+//! use phobos::prelude::*;
+//!
+//! let gbuffer = image!("gbuffer");
+//! let graph = PassGraph::<domain::Graphics>::new()
+//!     .transient_image(&gbuffer, TransientImageInfo {
+//!         width: 1920,
+//!         height: 1080,
+//!         format: vk::Format::R16G16B16A16_SFLOAT,
+//!         usage: vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::SAMPLED,
+//!         samples: vk::SampleCountFlags::TYPE_1,
+//!         aspect: vk::ImageAspectFlags::COLOR,
+//!     })?;
+//! // Add passes that use `gbuffer` as usual. There is no need to bind it in the physical resource bindings.
+//! ```
+
+use anyhow::Result;
+use ash::vk;
+
+use crate::{
+    Allocator, Buffer, Device, Image, ImageView, MemoryType, PhysicalResourceBindings,
+    VirtualResource,
+};
+use crate::pool::{LocalPool, Poolable, Pooled};
+use crate::resource::image::ImageInfo;
+
+/// Describes a transient image owned by the pass graph.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TransientImageInfo {
+    /// Width of the image in pixels.
+    pub width: u32,
+    /// Height of the image in pixels.
+    pub height: u32,
+    /// Format of the image.
+    pub format: vk::Format,
+    /// All usage flags this image will be used with.
+    pub usage: vk::ImageUsageFlags,
+    /// Number of MSAA samples.
+    pub samples: vk::SampleCountFlags,
+    /// Aspect used for the image view that is bound to the virtual resource.
+    pub aspect: vk::ImageAspectFlags,
+}
+
+/// Describes a transient buffer owned by the pass graph.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TransientBufferInfo {
+    /// Size of the buffer in bytes.
+    pub size: vk::DeviceSize,
+    /// All usage flags this buffer will be used with.
+    pub usage: vk::BufferUsageFlags,
+}
+
+/// Description of any transient resource.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum TransientResourceInfo {
+    Image(TransientImageInfo),
+    Buffer(TransientBufferInfo),
+}
+
+/// A transient resource declared in a pass graph.
+#[derive(Debug, Clone)]
+pub(crate) struct TransientResource {
+    pub(crate) resource: VirtualResource,
+    pub(crate) info: TransientResourceInfo,
+    /// Memory slot this resource was assigned to while building the graph. Resources in the same
+    /// slot share their memory.
+    pub(crate) slot: usize,
+}
+
+/// Key used to look up transient memory blocks in a [`Pool`](crate::pool::Pool).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TransientMemoryKey {
+    size: vk::DeviceSize,
+    alignment: vk::DeviceSize,
+    memory_type_bits: u32,
+}
+
+/// A block of device memory that one or more transient resources are bound to.
+pub struct TransientMemory<A: Allocator> {
+    memory: A::Allocation,
+}
+
+/// Physical resources created for the transient resources of a single graph recording. These are kept alive
+/// inside a [`LocalPool`] until it is dropped.
+pub(crate) struct TransientAllocation<A: Allocator> {
+    // Note that the order of these fields is important, views must be dropped first and memory last.
+    _views: Vec<ImageView>,
+    _images: Vec<Image<A>>,
+    _buffers: Vec<Buffer<A>>,
+    _memory: Vec<Pooled<TransientMemory<A>>>,
+}
+
+impl TransientResourceInfo {
+    /// Returns true if two resources with these descriptions are allowed to share memory.
+    pub(crate) fn can_alias(&self, other: &TransientResourceInfo) -> bool {
+        matches!(
+            (self, other),
+            (TransientResourceInfo::Image(_), TransientResourceInfo::Image(_))
+                | (TransientResourceInfo::Buffer(_), TransientResourceInfo::Buffer(_))
+        )
+    }
+}
+
+impl<A: Allocator> TransientMemory<A> {
+    /// Allocate a new block of transient memory.
+    pub(crate) fn new(allocator: &mut A, key: &TransientMemoryKey) -> Result<Self> {
+        let requirements = vk::MemoryRequirements {
+            size: key.size,
+            alignment: key.alignment,
+            memory_type_bits: key.memory_type_bits,
+        };
+        Ok(Self {
+            memory: allocator.allocate("transient_memory", &requirements, MemoryType::GpuOnly)?,
+        })
+    }
+}
+
+impl<A: Allocator> Poolable for TransientMemory<A> {
+    type Key = TransientMemoryKey;
+
+    fn on_release(&mut self) {}
+}
+
+enum UnboundResource<A: Allocator> {
+    Image(Image<A>, vk::ImageAspectFlags),
+    Buffer(Buffer<A>),
+}
+
+impl<A: Allocator> UnboundResource<A> {
+    fn new(device: &Device, info: &TransientResourceInfo) -> Result<Self> {
+        Ok(match info {
+            TransientResourceInfo::Image(info) => UnboundResource::Image(
+                Image::new_unbound(
+                    device.clone(),
+                    &ImageInfo {
+                        width: info.width,
+                        height: info.height,
+                        usage: info.usage,
+                        format: info.format,
+                        samples: info.samples,
+                        ..Default::default()
+                    },
+                )?,
+                info.aspect,
+            ),
+            TransientResourceInfo::Buffer(info) => {
+                UnboundResource::Buffer(Buffer::new_unbound(device.clone(), info.size, info.usage)?)
+            }
+        })
+    }
+
+    fn memory_requirements(&self) -> vk::MemoryRequirements {
+        match self {
+            UnboundResource::Image(image, _) => image.memory_requirements(),
+            UnboundResource::Buffer(buffer) => buffer.memory_requirements(),
+        }
+    }
+
+    fn bind_memory(&mut self, memory: &A::Allocation) -> Result<()> {
+        match self {
+            UnboundResource::Image(image, _) => image.bind_memory(memory),
+            UnboundResource::Buffer(buffer) => buffer.bind_memory(memory),
+        }
+    }
+}
+
+/// Create all transient resources in the graph and bind them to their virtual resources. Resources in the same slot are
+/// bound to the same memory block, as long as their memory requirements are compatible. The returned bindings contain
+/// all bindings in `bindings`, plus a binding for each transient resource.
+pub(crate) fn allocate_transients<A: Allocator>(
+    device: &Device,
+    transients: &[TransientResource],
+    local_pool: &mut LocalPool<A>,
+    bindings: &PhysicalResourceBindings,
+) -> Result<PhysicalResourceBindings> {
+    let mut resources = transients
+        .iter()
+        .map(|transient| UnboundResource::<A>::new(device, &transient.info))
+        .collect::<Result<Vec<_>>>()?;
+
+    // Group resources per slot. If the memory requirements of a resource are incompatible with the others in its slot,
+    // it gets a memory block of its own.
+    let mut blocks: Vec<(TransientMemoryKey, Vec<usize>)> = Vec::new();
+    let mut slot_blocks: Vec<(usize, usize)> = Vec::new();
+    for (index, (transient, resource)) in transients.iter().zip(resources.iter()).enumerate() {
+        let requirements = resource.memory_requirements();
+        let existing = slot_blocks
+            .iter()
+            .filter(|(slot, _)| *slot == transient.slot)
+            .map(|(_, block)| *block)
+            .find(|block| blocks[*block].0.memory_type_bits & requirements.memory_type_bits != 0);
+        match existing {
+            Some(block) => {
+                let (key, members) = &mut blocks[block];
+                key.size = key.size.max(requirements.size);
+                key.alignment = key.alignment.max(requirements.alignment);
+                key.memory_type_bits &= requirements.memory_type_bits;
+                members.push(index);
+            }
+            None => {
+                slot_blocks.push((transient.slot, blocks.len()));
+                blocks.push((
+                    TransientMemoryKey {
+                        size: requirements.size,
+                        alignment: requirements.alignment,
+                        memory_type_bits: requirements.memory_type_bits,
+                    },
+                    vec![index],
+                ));
+            }
+        }
+    }
+
+    let mut memory = Vec::with_capacity(blocks.len());
+    for (key, members) in &blocks {
+        let block = TransientMemory::new_in_pool(&local_pool.pool.transient_memory, key)?;
+        for member in members {
+            resources[*member].bind_memory(&block.memory)?;
+        }
+        memory.push(block);
+    }
+
+    let mut bindings = bindings.clone();
+    let mut views = Vec::new();
+    let mut images = Vec::new();
+    let mut buffers = Vec::new();
+    for (transient, resource) in transients.iter().zip(resources) {
+        match resource {
+            UnboundResource::Image(image, aspect) => {
+                let view = image.view(aspect)?;
+                bindings.bind_image(transient.resource.name(), &view);
+                views.push(view);
+                images.push(image);
+            }
+            UnboundResource::Buffer(buffer) => {
+                bindings.bind_buffer(transient.resource.name(), &buffer.view_full());
+                buffers.push(buffer);
+            }
+        }
+    }
+
+    local_pool.transients.push(TransientAllocation {
+        _views: views,
+        _images: images,
+        _buffers: buffers,
+        _memory: memory,
+    });
+
+    Ok(bindings)
+}
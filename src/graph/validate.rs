@@ -0,0 +1,211 @@
+//! Validation of a built pass graph against its physical resource bindings.
+//!
+//! Recording a graph first checks that every resource used by a pass is bound to a physical resource of the correct type,
+//! and that the images used as attachments are compatible with how they are used. Each problem is reported as an [`Error`]
+//! naming the pass and the resource, instead of failing somewhere in the middle of recording. All problems are reported together
+//! in an [`Error::InvalidPassGraph`].
+//! The same checks can be run without recording through [`BuiltPassGraph::validate()`], for example to report problems to the user in an editor.
+//!
+//! # Example
+//!
+//! ```
+//! use phobos::prelude::*;
+//!
+//! let graph = graph.build()?;
+//! let mut bindings = PhysicalResourceBindings::new();
+//! bindings.bind_image("swapchain", &swapchain_view);
+//! if let Err(error) = graph.validate(&bindings) {
+//!     if let Some(Error::InvalidPassGraph(problems)) = error.downcast_ref::<Error>() {
+//!         for problem in problems {
+//!             if let Error::MissingBinding { pass, resource } = problem {
+//!                 // ...
+//!             }
+//!         }
+//!     }
+//! }
+//! ```
+
+use anyhow::Result;
+use ash::vk;
+
+use crate::{Allocator, Error, ImageView, PhysicalResourceBindings, VirtualResource};
+use crate::graph::pass_graph::{BuiltPassGraph, PassNode, PassResource};
+use crate::graph::physical_resource::PhysicalResource;
+use crate::graph::resource::{AttachmentType, ResourceType, ResourceUsage};
+use crate::graph::task_graph::Node;
+use crate::resource::image::format_aspects;
+use crate::sync::domain::ExecutionDomain;
+
+impl<'cb, D: ExecutionDomain, U, A: Allocator> BuiltPassGraph<'cb, D, U, A> {
+    /// Check that this graph can be recorded with the given bindings. Transient resources are created by the graph,
+    /// so they do not need to be bound. This is done automatically when recording the graph.
+    /// # Errors
+    /// * Fails with [`Error::InvalidPassGraph`] containing every problem found. These can be:
+    ///     * [`Error::MissingBinding`] if a resource used by a pass is not bound.
+    ///     * [`Error::BindingTypeMismatch`] if a resource is bound to a different type of physical resource.
+    ///     * [`Error::AttachmentFormatMismatch`] if the format of an attachment does not match how it is used,
+    ///       for example when a color image is used as a depth attachment, or when a resolve attachment has a different format than its source.
+    ///     * [`Error::SampleCountMismatch`] if the attachments of a render pass have different sample counts, or if the
+    ///       destination of a resolve is multisampled.
+    pub fn validate(&self, bindings: &PhysicalResourceBindings) -> Result<()> {
+        let problems = self.validation_errors(bindings);
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidPassGraph(problems).into())
+        }
+    }
+
+    /// Get every problem that prevents this graph from being recorded with the given bindings.
+    /// See [`BuiltPassGraph::validate()`] for the problems that are detected.
+    pub fn validation_errors(&self, bindings: &PhysicalResourceBindings) -> Vec<Error> {
+        let mut problems = Vec::new();
+        for node in self.task_graph().graph.node_indices() {
+            if node == self.source() {
+                continue;
+            }
+            let Node::Task(pass) = self.task_graph().graph.node_weight(node).unwrap() else { continue; };
+            for resource in pass.inputs.iter().chain(pass.outputs.iter()) {
+                if let Err(problem) = self.validate_binding(pass, resource, bindings) {
+                    problems.push(problem);
+                }
+            }
+            if pass.is_renderpass {
+                self.validate_attachments(pass, bindings, &mut problems);
+            }
+        }
+        problems
+    }
+
+    fn is_transient(&self, resource: &PassResource) -> bool {
+        self.transients
+            .iter()
+            .any(|transient| transient.resource.is_associated_with(&resource.resource))
+    }
+
+    /// Resolve the physical resource a resource is bound to. For subresources, this is the resource they are a part of.
+    fn resolve_whole<'b>(
+        pass: &PassNode<PassResource, D, U, A>,
+        resource: &PassResource,
+        bindings: &'b PhysicalResourceBindings,
+    ) -> Result<&'b PhysicalResource, Error> {
+        bindings
+            .resolve(&resource.resource.whole())
+            .ok_or_else(|| Error::MissingBinding {
+                pass: pass.identifier.clone(),
+                resource: resource.resource.uid(),
+            })
+    }
+
+    fn validate_binding(
+        &self,
+        pass: &PassNode<PassResource, D, U, A>,
+        resource: &PassResource,
+        bindings: &PhysicalResourceBindings,
+    ) -> Result<(), Error> {
+        if self.is_transient(resource) {
+            return Ok(());
+        }
+        let expected = resource.resource.resource_type();
+        let found = match Self::resolve_whole(pass, resource, bindings)? {
+            PhysicalResource::Image(_) => ResourceType::Image,
+            PhysicalResource::Buffer(_) => ResourceType::Buffer,
+            PhysicalResource::AccelerationStructure(_) => ResourceType::AccelerationStructure,
+        };
+        if found != expected {
+            return Err(Error::BindingTypeMismatch {
+                pass: pass.identifier.clone(),
+                resource: resource.resource.uid(),
+                expected,
+            });
+        }
+        Ok(())
+    }
+
+    /// Check the attachments of a render pass. Missing or mismatched bindings are already reported by
+    /// [`Self::validate_binding()`], so those attachments are skipped here.
+    fn validate_attachments(
+        &self,
+        pass: &PassNode<PassResource, D, U, A>,
+        bindings: &PhysicalResourceBindings,
+        problems: &mut Vec<Error>,
+    ) {
+        let image = |resource: &PassResource| -> Option<ImageView> {
+            if self.is_transient(resource) {
+                return None;
+            }
+            match Self::resolve_whole(pass, resource, bindings) {
+                Ok(PhysicalResource::Image(image)) => Some(image.clone()),
+                _ => None,
+            }
+        };
+
+        let mut samples = None;
+        for resource in &pass.outputs {
+            let ResourceUsage::Attachment(ty) = &resource.usage else { continue; };
+            let Some(view) = image(resource) else { continue; };
+            let (usage, aspects) = match ty {
+                AttachmentType::Color => ("color", vk::ImageAspectFlags::COLOR),
+                AttachmentType::Depth => ("depth", vk::ImageAspectFlags::DEPTH),
+                AttachmentType::Stencil => ("stencil", vk::ImageAspectFlags::STENCIL),
+                AttachmentType::DepthStencil(_) => (
+                    "depth-stencil",
+                    vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL,
+                ),
+                AttachmentType::Resolve(src) => {
+                    self.validate_resolve(pass, resource, &view, src, bindings, problems);
+                    continue;
+                }
+            };
+            if !format_aspects(view.format()).contains(aspects) {
+                problems.push(Error::AttachmentFormatMismatch {
+                    pass: pass.identifier.clone(),
+                    resource: resource.resource.uid(),
+                    format: view.format(),
+                    usage,
+                });
+            }
+            // All attachments must have the same sample count as the first one.
+            let expected = *samples.get_or_insert(view.samples());
+            if view.samples() != expected {
+                problems.push(Error::SampleCountMismatch {
+                    pass: pass.identifier.clone(),
+                    resource: resource.resource.uid(),
+                    expected,
+                    found: view.samples(),
+                });
+            }
+        }
+    }
+
+    fn validate_resolve(
+        &self,
+        pass: &PassNode<PassResource, D, U, A>,
+        resource: &PassResource,
+        view: &ImageView,
+        src: &VirtualResource,
+        bindings: &PhysicalResourceBindings,
+        problems: &mut Vec<Error>,
+    ) {
+        if view.samples() != vk::SampleCountFlags::TYPE_1 {
+            problems.push(Error::SampleCountMismatch {
+                pass: pass.identifier.clone(),
+                resource: resource.resource.uid(),
+                expected: vk::SampleCountFlags::TYPE_1,
+                found: view.samples(),
+            });
+        }
+        let src_format = match bindings.resolve(&src.whole()) {
+            Some(PhysicalResource::Image(src)) => src.format(),
+            _ => return,
+        };
+        if view.format() != src_format {
+            problems.push(Error::AttachmentFormatMismatch {
+                pass: pass.identifier.clone(),
+                resource: resource.resource.uid(),
+                format: view.format(),
+                usage: "resolve",
+            });
+        }
+    }
+}
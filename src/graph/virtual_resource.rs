@@ -2,16 +2,25 @@
 
 use std::fmt::{Display, Formatter};
 
+use ash::vk;
+
 use crate::graph::resource::ResourceType;
+use crate::resource::image::ImageSubresource;
 
 /// Represents a virtual resource in the system, uniquely identified by a string.
 ///
 /// Note that the resource named `swapchain` is assumed to always be the swapchain resource for presenting.
+///
+/// An image resource can also refer to a range of mip levels and array layers of its image through [`VirtualResource::subresource()`].
+/// Each subresource is tracked separately by the graph, with its own versions and barriers. This means that subresources of the same image
+/// that are used in a graph must not overlap, and the full image must not be used in the same graph. Adding a pass that breaks this rule
+/// fails with [`Error::OverlappingSubresources`](crate::Error::OverlappingSubresources).
 #[derive(Debug, Default, Clone, Hash, Eq, PartialEq)]
 pub struct VirtualResource {
     pub(crate) name: String,
     pub(crate) version: usize,
     ty: ResourceType,
+    range: Option<ImageSubresource>,
 }
 
 /// Holds a hashed resource in the pass graph implementation
@@ -33,6 +42,7 @@ impl VirtualResource {
             name: name.into(),
             version: usize::MAX,
             ty: ResourceType::Image,
+            range: None,
         }
     }
 
@@ -42,6 +52,7 @@ impl VirtualResource {
             name: name.into(),
             version: 0,
             ty: ResourceType::Image,
+            range: None,
         }
     }
 
@@ -51,6 +62,17 @@ impl VirtualResource {
             name: name.into(),
             version: 0,
             ty: ResourceType::Buffer,
+            range: None,
+        }
+    }
+
+    /// Create a new acceleration structure virtual resource.
+    pub fn acceleration_structure(name: impl Into<String>) -> Self {
+        VirtualResource {
+            name: name.into(),
+            version: 0,
+            ty: ResourceType::AccelerationStructure,
+            range: None,
         }
     }
 
@@ -61,9 +83,56 @@ impl VirtualResource {
             name: self.name.clone(),
             version: self.version + 1,
             ty: self.ty,
+            range: self.range,
+        }
+    }
+
+    /// Create a virtual resource that refers to a range of mip levels and array layers of this image resource.
+    /// The range is relative to the image view bound to this resource. When the graph is recorded, a view of only this range is
+    /// created, and bound to the returned resource.
+    /// # Example
+    /// ```
+    /// use phobos::prelude::*;
+    ///
+    /// let bloom = image!("bloom");
+    /// // Read from the first mip level, and write to the second one.
+    /// let pass = PassBuilder::render("downsample")
+    ///     .sample_image(&bloom.subresource(ImageSubresource::mip(0)), PipelineStage::FRAGMENT_SHADER)
+    ///     .load_color_attachment(&bloom.subresource(ImageSubresource::mip(1)))?
+    ///     .build();
+    /// ```
+    pub fn subresource(&self, range: ImageSubresource) -> Self {
+        VirtualResource {
+            name: self.name.clone(),
+            version: self.version,
+            ty: self.ty,
+            range: Some(range),
+        }
+    }
+
+    /// Get the virtual resource for the entire resource this resource is a subresource of.
+    pub(crate) fn whole(&self) -> Self {
+        VirtualResource {
+            name: self.name.clone(),
+            version: self.version,
+            ty: self.ty,
+            range: None,
         }
     }
 
+    /// Get the resource holding the contents this image had at the end of the previous frame. This is only meaningful
+    /// if the image was declared as a history image with [`PassGraph::history_image()`](crate::PassGraph::history_image).
+    /// See the [`history`](crate::graph::history) module documentation for more information.
+    pub fn history(&self) -> Self {
+        VirtualResource::image(format!("{}@history", self.name))
+    }
+
+    /// Get the range of mip levels and array layers this resource refers to. If this is `None`, the resource refers to the
+    /// entire bound resource.
+    pub fn subresource_range(&self) -> Option<ImageSubresource> {
+        self.range
+    }
+
     /// Returns the full, original name of the resource
     pub fn name(&self) -> &str {
         &self.name
@@ -84,6 +153,23 @@ impl VirtualResource {
         self.name() == rhs.name()
     }
 
+    /// Check if these virtual resources refer to the same subresource of the same physical resource, possibly with a different version.
+    pub fn is_same_subresource(&self, rhs: &VirtualResource) -> bool {
+        self.is_associated_with(rhs) && self.range == rhs.range
+    }
+
+    /// Check if these virtual resources refer to different, but overlapping parts of the same image. The whole image overlaps
+    /// with all of its subresources.
+    pub(crate) fn overlaps(&self, rhs: &VirtualResource) -> bool {
+        if !self.is_associated_with(rhs) || self.range == rhs.range {
+            return false;
+        }
+        match (&self.range, &rhs.range) {
+            (Some(range), Some(other)) => range.overlaps(other),
+            _ => true,
+        }
+    }
+
     /// One virtual resource is older than another if it has less '+' symbols.
     pub fn is_older(lhs: &VirtualResource, rhs: &VirtualResource) -> bool {
         if !lhs.is_associated_with(rhs) {
@@ -110,14 +196,38 @@ impl VirtualResource {
     pub fn uid(&self) -> String {
         format!("{}", self)
     }
+
+    /// Get the key this resource is bound with in the [`PhysicalResourceBindings`](crate::PhysicalResourceBindings).
+    /// For subresources, this includes the subresource range.
+    pub(crate) fn binding_key(&self) -> String {
+        let Some(range) = &self.range else { return self.name.clone(); };
+        let format_range = |base: u32, count: u32, remaining: u32| {
+            if count == remaining {
+                format!("{base}..")
+            } else {
+                format!("{base}..{}", base + count)
+            }
+        };
+        format!(
+            "{}[mips {}, layers {}]",
+            self.name,
+            format_range(range.base_level, range.level_count, vk::REMAINING_MIP_LEVELS),
+            format_range(range.base_layer, range.layer_count, vk::REMAINING_ARRAY_LAYERS)
+        )
+    }
 }
 
 impl Display for VirtualResource {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         if self.version == usize::MAX {
-            write!(f, "{}_final", self.name())
+            write!(f, "{}_final", self.binding_key())
         } else {
-            write!(f, "{}{}", self.name(), String::from_utf8(vec![b'+'; self.version]).unwrap())
+            write!(
+                f,
+                "{}{}",
+                self.binding_key(),
+                String::from_utf8(vec![b'+'; self.version]).unwrap()
+            )
         }
     }
 }
@@ -137,3 +247,11 @@ macro_rules! buffer {
         ::phobos::prelude::VirtualResource::buffer($id)
     };
 }
+
+/// Syntax sugar to easily construct acceleration structure virtual resources
+#[macro_export]
+macro_rules! accel {
+    ($id:literal) => {
+        ::phobos::prelude::VirtualResource::acceleration_structure($id)
+    };
+}
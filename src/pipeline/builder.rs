@@ -181,7 +181,9 @@ impl PipelineBuilder {
         Ok(self)
     }
 
-    /// Add a shader to the pipeline.
+    /// Add a shader to the pipeline. A pipeline with a mesh shader, and optionally a task shader, does not use vertex input
+    /// or input assembly state, so any vertex bindings and attributes are ignored. Such a pipeline requires
+    /// [`ExtensionID::MeshShader`](crate::core::device::ExtensionID::MeshShader) to be enabled.
     pub fn attach_shader(mut self, info: ShaderCreateInfo) -> Self {
         self.inner.shaders.push(info);
         self
@@ -74,25 +74,16 @@ unsafe impl<A: Allocator> Send for PipelineCache<A> {}
 // internal data
 unsafe impl<A: Allocator> Sync for PipelineCache<A> {}
 
-macro_rules! require_extension {
-    ($pci:ident, $device:ident, $state:expr, $ext:expr) => {
-        if $pci.dynamic_states.contains(&$state) && !$device.is_extension_enabled($ext) {
+/// Check if dynamic states are supported by the enabled extension set
+fn verify_valid_dynamic_states(device: &Device, pci: &PipelineCreateInfo) {
+    for state in &pci.dynamic_states {
+        if let Err(error) = device.require_dynamic_state(*state) {
             error!(
-                "Pipeline {} requested dynamic state {:?}, but corresponding extension {:?} is not enabled. Maybe it is unsupported on the current device?",
-                $pci.name, $state, $ext
+                "Pipeline {} requested dynamic state {:?}, but it is not available: {}",
+                pci.name, state, error
             );
         }
-    };
-}
-
-/// Check if dynamic states are supported by the enabled extension set
-fn verify_valid_dynamic_states(device: &Device, pci: &PipelineCreateInfo) {
-    require_extension!(
-        pci,
-        device,
-        vk::DynamicState::POLYGON_MODE_EXT,
-        ExtensionID::ExtendedDynamicState3
-    );
+    }
 }
 
 impl ResourceKey for PipelineCreateInfo {
@@ -113,6 +104,9 @@ impl Resource for Pipeline {
 
     fn create(device: Device, info: &Self::Key, params: Self::ExtraParams<'_>) -> Result<Self> {
         let (shaders, pipeline_layouts, set_layouts) = params;
+        if info.is_mesh_pipeline() {
+            device.require_extension(ExtensionID::MeshShader)?;
+        }
         let layout = pipeline_layouts.get_or_create(&info.layout, set_layouts)?;
         let mut pci = info.to_vk(unsafe { layout.handle() });
 
@@ -156,6 +150,8 @@ impl Resource for Pipeline {
                 handle,
                 layout: layout.handle(),
                 set_layouts: layout.set_layouts().to_vec(),
+                samples: info.multisample.0.rasterization_samples,
+                dynamic_states: info.dynamic_states.clone(),
             })
         }
     }
@@ -164,16 +164,33 @@ impl PipelineCreateInfo {
         self.build_rendering_state();
     }
 
+    /// Whether this pipeline uses a mesh shader instead of vertex input.
+    pub(crate) fn is_mesh_pipeline(&self) -> bool {
+        self.shaders
+            .iter()
+            .any(|shader| shader.stage() == vk::ShaderStageFlags::MESH_EXT)
+    }
+
     // Shader stage not yet filled out
     pub(crate) fn to_vk(&self, layout: vk::PipelineLayout) -> vk::GraphicsPipelineCreateInfo {
+        // Mesh pipelines do not have vertex input and input assembly state.
+        let is_mesh_pipeline = self.is_mesh_pipeline();
         vk::GraphicsPipelineCreateInfo {
             s_type: vk::StructureType::GRAPHICS_PIPELINE_CREATE_INFO,
             p_next: (&self.vk_rendering_state as *const _) as *const std::ffi::c_void,
             flags: Default::default(),
             stage_count: 0,
             p_stages: std::ptr::null(),
-            p_vertex_input_state: &self.vertex_input_state,
-            p_input_assembly_state: &self.input_assembly.0,
+            p_vertex_input_state: if is_mesh_pipeline {
+                std::ptr::null()
+            } else {
+                &self.vertex_input_state
+            },
+            p_input_assembly_state: if is_mesh_pipeline {
+                std::ptr::null()
+            } else {
+                &self.input_assembly.0
+            },
             p_tessellation_state: match &self.vk_tessellation_state {
                 None => std::ptr::null(),
                 Some(info) => info,
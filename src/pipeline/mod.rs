@@ -47,6 +47,20 @@
 //!
 //! cache.create_named_pipeline(pci)?;
 //! ```
+//!
+//! Pipelines using mesh shaders are built the same way, but attach a mesh shader (and optionally a task shader) instead
+//! of a vertex shader. These require enabling mesh shading with [`AppBuilder::mesh_shading()`](crate::AppBuilder::mesh_shading).
+//! ```
+//! use phobos::prelude::*;
+//!
+//! let pci = PipelineBuilder::new("meshlets")
+//!     .dynamic_states(&[vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR])
+//!     .blend_attachment_none()
+//!     .attach_shader(ShaderCreateInfo::from_spirv(vk::ShaderStageFlags::TASK_EXT, task_code))
+//!     .attach_shader(ShaderCreateInfo::from_spirv(vk::ShaderStageFlags::MESH_EXT, mesh_code))
+//!     .attach_shader(ShaderCreateInfo::from_spirv(vk::ShaderStageFlags::FRAGMENT, frag_code))
+//!     .build();
+//! ```
 //! # Correct usage
 //! The pipeline cache internally frees up resources by destroying pipelines that have not been accessed in a long time.
 //! To ensure this happens periodically, call [`PipelineCache::next_frame()`](crate::PipelineCache::next_frame) at the end of each iteration of your render loop.
@@ -81,6 +95,8 @@ pub struct Pipeline {
     pub(crate) handle: vk::Pipeline,
     pub(crate) layout: vk::PipelineLayout,
     pub(crate) set_layouts: Vec<vk::DescriptorSetLayout>,
+    pub(crate) samples: vk::SampleCountFlags,
+    pub(crate) dynamic_states: Vec<vk::DynamicState>,
 }
 
 /// A fully built Vulkan compute pipeline. This is a managed resource, so it cannot be manually
@@ -37,8 +37,50 @@ pub struct ReflectionInfo {
     pub(crate) push_constants: Vec<PushConstantRange>,
 }
 
+/// Read the execution model of the first entry point directly from the SPIR-V binary.
 #[cfg(feature = "shader-reflection")]
-fn get_shader_stage(ast: &Ast) -> Result<vk::ShaderStageFlags> {
+fn spirv_execution_model(code: &[u32]) -> Option<u32> {
+    const OP_ENTRY_POINT: u32 = 15;
+    // Skip the header of the module
+    let mut words = code.get(5..)?;
+    while let Some(instruction) = words.first() {
+        let word_count = (instruction >> 16) as usize;
+        if word_count == 0 {
+            return None;
+        }
+        if instruction & 0xFFFF == OP_ENTRY_POINT {
+            return words.get(1).copied();
+        }
+        words = words.get(word_count..)?;
+    }
+    None
+}
+
+#[cfg(feature = "shader-reflection")]
+fn get_shader_stage(ast: &Ast, code: &[u32]) -> Result<vk::ShaderStageFlags> {
+    const EXECUTION_MODEL_TASK_NV: u32 = 5267;
+    const EXECUTION_MODEL_MESH_NV: u32 = 5268;
+    const EXECUTION_MODEL_TASK_EXT: u32 = 5364;
+    const EXECUTION_MODEL_MESH_EXT: u32 = 5365;
+
+    // SPIRV-Cross does not know about the mesh shading execution models, so these are read from the binary instead.
+    // Shaders for VK_NV_mesh_shader use different built-ins and limits than the EXT stages, so they are rejected.
+    match spirv_execution_model(code) {
+        Some(EXECUTION_MODEL_TASK_EXT) => {
+            return Ok(vk::ShaderStageFlags::TASK_EXT);
+        }
+        Some(EXECUTION_MODEL_MESH_EXT) => {
+            return Ok(vk::ShaderStageFlags::MESH_EXT);
+        }
+        Some(EXECUTION_MODEL_TASK_NV | EXECUTION_MODEL_MESH_NV) => {
+            return Err(Error::Uncategorized(
+                "NV mesh shaders are not supported, compile them for VK_EXT_mesh_shader instead",
+            )
+            .into());
+        }
+        _ => {}
+    }
+
     let entry = ast
         .get_entry_points()?
         .first()
@@ -217,10 +259,11 @@ fn find_push_constants(
 }
 
 #[cfg(feature = "shader-reflection")]
-fn reflect_module(module: spv_cross::spirv::Module) -> Result<ReflectionInfo> {
+fn reflect_module(code: &[u32]) -> Result<ReflectionInfo> {
+    let module = spv_cross::spirv::Module::from_words(code);
     let mut ast: Ast = Ast::parse(&module)?;
     let resources = ast.get_shader_resources()?;
-    let stage = get_shader_stage(&ast)?;
+    let stage = get_shader_stage(&ast, code)?;
 
     let mut info = ReflectionInfo {
         bindings: Default::default(),
@@ -261,8 +304,7 @@ fn merge_push_constants(reflected_shaders: &[ReflectionInfo]) -> Result<Vec<Push
 pub(crate) fn reflect_shaders(shaders: &[ShaderCreateInfo]) -> Result<ReflectionInfo> {
     let mut reflected_shaders = Vec::new();
     for shader in shaders {
-        let module = spv_cross::spirv::Module::from_words(shader.code());
-        reflected_shaders.push(reflect_module(module)?);
+        reflected_shaders.push(reflect_module(shader.code())?);
     }
 
     Ok(ReflectionInfo {
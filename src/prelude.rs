@@ -8,7 +8,10 @@ pub use crate::allocator::default_allocator;
 pub use crate::allocator::default_allocator::DefaultAllocator;
 pub use crate::allocator::memory_type::MemoryType;
 pub use crate::allocator::scratch_allocator::ScratchAllocator;
-pub use crate::command_buffer::{CommandBuffer, IncompleteCommandBuffer};
+pub use crate::command_buffer::{
+    CommandBuffer, IncompleteCommandBuffer, SecondaryCommandBuffer, SecondaryRenderingInfo,
+};
+pub use crate::command_buffer::state::{RenderingAttachmentInfo, RenderingInfo};
 pub use crate::core::app_info::*;
 pub use crate::core::debug::DebugMessenger;
 pub use crate::core::device::Device;
@@ -19,9 +22,15 @@ pub use crate::core::physical_device::*;
 pub use crate::core::queue::QueueType;
 pub use crate::descriptor::cache::DescriptorCache;
 pub use crate::descriptor::descriptor_set::DescriptorSet;
-pub use crate::graph::pass::{ClearColor, ClearDepthStencil, Pass, PassBuilder};
+pub use crate::graph::cache::PassGraphCache;
+pub use crate::graph::history::HistoryImage;
+pub use crate::graph::pass::{ClearColor, ClearDepthStencil, Pass, PassBuilder, PassQueue};
 pub use crate::graph::pass_graph::PassGraph;
 pub use crate::graph::physical_resource::PhysicalResourceBindings;
+pub use crate::graph::profiler::GraphProfiler;
+pub use crate::graph::resource::{AttachmentOps, ResourceState};
+pub use crate::graph::sub_graph::SubGraph;
+pub use crate::graph::transient::{TransientBufferInfo, TransientImageInfo};
 pub use crate::graph::virtual_resource::VirtualResource;
 pub use crate::pipeline::{PipelineStage, PipelineType};
 pub use crate::pipeline::builder::PipelineBuilder;
@@ -33,11 +42,12 @@ pub use crate::pipeline::raytracing::RayTracingPipelineBuilder;
 pub use crate::pipeline::shader::ShaderCreateInfo;
 pub use crate::resource::*;
 pub use crate::resource::buffer::{Buffer, BufferView};
-pub use crate::resource::image::{Image, ImageView};
+pub use crate::resource::image::{Image, ImageInfo, ImageSubresource, ImageView};
 pub use crate::resource::query_pool::*;
 pub use crate::resource::raytracing::*;
 pub use crate::sampler::Sampler;
 pub use crate::sync::domain;
+pub use crate::sync::event::Event;
 pub use crate::sync::execution_manager::ExecutionManager;
 pub use crate::sync::fence::*;
 pub use crate::sync::semaphore::*;
@@ -53,6 +63,7 @@ pub use crate::wsi::swapchain::Swapchain;
 pub mod traits {
     pub use crate::allocator::traits::*;
     pub use crate::command_buffer::traits::*;
+    pub use crate::graph::json::GraphJson;
     pub use crate::graph::pass_graph::GraphViz;
     pub use crate::graph::record::RecordGraphToCommandBuffer;
     pub use crate::wsi::window::{WindowInterface, WindowSize};
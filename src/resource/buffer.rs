@@ -45,9 +45,10 @@ use crate::{Allocation, Allocator, DefaultAllocator, Device, Error, MemoryType};
 pub struct Buffer<A: Allocator = DefaultAllocator> {
     #[derivative(Debug = "ignore")]
     device: Device,
+    /// GPU memory allocation. If this is None, the memory is managed externally (for example when aliasing memory).
     #[derivative(Debug = "ignore")]
     #[allow(dead_code)]
-    memory: A::Allocation,
+    memory: Option<A::Allocation>,
     address: vk::DeviceAddress,
     pointer: Option<NonNull<c_void>>,
     handle: vk::Buffer,
@@ -58,6 +59,10 @@ pub struct Buffer<A: Allocator = DefaultAllocator> {
 // so its value is not dropped when sending this to a different thread.
 unsafe impl<A: Allocator> Send for Buffer<A> {}
 
+// SAFETY: No method taking `&self` accesses the mapped memory. The only way to write to it is `Buffer::mapped_slice()`,
+// which takes `&mut self`, or through a `BufferView`, which is `Send` and `Copy` already.
+unsafe impl<A: Allocator> Sync for Buffer<A> {}
+
 /// View into a specific offset and range of a [`Buffer`].
 /// Care should be taken with the lifetime of this, as there is no checking that the buffer
 /// is not dropped while using this.
@@ -73,6 +78,10 @@ pub struct BufferView {
 // so its value is not dropped when sending this to a different thread.
 unsafe impl Send for BufferView {}
 
+// SAFETY: A buffer view is `Copy`, so a shared reference can already be turned into a view that is sent to another thread.
+// Methods taking `&self` only read the handle, offset and size. Writing to the mapped memory requires `&mut self`.
+unsafe impl Sync for BufferView {}
+
 impl<A: Allocator> Buffer<A> {
     /// Allocate a new buffer with a specific size, at a specific memory location.
     /// All usage flags must be given.
@@ -83,59 +92,12 @@ impl<A: Allocator> Buffer<A> {
         usage: vk::BufferUsageFlags,
         location: MemoryType,
     ) -> Result<Self> {
-        let size = size.into();
-        let sharing_mode = if device.is_single_queue() {
-            vk::SharingMode::EXCLUSIVE
-        } else {
-            vk::SharingMode::CONCURRENT
-        };
-        let handle = unsafe {
-            device.create_buffer(
-                &vk::BufferCreateInfo {
-                    s_type: vk::StructureType::BUFFER_CREATE_INFO,
-                    p_next: std::ptr::null(),
-                    flags: vk::BufferCreateFlags::empty(),
-                    size,
-                    usage: usage | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
-                    sharing_mode,
-                    queue_family_index_count: if sharing_mode == vk::SharingMode::CONCURRENT {
-                        device.queue_families().len() as u32
-                    } else {
-                        0
-                    },
-                    p_queue_family_indices: if sharing_mode == vk::SharingMode::CONCURRENT {
-                        device.queue_families().as_ptr()
-                    } else {
-                        std::ptr::null()
-                    },
-                },
-                None,
-            )?
-        };
-        #[cfg(feature = "log-objects")]
-        trace!("Created new VkBuffer {handle:p} (size = {size} bytes)");
-
-        let requirements = unsafe { device.get_buffer_memory_requirements(handle) };
+        let mut buffer = Self::new_unbound(device, size, usage)?;
+        let requirements = buffer.memory_requirements();
         let memory = allocator.allocate("buffer", &requirements, location)?;
-
-        unsafe { device.bind_buffer_memory(handle, memory.memory(), memory.offset())? };
-
-        let address = unsafe {
-            device.get_buffer_device_address(&vk::BufferDeviceAddressInfo {
-                s_type: vk::StructureType::BUFFER_DEVICE_ADDRESS_INFO,
-                p_next: std::ptr::null(),
-                buffer: handle,
-            })
-        };
-
-        Ok(Self {
-            device,
-            pointer: memory.mapped_ptr(),
-            memory,
-            handle,
-            size,
-            address,
-        })
+        buffer.bind_memory(&memory)?;
+        buffer.memory = Some(memory);
+        Ok(buffer)
     }
 
     /// Allocate a new buffer with a specific alignment instead of the inferred alignment from the usage flags.
@@ -149,6 +111,23 @@ impl<A: Allocator> Buffer<A> {
     ) -> Result<Self> {
         let alignment = alignment.into();
         let size = align(size.into(), alignment);
+        let mut buffer = Self::new_unbound(device, size, usage)?;
+        let mut requirements = buffer.memory_requirements();
+        requirements.alignment = alignment;
+        let memory = allocator.allocate("buffer", &requirements, location)?;
+        buffer.bind_memory(&memory)?;
+        buffer.memory = Some(memory);
+        Ok(buffer)
+    }
+
+    /// Create a new buffer without any memory bound to it. The caller is responsible for binding memory
+    /// using [`Buffer::bind_memory()`] before using it. This is used to alias memory between multiple buffers.
+    pub(crate) fn new_unbound(
+        device: Device,
+        size: impl Into<vk::DeviceSize>,
+        usage: vk::BufferUsageFlags,
+    ) -> Result<Self> {
+        let size = size.into();
         let sharing_mode = if device.is_single_queue() {
             vk::SharingMode::EXCLUSIVE
         } else {
@@ -180,30 +159,41 @@ impl<A: Allocator> Buffer<A> {
         #[cfg(feature = "log-objects")]
         trace!("Created new VkBuffer {handle:p} (size = {size} bytes)");
 
-        let mut requirements = unsafe { device.get_buffer_memory_requirements(handle) };
-        requirements.alignment = alignment;
-        let memory = allocator.allocate("buffer", &requirements, location)?;
-
-        unsafe { device.bind_buffer_memory(handle, memory.memory(), memory.offset())? };
-
-        let address = unsafe {
-            device.get_buffer_device_address(&vk::BufferDeviceAddressInfo {
-                s_type: vk::StructureType::BUFFER_DEVICE_ADDRESS_INFO,
-                p_next: std::ptr::null(),
-                buffer: handle,
-            })
-        };
-
         Ok(Self {
             device,
-            pointer: memory.mapped_ptr(),
-            memory,
+            memory: None,
+            address: 0,
+            pointer: None,
             handle,
             size,
-            address,
         })
     }
 
+    /// Get the memory requirements of this buffer.
+    pub(crate) fn memory_requirements(&self) -> vk::MemoryRequirements {
+        unsafe { self.device.get_buffer_memory_requirements(self.handle) }
+    }
+
+    /// Bind memory to a buffer created with [`Buffer::new_unbound()`]. The buffer does not take ownership
+    /// of the allocation, so the caller must keep it alive for as long as the buffer is in use.
+    pub(crate) fn bind_memory(&mut self, memory: &A::Allocation) -> Result<()> {
+        unsafe {
+            self.device
+                .bind_buffer_memory(self.handle, memory.memory(), memory.offset())?
+        };
+
+        self.address = unsafe {
+            self.device
+                .get_buffer_device_address(&vk::BufferDeviceAddressInfo {
+                    s_type: vk::StructureType::BUFFER_DEVICE_ADDRESS_INFO,
+                    p_next: std::ptr::null(),
+                    buffer: self.handle,
+                })
+        };
+        self.pointer = memory.mapped_ptr();
+        Ok(())
+    }
+
     /// Allocate a new buffer with device local memory (VRAM). This is usually the correct memory location for most buffers.
     pub fn new_device_local(
         device: Device,
@@ -19,7 +19,7 @@ use std::sync::Arc;
 use anyhow::Result;
 use ash::vk;
 
-use crate::{Allocation, Allocator, DefaultAllocator, Device, MemoryType};
+use crate::{Allocation, Allocator, DefaultAllocator, Device, Error, MemoryType};
 
 /// Abstraction over a [`VkImage`](vk::Image). Stores information about size, format, etc. Additionally couples the image data together
 /// with a memory allocation.
@@ -31,8 +31,8 @@ pub struct Image<A: Allocator = DefaultAllocator> {
     device: Device,
     /// [`VkImage`](vk::Image) handle.
     handle: vk::Image,
-    /// GPU memory allocation. If this is None, then the image is not owned by our system (for example a swapchain image) and should not be
-    /// destroyed.
+    /// GPU memory allocation. If this is None, then the image is either not owned by our system (for example a swapchain image),
+    /// or its memory is managed externally (for example when aliasing memory).
     #[derivative(Debug = "ignore")]
     memory: Option<A::Allocation>,
     /// Image format
@@ -46,6 +46,11 @@ pub struct Image<A: Allocator = DefaultAllocator> {
     mip_levels: u32,
     /// Number of samples. Useful for multisampled attachments
     samples: vk::SampleCountFlags,
+    /// Whether this image is owned by our system. If this is false, the image is managed externally (for example a swapchain image) and
+    /// should not be destroyed.
+    owned: bool,
+    /// Sharing mode of this image. Exclusive images need a queue family ownership transfer when used on multiple queue families.
+    sharing_mode: vk::SharingMode,
 }
 
 unsafe impl<A: Allocator> Send for Image<A> {}
@@ -83,10 +88,69 @@ pub struct ImgView {
     base_layer: u32,
     /// Amount of array layers in the viewed array layer range.
     layer_count: u32,
+    /// Sharing mode of the corresponding image.
+    sharing_mode: vk::SharingMode,
     /// Unique ID for this image view, because vk handles may be reused.
     id: u64,
 }
 
+/// A range of mip levels and array layers of an image view. All values are relative to the first level and layer of the view.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ImageSubresource {
+    /// First mip level in the range.
+    pub base_level: u32,
+    /// Number of mip levels in the range, or [`vk::REMAINING_MIP_LEVELS`] for all remaining levels.
+    pub level_count: u32,
+    /// First array layer in the range.
+    pub base_layer: u32,
+    /// Number of array layers in the range, or [`vk::REMAINING_ARRAY_LAYERS`] for all remaining layers.
+    pub layer_count: u32,
+}
+
+impl ImageSubresource {
+    /// A single mip level of all array layers.
+    pub fn mip(level: u32) -> Self {
+        Self {
+            base_level: level,
+            level_count: 1,
+            base_layer: 0,
+            layer_count: vk::REMAINING_ARRAY_LAYERS,
+        }
+    }
+
+    /// A single array layer with all of its mip levels.
+    pub fn layer(layer: u32) -> Self {
+        Self {
+            base_level: 0,
+            level_count: vk::REMAINING_MIP_LEVELS,
+            base_layer: layer,
+            layer_count: 1,
+        }
+    }
+
+    /// Returns true if both ranges share at least one mip level of one array layer. Ranges that extend to the remaining
+    /// levels or layers are assumed to extend to the end of any image.
+    pub(crate) fn overlaps(&self, other: &ImageSubresource) -> bool {
+        let levels =
+            |range: &Self| span(range.base_level, range.level_count, vk::REMAINING_MIP_LEVELS);
+        let layers =
+            |range: &Self| span(range.base_layer, range.layer_count, vk::REMAINING_ARRAY_LAYERS);
+        let intersects = |(start, end): (u64, u64), (other_start, other_end): (u64, u64)| {
+            start < other_end && other_start < end
+        };
+        intersects(levels(self), levels(other)) && intersects(layers(self), layers(other))
+    }
+}
+
+/// Get the start and end of a range of mip levels or array layers, where `remaining` means the range extends to the end of the image.
+fn span(base: u32, count: u32, remaining: u32) -> (u64, u64) {
+    if count == remaining {
+        (base as u64, u64::MAX)
+    } else {
+        (base as u64, base as u64 + count as u64)
+    }
+}
+
 /// Reference-counted version of [`ImgView`].
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct ImageView(pub Arc<ImgView>);
@@ -103,6 +167,43 @@ unsafe impl Send for ImageView {}
 
 unsafe impl Sync for ImageView {}
 
+/// Full description of an image created through [`Image::new_with_info()`]. Use this over [`Image::new()`] to create
+/// images with multiple array layers or mip levels.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ImageInfo {
+    /// Width of the image in pixels.
+    pub width: u32,
+    /// Height of the image in pixels.
+    pub height: u32,
+    /// Number of array layers. Set [`vk::ImageCreateFlags::CUBE_COMPATIBLE`] in `flags` to use a six-layer image as a cubemap.
+    pub layers: u32,
+    /// Number of mip levels.
+    pub mip_levels: u32,
+    /// All usage flags this image will be used with.
+    pub usage: vk::ImageUsageFlags,
+    /// Format of the image.
+    pub format: vk::Format,
+    /// Number of MSAA samples.
+    pub samples: vk::SampleCountFlags,
+    /// Additional image creation flags.
+    pub flags: vk::ImageCreateFlags,
+}
+
+impl Default for ImageInfo {
+    fn default() -> Self {
+        Self {
+            width: 0,
+            height: 0,
+            layers: 1,
+            mip_levels: 1,
+            usage: vk::ImageUsageFlags::empty(),
+            format: vk::Format::UNDEFINED,
+            samples: vk::SampleCountFlags::TYPE_1,
+            flags: vk::ImageCreateFlags::empty(),
+        }
+    }
+}
+
 impl<A: Allocator> Image<A> {
     // TODO: Allow specifying an initial layout for convenience
     /// Create a new simple [`VkImage`] and allocate some memory to it.
@@ -115,8 +216,36 @@ impl<A: Allocator> Image<A> {
         format: vk::Format,
         samples: vk::SampleCountFlags,
     ) -> Result<Self> {
+        Self::new_with_info(
+            device,
+            alloc,
+            &ImageInfo {
+                width,
+                height,
+                usage,
+                format,
+                samples,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Create a new [`VkImage`] from a full description and allocate some memory to it.
+    pub fn new_with_info(device: Device, alloc: &mut A, info: &ImageInfo) -> Result<Self> {
+        let mut image = Self::new_unbound(device, info)?;
+        let requirements = image.memory_requirements();
+        // TODO: Proper memory location configuration
+        let memory = alloc.allocate("image_", &requirements, MemoryType::GpuOnly)?;
+        image.bind_memory(&memory)?;
+        image.memory = Some(memory);
+        Ok(image)
+    }
+
+    /// Create a new [`VkImage`] without any memory bound to it. The caller is responsible for binding memory
+    /// using [`Image::bind_memory()`] before using it. This is used to alias memory between multiple images.
+    pub(crate) fn new_unbound(device: Device, info: &ImageInfo) -> Result<Self> {
         let sharing_mode = if device.is_single_queue()
-            || usage.intersects(
+            || info.usage.intersects(
                 vk::ImageUsageFlags::COLOR_ATTACHMENT
                     | vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
             ) {
@@ -130,19 +259,19 @@ impl<A: Allocator> Image<A> {
                 &vk::ImageCreateInfo {
                     s_type: vk::StructureType::IMAGE_CREATE_INFO,
                     p_next: std::ptr::null(),
-                    flags: Default::default(),
+                    flags: info.flags,
                     image_type: vk::ImageType::TYPE_2D,
-                    format,
+                    format: info.format,
                     extent: vk::Extent3D {
-                        width,
-                        height,
+                        width: info.width,
+                        height: info.height,
                         depth: 1,
                     },
-                    mip_levels: 1,
-                    array_layers: 1,
-                    samples,
+                    mip_levels: info.mip_levels,
+                    array_layers: info.layers,
+                    samples: info.samples,
                     tiling: vk::ImageTiling::OPTIMAL,
-                    usage,
+                    usage: info.usage,
                     sharing_mode,
                     queue_family_index_count: if sharing_mode == vk::SharingMode::CONCURRENT {
                         device.queue_families().len() as u32
@@ -162,30 +291,39 @@ impl<A: Allocator> Image<A> {
         #[cfg(feature = "log-objects")]
         trace!("Created new VkImage {handle:p}");
 
-        let requirements = unsafe { device.get_image_memory_requirements(handle) };
-
-        // TODO: Proper memory location configuration
-        let memory = alloc.allocate("image_", &requirements, MemoryType::GpuOnly)?;
-        unsafe {
-            device.bind_image_memory(handle, memory.memory(), memory.offset())?;
-        }
-
         Ok(Self {
             device,
             handle,
-            format,
+            format: info.format,
             size: vk::Extent3D {
-                width,
-                height,
+                width: info.width,
+                height: info.height,
                 depth: 1,
             },
-            layers: 1,
-            mip_levels: 1,
-            samples,
-            memory: Some(memory),
+            layers: info.layers,
+            mip_levels: info.mip_levels,
+            samples: info.samples,
+            memory: None,
+            owned: true,
+            sharing_mode,
         })
     }
 
+    /// Get the memory requirements of this image.
+    pub(crate) fn memory_requirements(&self) -> vk::MemoryRequirements {
+        unsafe { self.device.get_image_memory_requirements(self.handle) }
+    }
+
+    /// Bind memory to an image created with [`Image::new_unbound()`]. The image does not take ownership
+    /// of the allocation, so the caller must keep it alive for as long as the image is in use.
+    pub(crate) fn bind_memory(&mut self, memory: &A::Allocation) -> Result<()> {
+        unsafe {
+            self.device
+                .bind_image_memory(self.handle, memory.memory(), memory.offset())?;
+        }
+        Ok(())
+    }
+
     pub(crate) fn new_managed(
         device: Device,
         handle: vk::Image,
@@ -204,6 +342,8 @@ impl<A: Allocator> Image<A> {
             layers,
             mip_levels,
             samples,
+            owned: false,
+            sharing_mode: vk::SharingMode::EXCLUSIVE,
         }
     }
 
@@ -219,7 +359,12 @@ impl<A: Allocator> Image<A> {
             p_next: std::ptr::null(),
             flags: Default::default(),
             image: self.handle,
-            view_type: vk::ImageViewType::TYPE_2D, // TODO: 3D images, cubemaps, etc
+            // TODO: 3D images, cubemaps, etc
+            view_type: if self.layers > 1 {
+                vk::ImageViewType::TYPE_2D_ARRAY
+            } else {
+                vk::ImageViewType::TYPE_2D
+            },
             format: self.format,
             components: vk::ComponentMapping::default(),
             subresource_range: vk::ImageSubresourceRange {
@@ -246,13 +391,14 @@ impl<A: Allocator> Image<A> {
             level_count: self.mip_levels,
             base_layer: 0,
             layer_count: self.layers,
+            sharing_mode: self.sharing_mode,
             id: ImgView::get_new_id(),
         })))
     }
 
     /// Whether this image resource is owned by the application or an external manager (such as the swapchain).
     pub fn is_owned(&self) -> bool {
-        self.memory.is_some()
+        self.owned
     }
 
     /// Get unsafe access to the underlying `VkImage` handle.
@@ -332,6 +478,78 @@ impl ImgView {
         }
     }
 
+    /// Create a new image view over a range of mip levels and array layers of this view.
+    /// # Errors
+    /// * Fails if the range is not fully contained in this view.
+    /// * Fails if creating the image view fails.
+    pub fn subresource_view(&self, subresource: &ImageSubresource) -> Result<ImageView> {
+        let level_count = match subresource.level_count {
+            vk::REMAINING_MIP_LEVELS => self.level_count.saturating_sub(subresource.base_level),
+            count => count,
+        };
+        let layer_count = match subresource.layer_count {
+            vk::REMAINING_ARRAY_LAYERS => self.layer_count.saturating_sub(subresource.base_layer),
+            count => count,
+        };
+        if level_count == 0
+            || layer_count == 0
+            || subresource.base_level + level_count > self.level_count
+            || subresource.base_layer + layer_count > self.layer_count
+        {
+            return Err(Error::Uncategorized(
+                "Subresource range is out of bounds of the image view",
+            )
+            .into());
+        }
+
+        let range = vk::ImageSubresourceRange {
+            aspect_mask: self.aspect,
+            base_mip_level: self.base_level + subresource.base_level,
+            level_count,
+            base_array_layer: self.base_layer + subresource.base_layer,
+            layer_count,
+        };
+        let info = vk::ImageViewCreateInfo {
+            s_type: vk::StructureType::IMAGE_VIEW_CREATE_INFO,
+            p_next: std::ptr::null(),
+            flags: Default::default(),
+            image: self.image,
+            view_type: if layer_count > 1 {
+                vk::ImageViewType::TYPE_2D_ARRAY
+            } else {
+                vk::ImageViewType::TYPE_2D
+            },
+            format: self.format,
+            components: vk::ComponentMapping::default(),
+            subresource_range: range,
+        };
+
+        let view_handle = unsafe { self.device.create_image_view(&info, None)? };
+        #[cfg(feature = "log-objects")]
+        trace!("Created new VkImageView {view_handle:p}");
+        // The size of a view is the size of its first mip level.
+        let mip_size = |size: u32| (size >> subresource.base_level).max(1);
+        Ok(ImageView(Arc::new(ImgView {
+            device: self.device.clone(),
+            handle: view_handle,
+            image: self.image,
+            format: self.format,
+            samples: self.samples,
+            aspect: self.aspect,
+            size: vk::Extent3D {
+                width: mip_size(self.size.width),
+                height: mip_size(self.size.height),
+                depth: mip_size(self.size.depth),
+            },
+            base_level: range.base_mip_level,
+            level_count,
+            base_layer: range.base_array_layer,
+            layer_count,
+            sharing_mode: self.sharing_mode,
+            id: ImgView::get_new_id(),
+        })))
+    }
+
     /// Get unsafe access to the underlying `VkImageView` handle.
     /// # Safety
     /// Any vulkan calls that mutate this image view's state may put the system in an undefined state.
@@ -401,6 +619,11 @@ impl ImgView {
         self.level_count
     }
 
+    /// Get the sharing mode of the image this view was made from.
+    pub fn sharing_mode(&self) -> vk::SharingMode {
+        self.sharing_mode
+    }
+
     /// Get the unique ID of this image view. Every unique image view
     /// is guaranteed to have its own unique id.
     pub fn id(&self) -> u64 {
@@ -417,3 +640,204 @@ impl Drop for ImgView {
         }
     }
 }
+
+/// Get the aspects of images with this format.
+pub(crate) fn format_aspects(format: vk::Format) -> vk::ImageAspectFlags {
+    match format {
+        vk::Format::D16_UNORM | vk::Format::X8_D24_UNORM_PACK32 | vk::Format::D32_SFLOAT => {
+            vk::ImageAspectFlags::DEPTH
+        }
+        vk::Format::S8_UINT => vk::ImageAspectFlags::STENCIL,
+        vk::Format::D16_UNORM_S8_UINT
+        | vk::Format::D24_UNORM_S8_UINT
+        | vk::Format::D32_SFLOAT_S8_UINT => {
+            vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
+        }
+        _ => vk::ImageAspectFlags::COLOR,
+    }
+}
+
+/// Get the size in bytes of a texel block of one aspect of an image with this format, and the width and height of
+/// that block in texels. Returns `None` for formats with an unknown block size, such as multi-planar formats.
+pub(crate) fn format_texel_block(
+    format: vk::Format,
+    aspect: vk::ImageAspectFlags,
+) -> Option<(u64, u32, u32)> {
+    use vk::Format as F;
+    // Depth and stencil aspects are copied to and from buffers separately, each in their own tightly packed format.
+    if aspect == vk::ImageAspectFlags::STENCIL {
+        return Some((1, 1, 1));
+    }
+    if aspect == vk::ImageAspectFlags::DEPTH {
+        return match format {
+            F::D16_UNORM | F::D16_UNORM_S8_UINT => Some((2, 1, 1)),
+            F::X8_D24_UNORM_PACK32
+            | F::D24_UNORM_S8_UINT
+            | F::D32_SFLOAT
+            | F::D32_SFLOAT_S8_UINT => Some((4, 1, 1)),
+            _ => None,
+        };
+    }
+    let block = match format {
+        F::R4G4_UNORM_PACK8
+        | F::R8_UNORM
+        | F::R8_SNORM
+        | F::R8_USCALED
+        | F::R8_SSCALED
+        | F::R8_UINT
+        | F::R8_SINT
+        | F::R8_SRGB => (1, 1, 1),
+        F::R4G4B4A4_UNORM_PACK16
+        | F::B4G4R4A4_UNORM_PACK16
+        | F::R5G6B5_UNORM_PACK16
+        | F::B5G6R5_UNORM_PACK16
+        | F::R5G5B5A1_UNORM_PACK16
+        | F::B5G5R5A1_UNORM_PACK16
+        | F::A1R5G5B5_UNORM_PACK16
+        | F::R8G8_UNORM
+        | F::R8G8_SNORM
+        | F::R8G8_USCALED
+        | F::R8G8_SSCALED
+        | F::R8G8_UINT
+        | F::R8G8_SINT
+        | F::R8G8_SRGB
+        | F::R16_UNORM
+        | F::R16_SNORM
+        | F::R16_USCALED
+        | F::R16_SSCALED
+        | F::R16_UINT
+        | F::R16_SINT
+        | F::R16_SFLOAT => (2, 1, 1),
+        F::R8G8B8_UNORM
+        | F::R8G8B8_SNORM
+        | F::R8G8B8_USCALED
+        | F::R8G8B8_SSCALED
+        | F::R8G8B8_UINT
+        | F::R8G8B8_SINT
+        | F::R8G8B8_SRGB
+        | F::B8G8R8_UNORM
+        | F::B8G8R8_SNORM
+        | F::B8G8R8_USCALED
+        | F::B8G8R8_SSCALED
+        | F::B8G8R8_UINT
+        | F::B8G8R8_SINT
+        | F::B8G8R8_SRGB => (3, 1, 1),
+        F::R8G8B8A8_UNORM
+        | F::R8G8B8A8_SNORM
+        | F::R8G8B8A8_USCALED
+        | F::R8G8B8A8_SSCALED
+        | F::R8G8B8A8_UINT
+        | F::R8G8B8A8_SINT
+        | F::R8G8B8A8_SRGB
+        | F::B8G8R8A8_UNORM
+        | F::B8G8R8A8_SNORM
+        | F::B8G8R8A8_USCALED
+        | F::B8G8R8A8_SSCALED
+        | F::B8G8R8A8_UINT
+        | F::B8G8R8A8_SINT
+        | F::B8G8R8A8_SRGB
+        | F::A8B8G8R8_UNORM_PACK32
+        | F::A8B8G8R8_SNORM_PACK32
+        | F::A8B8G8R8_USCALED_PACK32
+        | F::A8B8G8R8_SSCALED_PACK32
+        | F::A8B8G8R8_UINT_PACK32
+        | F::A8B8G8R8_SINT_PACK32
+        | F::A8B8G8R8_SRGB_PACK32
+        | F::A2R10G10B10_UNORM_PACK32
+        | F::A2R10G10B10_SNORM_PACK32
+        | F::A2R10G10B10_USCALED_PACK32
+        | F::A2R10G10B10_SSCALED_PACK32
+        | F::A2R10G10B10_UINT_PACK32
+        | F::A2R10G10B10_SINT_PACK32
+        | F::A2B10G10R10_UNORM_PACK32
+        | F::A2B10G10R10_SNORM_PACK32
+        | F::A2B10G10R10_USCALED_PACK32
+        | F::A2B10G10R10_SSCALED_PACK32
+        | F::A2B10G10R10_UINT_PACK32
+        | F::A2B10G10R10_SINT_PACK32
+        | F::R16G16_UNORM
+        | F::R16G16_SNORM
+        | F::R16G16_USCALED
+        | F::R16G16_SSCALED
+        | F::R16G16_UINT
+        | F::R16G16_SINT
+        | F::R16G16_SFLOAT
+        | F::R32_UINT
+        | F::R32_SINT
+        | F::R32_SFLOAT
+        | F::B10G11R11_UFLOAT_PACK32
+        | F::E5B9G9R9_UFLOAT_PACK32 => (4, 1, 1),
+        F::R16G16B16_UNORM
+        | F::R16G16B16_SNORM
+        | F::R16G16B16_USCALED
+        | F::R16G16B16_SSCALED
+        | F::R16G16B16_UINT
+        | F::R16G16B16_SINT
+        | F::R16G16B16_SFLOAT => (6, 1, 1),
+        F::R16G16B16A16_UNORM
+        | F::R16G16B16A16_SNORM
+        | F::R16G16B16A16_USCALED
+        | F::R16G16B16A16_SSCALED
+        | F::R16G16B16A16_UINT
+        | F::R16G16B16A16_SINT
+        | F::R16G16B16A16_SFLOAT
+        | F::R32G32_UINT
+        | F::R32G32_SINT
+        | F::R32G32_SFLOAT
+        | F::R64_UINT
+        | F::R64_SINT
+        | F::R64_SFLOAT => (8, 1, 1),
+        F::R32G32B32_UINT | F::R32G32B32_SINT | F::R32G32B32_SFLOAT => (12, 1, 1),
+        F::R32G32B32A32_UINT
+        | F::R32G32B32A32_SINT
+        | F::R32G32B32A32_SFLOAT
+        | F::R64G64_UINT
+        | F::R64G64_SINT
+        | F::R64G64_SFLOAT => (16, 1, 1),
+        F::R64G64B64_UINT | F::R64G64B64_SINT | F::R64G64B64_SFLOAT => (24, 1, 1),
+        F::R64G64B64A64_UINT | F::R64G64B64A64_SINT | F::R64G64B64A64_SFLOAT => (32, 1, 1),
+        F::BC1_RGB_UNORM_BLOCK
+        | F::BC1_RGB_SRGB_BLOCK
+        | F::BC1_RGBA_UNORM_BLOCK
+        | F::BC1_RGBA_SRGB_BLOCK
+        | F::BC4_UNORM_BLOCK
+        | F::BC4_SNORM_BLOCK
+        | F::ETC2_R8G8B8_UNORM_BLOCK
+        | F::ETC2_R8G8B8_SRGB_BLOCK
+        | F::ETC2_R8G8B8A1_UNORM_BLOCK
+        | F::ETC2_R8G8B8A1_SRGB_BLOCK
+        | F::EAC_R11_UNORM_BLOCK
+        | F::EAC_R11_SNORM_BLOCK => (8, 4, 4),
+        F::BC2_UNORM_BLOCK
+        | F::BC2_SRGB_BLOCK
+        | F::BC3_UNORM_BLOCK
+        | F::BC3_SRGB_BLOCK
+        | F::BC5_UNORM_BLOCK
+        | F::BC5_SNORM_BLOCK
+        | F::BC6H_UFLOAT_BLOCK
+        | F::BC6H_SFLOAT_BLOCK
+        | F::BC7_UNORM_BLOCK
+        | F::BC7_SRGB_BLOCK
+        | F::ETC2_R8G8B8A8_UNORM_BLOCK
+        | F::ETC2_R8G8B8A8_SRGB_BLOCK
+        | F::EAC_R11G11_UNORM_BLOCK
+        | F::EAC_R11G11_SNORM_BLOCK
+        | F::ASTC_4X4_UNORM_BLOCK
+        | F::ASTC_4X4_SRGB_BLOCK => (16, 4, 4),
+        F::ASTC_5X4_UNORM_BLOCK | F::ASTC_5X4_SRGB_BLOCK => (16, 5, 4),
+        F::ASTC_5X5_UNORM_BLOCK | F::ASTC_5X5_SRGB_BLOCK => (16, 5, 5),
+        F::ASTC_6X5_UNORM_BLOCK | F::ASTC_6X5_SRGB_BLOCK => (16, 6, 5),
+        F::ASTC_6X6_UNORM_BLOCK | F::ASTC_6X6_SRGB_BLOCK => (16, 6, 6),
+        F::ASTC_8X5_UNORM_BLOCK | F::ASTC_8X5_SRGB_BLOCK => (16, 8, 5),
+        F::ASTC_8X6_UNORM_BLOCK | F::ASTC_8X6_SRGB_BLOCK => (16, 8, 6),
+        F::ASTC_8X8_UNORM_BLOCK | F::ASTC_8X8_SRGB_BLOCK => (16, 8, 8),
+        F::ASTC_10X5_UNORM_BLOCK | F::ASTC_10X5_SRGB_BLOCK => (16, 10, 5),
+        F::ASTC_10X6_UNORM_BLOCK | F::ASTC_10X6_SRGB_BLOCK => (16, 10, 6),
+        F::ASTC_10X8_UNORM_BLOCK | F::ASTC_10X8_SRGB_BLOCK => (16, 10, 8),
+        F::ASTC_10X10_UNORM_BLOCK | F::ASTC_10X10_SRGB_BLOCK => (16, 10, 10),
+        F::ASTC_12X10_UNORM_BLOCK | F::ASTC_12X10_SRGB_BLOCK => (16, 12, 10),
+        F::ASTC_12X12_UNORM_BLOCK | F::ASTC_12X12_SRGB_BLOCK => (16, 12, 12),
+        _ => return None,
+    };
+    Some(block)
+}
@@ -9,13 +9,15 @@ use std::task::{Poll, Context};
 use anyhow::Result;
 use ash::vk;
 use futures::Future;
-use multimap::{Entry, MultiMap};
+use multimap::MultiMap;
 
 use crate::{
-    Allocator, BufferView, DefaultAllocator, DescriptorCache, Device, Fence, PipelineCache,
-    ScratchAllocator,
+    Allocator, BufferView, DefaultAllocator, DescriptorCache, Device, Event, Fence, ImageView,
+    PipelineCache, ScratchAllocator,
 };
 use crate::allocator::scratch_allocator::ScratchAllocatorCreateInfo;
+use crate::command_buffer::command_pool::CommandPool;
+use crate::graph::transient::{TransientAllocation, TransientMemory, TransientMemoryKey};
 
 /// Indicates that this object can be pooled in a [`Pool`](crate::pool::Pool)
 pub trait Poolable {
@@ -26,18 +28,19 @@ pub trait Poolable {
     fn on_release(&mut self);
 
     /// Create a new pooled object from a pool.
-    fn new_in_pool(pool: &Pool<Self>, key: &Self::Key) -> Result<Pooled<Self>>
+    fn new_in_pool<F>(pool: &Pool<Self, F>, key: &Self::Key) -> Result<Pooled<Self>>
     where
-        Self: Sized, {
-        let item = pool.with(|pool| pool.get(key))?;
-        Ok(Pooled::from_pool(pool.clone(), key.clone(), item))
+        Self: Sized,
+        F: FnMut(&Self::Key) -> Result<Self> + ?Sized, {
+        let item = pool.get(key)?;
+        Ok(Pooled::from_pool(pool.items.clone(), key.clone(), item))
     }
 
     /// Move this item into the pool when it is dropped
-    fn into_pooled(self, pool: &Pool<Self>, key: Self::Key) -> Pooled<Self>
+    fn into_pooled<F: ?Sized>(self, pool: &Pool<Self, F>, key: Self::Key) -> Pooled<Self>
     where
         Self: Sized, {
-        Pooled::from_pool(pool.clone(), key, self)
+        Pooled::from_pool(pool.items.clone(), key, self)
     }
 }
 
@@ -45,22 +48,30 @@ pub trait Poolable {
 /// be reused immediately.
 pub struct Pooled<P: Poolable> {
     item: Option<P>,
-    pool: Pool<P>,
+    pool: PoolItems<P>,
     key: Option<P::Key>,
 }
 
-type BoxedCreateFunc<P> = Box<dyn FnMut(&<P as Poolable>::Key) -> Result<P>>;
+/// Objects that are currently not in use, shared between a pool and the objects taken from it.
+type PoolItems<P> = Arc<Mutex<MultiMap<<P as Poolable>::Key, P>>>;
 
-struct PoolInner<P: Poolable> {
-    items: MultiMap<P::Key, P>,
-    create_fn: BoxedCreateFunc<P>,
-}
+/// Callback of a pool created with [`Pool::new()`].
+pub type CreateFunc<P> = dyn FnMut(&<P as Poolable>::Key) -> Result<P>;
+
+/// Callback of a pool created with [`Pool::new_send()`].
+pub type SendCreateFunc<P> = dyn FnMut(&<P as Poolable>::Key) -> Result<P> + Send;
 
-/// Represents an object pool that can be allocated from
-pub struct Pool<P: Poolable> {
-    inner: Arc<Mutex<PoolInner<P>>>,
+/// Represents an object pool that can be allocated from. `F` is the type of the callback used to create
+/// new objects, which decides whether the pool can be shared with other threads.
+pub struct Pool<P: Poolable, F: ?Sized = CreateFunc<P>> {
+    items: PoolItems<P>,
+    create_fn: Arc<Mutex<Box<F>>>,
 }
 
+/// An object pool created with [`Pool::new_send()`]. Unlike other pools, this can be used from other threads
+/// if the pooled objects can.
+pub type SendPool<P> = Pool<P, SendCreateFunc<P>>;
+
 /// Acts as a global resource pool that can safely be shared everywhere.
 #[derive(Clone, Derivative)]
 #[derivative(Debug)]
@@ -71,10 +82,16 @@ pub struct ResourcePool<A: Allocator = DefaultAllocator> {
     pub descriptors: DescriptorCache,
     /// Scratch allocator pool used to easily create scratch buffers anywhere
     #[derivative(Debug = "ignore")]
-    pub allocators: Pool<ScratchAllocator<A>>,
+    pub allocators: SendPool<ScratchAllocator<A>>,
     /// Fence pool to reuse fences where possible
     #[derivative(Debug = "ignore")]
-    pub fences: Pool<Fence<()>>,
+    pub fences: SendPool<Fence<()>>,
+    /// Event pool to reuse events for split barriers in pass graphs
+    #[derivative(Debug = "ignore")]
+    pub events: SendPool<Event>,
+    /// Memory pool for transient resources in pass graphs
+    #[derivative(Debug = "ignore")]
+    pub transient_memory: SendPool<TransientMemory<A>>,
 }
 
 /// Information needed to create a resource pool
@@ -90,25 +107,34 @@ pub struct ResourcePoolCreateInfo<A: Allocator = DefaultAllocator> {
 /// A local pool that will release its resources back to the main resource pool when it goes out of scope.
 /// Such a scope could be a frame context, or a task spawned on a background thread.
 pub struct LocalPool<A: Allocator = DefaultAllocator> {
-    #[allow(dead_code)]
-    pool: ResourcePool<A>,
+    pub(crate) pool: ResourcePool<A>,
     vertex_allocator: Pooled<ScratchAllocator<A>>,
     index_allocator: Pooled<ScratchAllocator<A>>,
     uniform_allocator: Pooled<ScratchAllocator<A>>,
     storage_allocator: Pooled<ScratchAllocator<A>>,
+    /// Views of transient images and subresources. Declared before `transients`, so the views are destroyed before the images they point to.
+    pub(crate) image_views: Vec<ImageView>,
+    pub(crate) transients: Vec<TransientAllocation<A>>,
+    /// Events used by split barriers in pass graphs recorded with this pool.
+    pub(crate) events: Vec<Pooled<Event>>,
+    /// Command pools used to record secondary command buffers on this pool's thread.
+    pub(crate) command_pools: Vec<CommandPool>,
+    /// Local pools of worker threads that recorded commands for the same frame.
+    pub(crate) workers: Vec<LocalPool<A>>,
 }
 
-impl<P: Poolable> Clone for Pool<P> {
+impl<P: Poolable, F: ?Sized> Clone for Pool<P, F> {
     fn clone(&self) -> Self {
         Self {
-            inner: self.inner.clone(),
+            items: self.items.clone(),
+            create_fn: self.create_fn.clone(),
         }
     }
 }
 
 impl<P: Poolable> Pooled<P> {
     /// Create a new pooled object from a pool and an item
-    fn from_pool(pool: Pool<P>, key: P::Key, item: P) -> Self {
+    fn from_pool(pool: PoolItems<P>, key: P::Key, item: P) -> Self {
         Self {
             item: Some(item),
             pool,
@@ -154,49 +180,45 @@ impl<P: Poolable> Drop for Pooled<P> {
         let mut item = self.item.take().unwrap();
         let key = self.key.take().unwrap();
         item.on_release();
-        self.pool.with(|pool| pool.take(item, key));
+        self.pool.lock().unwrap().insert(key, item);
     }
 }
 
-impl<P: Poolable> PoolInner<P> {
-    /// Release an object back into the pool
-    fn take(&mut self, item: P, key: P::Key) {
-        self.items.insert(key, item);
-    }
-
+impl<P: Poolable, F: FnMut(&P::Key) -> Result<P> + ?Sized> Pool<P, F> {
     /// Grab an object from the pool. If there are none left, this will allocate a new one.
-    fn get(&mut self, key: &P::Key) -> Result<P> {
-        match self.items.entry(key.clone()) {
-            Entry::Occupied(mut entry) => {
-                let vec = entry.get_vec_mut();
-                match vec.pop() {
-                    None => (self.create_fn)(key),
-                    Some(item) => Ok(item),
-                }
-            }
-            Entry::Vacant(_) => (self.create_fn)(key),
+    fn get(&self, key: &P::Key) -> Result<P> {
+        let item = self
+            .items
+            .lock()
+            .unwrap()
+            .get_vec_mut(key)
+            .and_then(|vec| vec.pop());
+        match item {
+            None => (self.create_fn.lock().unwrap())(key),
+            Some(item) => Ok(item),
         }
     }
 }
 
 impl<P: Poolable> Pool<P> {
-    /// Get mutable access to the inner pool
-    fn with<F: FnOnce(&mut PoolInner<P>) -> R, R>(&self, f: F) -> R {
-        let mut inner = self.inner.lock().unwrap();
-        f(&mut inner)
-    }
-
     /// Create a new pool. This must be supplied with a callback to be called
     /// when the pool needs to allocate a new object.
     /// Optionally also takes in a count of objects to preallocate using this callback.
     pub fn new(create_fn: impl FnMut(&P::Key) -> Result<P> + 'static) -> Result<Self> {
-        let inner = PoolInner {
-            items: MultiMap::new(),
-            create_fn: Box::new(create_fn),
-        };
+        Ok(Self {
+            items: Arc::new(Mutex::new(MultiMap::new())),
+            create_fn: Arc::new(Mutex::new(Box::new(create_fn))),
+        })
+    }
+}
 
+impl<P: Poolable> SendPool<P> {
+    /// Create a new pool with a callback that is `Send`. Unlike pools created with [`Pool::new()`], these can be used
+    /// by worker threads when recording a pass graph in parallel.
+    pub fn new_send(create_fn: impl FnMut(&P::Key) -> Result<P> + Send + 'static) -> Result<Self> {
         Ok(Self {
-            inner: Arc::new(Mutex::new(inner)),
+            items: Arc::new(Mutex::new(MultiMap::new())),
+            create_fn: Arc::new(Mutex::new(Box::new(create_fn))),
         })
     }
 }
@@ -208,17 +230,24 @@ impl<A: Allocator + 'static> ResourcePool<A> {
         let descriptors = DescriptorCache::new(info.device.clone())?;
         let device = info.device.clone();
         let mut alloc = info.allocator.clone();
-        let allocators = Pool::new(move |key: &ScratchAllocatorCreateInfo| {
+        let allocators = Pool::new_send(move |key: &ScratchAllocatorCreateInfo| {
             ScratchAllocator::new(device.clone(), &mut alloc, info.scratch_size, key.usage)
         })?;
         let device = info.device.clone();
-        let fences = Pool::new(move |_| Ok(Fence::new(device.clone(), false)?))?;
+        let fences = Pool::new_send(move |_| Ok(Fence::new(device.clone(), false)?))?;
+        let device = info.device.clone();
+        let events = Pool::new_send(move |_| Ok(Event::new(device.clone())?))?;
+        let mut alloc = info.allocator.clone();
+        let transient_memory =
+            Pool::new_send(move |key: &TransientMemoryKey| TransientMemory::new(&mut alloc, key))?;
 
         Ok(Self {
             pipelines,
             descriptors,
             allocators,
             fences,
+            events,
+            transient_memory,
         })
     }
 }
@@ -274,6 +303,11 @@ impl<A: Allocator> LocalPool<A> {
             index_allocator: index_alloc,
             uniform_allocator: uniform_alloc,
             storage_allocator: storage_alloc,
+            image_views: Vec::new(),
+            transients: Vec::new(),
+            events: Vec::new(),
+            command_pools: Vec::new(),
+            workers: Vec::new(),
         })
     }
 
@@ -297,4 +331,31 @@ impl<A: Allocator> LocalPool<A> {
     pub fn allocate_scratch_ssbo(&mut self, size: vk::DeviceSize) -> Result<BufferView> {
         self.storage_allocator.allocate(size)
     }
+
+    /// Keep the local pool of a worker thread alive for as long as this pool. This is needed when the worker
+    /// recorded secondary command buffers that are executed by a command buffer recorded with this pool.
+    pub fn keep_alive(&mut self, worker: LocalPool<A>) {
+        self.workers.push(worker);
+    }
+
+    /// Get a command pool for secondary command buffers on a queue family, creating it if this pool does not have one yet.
+    /// The command pool is destroyed together with this local pool.
+    pub(crate) fn command_pool(&mut self, device: &Device, family: u32) -> Result<&CommandPool> {
+        let index = match self
+            .command_pools
+            .iter()
+            .position(|pool| pool.family() == family)
+        {
+            Some(index) => index,
+            None => {
+                self.command_pools.push(CommandPool::new(
+                    device.clone(),
+                    family,
+                    vk::CommandPoolCreateFlags::TRANSIENT,
+                )?);
+                self.command_pools.len() - 1
+            }
+        };
+        Ok(&self.command_pools[index])
+    }
 }
@@ -365,6 +365,44 @@ impl<Q: Query> QueryPool<Q> {
         Ok(data)
     }
 
+    /// Get the results of a range of queries in the pool without waiting for them.
+    /// Returns `None` if the result of any query in the range is not yet available.
+    /// # Errors
+    /// * Fails if the query range is out of range of the query pool.
+    pub fn try_results(&mut self, first: u32, count: u32) -> Result<Option<Vec<Q::Output>>> {
+        ensure!(first < self.count, "Query range out of range of query pool");
+        ensure!(first + count <= self.count, "Query range out of range of query pool");
+
+        let flags = vk::QueryResultFlags::TYPE_64;
+        let items_per_query = self
+            .queries
+            .first()
+            .map(|query| query.size())
+            .unwrap_or_default();
+        let mut buffer = vec![u64::default(); count as usize * items_per_query];
+        let result = unsafe {
+            self.device.get_query_pool_results(
+                self.handle,
+                first,
+                count,
+                buffer.as_mut_slice(),
+                flags,
+            )
+        };
+        match result {
+            Ok(()) => {}
+            Err(vk::Result::NOT_READY) => return Ok(None),
+            Err(err) => return Err(err.into()),
+        }
+        let data = buffer
+            .chunks_exact(items_per_query)
+            .zip(self.queries.iter().skip(first as usize))
+            .map(|(data, query)| query.parse_query(&self.device, data))
+            .collect::<Vec<_>>();
+
+        Ok(Some(data))
+    }
+
     /// Wait for results of all queries in the pool
     pub fn wait_for_all_results(&mut self) -> Result<Vec<Q::Output>> {
         self.wait_for_results(0, self.count)
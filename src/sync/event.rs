@@ -0,0 +1,66 @@
+//! Abstraction for `VkEvent` objects.
+
+use ash::vk;
+
+use crate::Device;
+use crate::pool::Poolable;
+
+/// Wrapper around a [`VkEvent`](vk::Event) object. Events are used for fine-grained GPU-GPU sync within a single queue,
+/// by signaling an event after some commands and waiting on it before later commands.
+/// The pass graph uses them to record [split barriers](crate::graph::pass_graph::BuiltPassGraph::split_barriers).
+#[derive(Debug)]
+pub struct Event {
+    device: Device,
+    handle: vk::Event,
+}
+
+impl Event {
+    /// Create a new `VkEvent` object. The event starts in the unsignaled state.
+    pub fn new(device: Device) -> Result<Self, vk::Result> {
+        let info = vk::EventCreateInfo {
+            s_type: vk::StructureType::EVENT_CREATE_INFO,
+            p_next: std::ptr::null(),
+            flags: Default::default(),
+        };
+
+        let handle = unsafe { device.create_event(&info, None)? };
+        #[cfg(feature = "log-objects")]
+        trace!("Created new VkEvent {handle:p}");
+
+        Ok(Event {
+            device,
+            handle,
+        })
+    }
+
+    /// Set the event to the unsignaled state from the host. The event must not be in use by the GPU.
+    pub fn reset(&self) -> Result<(), vk::Result> {
+        unsafe { self.device.reset_event(self.handle) }
+    }
+
+    /// Get unsafe access to the underlying `VkEvent` object.
+    /// # Safety
+    /// Any vulkan calls that mutate the event may put the system in an undefined state.
+    pub unsafe fn handle(&self) -> vk::Event {
+        self.handle
+    }
+}
+
+impl Drop for Event {
+    fn drop(&mut self) {
+        #[cfg(feature = "log-objects")]
+        trace!("Destroying VkEvent {:p}", self.handle);
+        unsafe {
+            self.device.destroy_event(self.handle, None);
+        }
+    }
+}
+
+impl Poolable for Event {
+    /// All events are created equal
+    type Key = ();
+
+    fn on_release(&mut self) {
+        self.reset().unwrap();
+    }
+}
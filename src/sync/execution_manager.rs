@@ -9,7 +9,7 @@ use ash::vk;
 use crate::{Allocator, CmdBuffer, DefaultAllocator, Device, Error, Fence, PhysicalDevice};
 use crate::command_buffer::*;
 use crate::core::queue::{DeviceQueue, Queue};
-use crate::pool::{Poolable, Pooled, ResourcePool};
+use crate::pool::{LocalPool, Poolable, Pooled, ResourcePool};
 use crate::sync::domain::ExecutionDomain;
 use crate::sync::submit_batch::SubmitBatch;
 
@@ -140,6 +140,56 @@ impl<A: Allocator> ExecutionManager<A> {
         )
     }
 
+    /// Obtain a secondary command buffer capable of operating on the specified domain. Secondary command buffers do not
+    /// lock the queue, so they can be recorded on multiple threads at the same time. Each thread must use its own `local_pool`,
+    /// which owns the memory of the command buffer. Once finished, run the command buffer from a primary command buffer
+    /// of the same domain with [`IncompleteCommandBuffer::execute_commands()`].
+    /// Secondary command buffers are finished with [`IncompleteCommandBuffer::finish_secondary()`], and cannot be submitted directly.
+    ///
+    /// If `rendering` is given, the command buffer can only be executed inside a dynamic render pass matching it,
+    /// started with [`vk::RenderingFlags::CONTENTS_SECONDARY_COMMAND_BUFFERS`]. Pipelines are then bound for this render pass.
+    ///
+    /// The local pool must be kept alive until the command buffer has finished executing, for example by passing it to
+    /// [`LocalPool::keep_alive()`](crate::pool::LocalPool::keep_alive) on the local pool of the primary command buffer.
+    /// # Errors
+    /// * Fails if there is no queue capable of operating on the domain.
+    /// * Fails if command buffer allocation fails.
+    /// # Example
+    /// ```
+    /// use phobos::prelude::*;
+    /// let mut worker_pool = LocalPool::new(pool.clone())?;
+    /// let secondary = exec.on_domain_secondary::<domain::Graphics>(&mut worker_pool, Some(&rendering))?
+    ///     .bind_graphics_pipeline("pipeline")?
+    ///     .full_viewport_scissor()
+    ///     .draw(3, 1, 0, 0)?
+    ///     .finish_secondary()?;
+    /// // On the main thread, inside a render pass started with CONTENTS_SECONDARY_COMMAND_BUFFERS.
+    /// let cmd = cmd.execute_commands(&[secondary]);
+    /// local_pool.keep_alive(worker_pool);
+    /// ```
+    pub fn on_domain_secondary<'q, D: ExecutionDomain>(
+        &'q self,
+        local_pool: &mut LocalPool<A>,
+        rendering: Option<&SecondaryRenderingInfo>,
+    ) -> Result<IncompleteCommandBuffer<'q, D, A>> {
+        let (queue_info, timestamp_valid_bits) = {
+            let queue = self.get_queue::<D>().ok_or(Error::NoCapableQueue)?;
+            (*queue.info(), queue.family_properties().timestamp_valid_bits)
+        };
+        let handle = local_pool
+            .command_pool(&self.device, queue_info.family_index)?
+            .allocate(vk::CommandBufferLevel::SECONDARY)?;
+        IncompleteCommandBuffer::new_secondary(
+            self.device.clone(),
+            handle,
+            queue_info,
+            timestamp_valid_bits,
+            rendering,
+            self.pool.pipelines.clone(),
+            self.pool.descriptors.clone(),
+        )
+    }
+
     /// Begin a submit batch. Note that all submits in a batch are over a single domain (currently).
     /// # Example
     /// ```
@@ -158,17 +208,43 @@ impl<A: Allocator> ExecutionManager<A> {
         SubmitBatch::new(self.device.clone(), self.clone(), &self.pool)
     }
 
-    /// Submit multiple SubmitInfo2 structures.
+    /// Get the device this execution manager submits to.
+    pub(crate) fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Submit multiple SubmitInfo2 structures to the queue of a domain.
     pub(crate) fn submit_batch<D: ExecutionDomain>(
         &self,
         submits: &[vk::SubmitInfo2],
-        fence: &Fence,
+        fence: Option<&Fence>,
     ) -> Result<()> {
-        let queue = self.get_queue::<D>().ok_or_else(|| Error::NoCapableQueue)?;
-        queue.submit2(submits, Some(fence))?;
+        let queue = self.get_queue::<D>().ok_or(Error::NoCapableQueue)?;
+        queue.submit2(submits, fence)?;
         Ok(())
     }
 
+    /// Get the `VkQueue` that submits over a domain are sent to. Multiple domains can resolve to the same queue.
+    pub(crate) fn queue_handle<D: ExecutionDomain>(&self) -> Result<vk::Queue> {
+        let queue = self.get_queue::<D>().ok_or(Error::NoCapableQueue)?;
+        // SAFETY: The handle is only used to compare queues, no commands are submitted through it.
+        Ok(unsafe { queue.handle() })
+    }
+
+    /// Obtain an [`IncompleteCommandBuffer`] over a domain. Unlike [`ExecutionManager::on_domain()`], this
+    /// always returns the concrete command buffer type.
+    pub(crate) fn allocate_command_buffer<'q, D: ExecutionDomain>(
+        &'q self,
+    ) -> Result<IncompleteCommandBuffer<'q, D, A>> {
+        let queue = self.get_queue::<D>().ok_or(Error::NoCapableQueue)?;
+        Queue::allocate_command_buffer::<'q, A, IncompleteCommandBuffer<'q, D, A>>(
+            self.device.clone(),
+            queue,
+            self.pool.pipelines.clone(),
+            self.pool.descriptors.clone(),
+        )
+    }
+
     /// Obtain a reference to a queue capable of presenting.
     pub(crate) fn get_present_queue(&self) -> Option<MutexGuard<Queue>> {
         self.queues
@@ -4,12 +4,14 @@
 //! - The [`fence`] module provides a wrapper around `VkFence` objects, used for CPU-GPU sync,
 //! as well as an implementation for [`Future`](std::future::Future) for them.
 //! - The [`semaphore`] module provides a simple wrapper around `VkSemaphore` objects, used for GPU-GPU sync.
+//! - The [`event`] module provides a simple wrapper around `VkEvent` objects, used for GPU-GPU sync within a single queue.
 //! - The [`execution_manager`] module abstracts away vulkan queues and synchronizes access to them by using the
 //! [`domain`](crate::domain) system. Most of the time, submissions should go through here.
 //! - [`submit_batch`] provides a utility to chain [`Semaphore`](crate::Semaphore)s together and submit them all
 //! as one batch.
 
 pub mod domain;
+pub mod event;
 pub mod execution_manager;
 pub mod fence;
 pub mod semaphore;
@@ -1,5 +1,6 @@
 //! Provides the [`SubmitBatch`] struct to batch submits together and synchronize between them easily.
 
+use std::marker::PhantomData;
 use std::sync::Arc;
 
 use anyhow::{ensure, Result};
@@ -9,18 +10,51 @@ use crate::command_buffer::CommandBuffer;
 use crate::pool::{LocalPool, Poolable, Pooled, ResourcePool};
 use crate::sync::domain::ExecutionDomain;
 use crate::{
-    Allocator, CmdBuffer, DefaultAllocator, Device, ExecutionManager, Fence, InFlightContext,
-    PipelineStage, Semaphore,
+    Allocator, CmdBuffer, DefaultAllocator, Device, Error, ExecutionManager, Fence,
+    InFlightContext, PipelineStage, Semaphore,
 };
 
-#[derive(Debug)]
-struct SubmitInfo<D: ExecutionDomain> {
-    cmd: CommandBuffer<D>,
-    signal_semaphore: Option<Arc<Semaphore>>,
+type SubmitFn<A> = fn(&ExecutionManager<A>, &[vk::SubmitInfo2], Option<&Fence>) -> Result<()>;
+type QueueFn<A> = fn(&ExecutionManager<A>) -> Result<vk::Queue>;
+
+#[derive(Derivative)]
+#[derivative(Debug)]
+struct SubmitInfo<A: Allocator> {
+    handle: vk::CommandBuffer,
+    #[derivative(Debug = "ignore")]
+    cmd: Box<dyn CmdBuffer<A>>,
+    #[derivative(Debug = "ignore")]
+    submit_fn: SubmitFn<A>,
+    /// Resolves the queue of the command buffer, used to group submits to the same queue.
+    #[derivative(Debug = "ignore")]
+    queue_fn: QueueFn<A>,
+    signal_semaphores: Vec<Arc<Semaphore>>,
+    /// Whether the first signal semaphore is already waited on by another submit.
+    waited_on: bool,
     wait_semaphores: Vec<Arc<Semaphore>>,
     wait_stages: Vec<PipelineStage>,
 }
 
+impl<A: Allocator> SubmitInfo<A> {
+    fn new<D: ExecutionDomain + 'static>(
+        cmd: CommandBuffer<D>,
+        signal_semaphore: Arc<Semaphore>,
+        wait_semaphores: Vec<Arc<Semaphore>>,
+        wait_stages: Vec<PipelineStage>,
+    ) -> Self {
+        Self {
+            handle: unsafe { cmd.handle() },
+            cmd: Box::new(cmd),
+            submit_fn: ExecutionManager::<A>::submit_batch::<D>,
+            queue_fn: ExecutionManager::<A>::queue_handle::<D>,
+            signal_semaphores: vec![signal_semaphore],
+            waited_on: false,
+            wait_semaphores,
+            wait_stages,
+        }
+    }
+}
+
 /// A handle to a submit inside a batch.
 /// Can be used to make submits wait on other submits inside a single batch
 #[derive(Debug, Copy, Clone)]
@@ -31,17 +65,23 @@ pub struct SubmitHandle {
 /// A batch of submits containing multiple command buffers that possibly
 /// wait on each other using semaphores. An example usage is given in the documentation for
 /// [`ExecutionManager::start_submit_batch`].
+///
+/// Submits are over the domain `D`, but command buffers for other domains can be submitted to their own queue
+/// using [`SubmitBatch::submit_on()`].
 #[derive(Derivative)]
 #[derivative(Debug)]
 pub struct SubmitBatch<D: ExecutionDomain, A: Allocator = DefaultAllocator> {
     device: Device,
     exec: ExecutionManager<A>,
-    submits: Vec<SubmitInfo<D>>,
+    submits: Vec<SubmitInfo<A>>,
     #[derivative(Debug = "ignore")]
     signal_fence: Pooled<Fence>,
+    /// Semaphore signaled when the swapchain image is acquired, and the index of the frame commands that present it.
+    frame_wait: Option<(Arc<Semaphore>, usize)>,
     // Local pool to be released when the fence completes
     #[derivative(Debug = "ignore")]
     local_pool: Option<LocalPool<A>>,
+    _domain: PhantomData<D>,
 }
 
 impl<D: ExecutionDomain + 'static, A: Allocator> SubmitBatch<D, A> {
@@ -53,35 +93,52 @@ impl<D: ExecutionDomain + 'static, A: Allocator> SubmitBatch<D, A> {
         Ok(Self {
             submits: vec![],
             signal_fence: Fence::new_in_pool(&pool.fences, &())?,
+            frame_wait: None,
             device,
             exec,
             local_pool: None,
+            _domain: PhantomData,
         })
     }
 
-    fn get_submit_semaphore(&self, submit: SubmitHandle) -> Option<Arc<Semaphore>> {
-        self.submits
-            .get(submit.index)
-            .and_then(|submit| submit.signal_semaphore.clone())
+    fn get_submit_semaphore(&mut self, submit: SubmitHandle) -> Result<Arc<Semaphore>> {
+        let submit = self
+            .submits
+            .get_mut(submit.index)
+            .ok_or(Error::Uncategorized("Invalid submit handle"))?;
+        // A binary semaphore can only be waited on once, so every additional wait needs its own semaphore.
+        if submit.waited_on {
+            let semaphore = Arc::new(Semaphore::new(self.device.clone())?);
+            submit.signal_semaphores.push(semaphore.clone());
+            Ok(semaphore)
+        } else {
+            submit.waited_on = true;
+            Ok(submit.signal_semaphores.first().cloned().unwrap())
+        }
     }
 
-    fn submit_after(
+    fn submit_after<C: ExecutionDomain + 'static>(
         &mut self,
         handles: &[SubmitHandle],
-        cmd: CommandBuffer<D>,
+        cmd: CommandBuffer<C>,
         wait_stages: &[PipelineStage],
     ) -> Result<SubmitHandle> {
+        ensure!(
+            handles.len() == wait_stages.len(),
+            "Number of wait stages must match number of submits"
+        );
+
         let wait_semaphores = handles
             .iter()
-            .map(|handle| self.get_submit_semaphore(*handle).unwrap())
-            .collect::<Vec<_>>();
+            .map(|handle| self.get_submit_semaphore(*handle))
+            .collect::<Result<Vec<_>>>()?;
 
-        self.submits.push(SubmitInfo {
+        self.submits.push(SubmitInfo::new(
             cmd,
-            signal_semaphore: Some(Arc::new(Semaphore::new(self.device.clone())?)),
+            Arc::new(Semaphore::new(self.device.clone())?),
             wait_semaphores,
-            wait_stages: wait_stages.to_vec(),
-        });
+            wait_stages.to_vec(),
+        ));
 
         Ok(SubmitHandle {
             index: self.submits.len() - 1,
@@ -118,30 +175,20 @@ impl<D: ExecutionDomain + 'static, A: Allocator> SubmitBatch<D, A> {
 
         self.local_pool = Some(pool);
 
-        let mut wait_semaphores = submits
+        let wait_semaphores = submits
             .iter()
-            .map(|handle| self.get_submit_semaphore(*handle).unwrap())
-            .collect::<Vec<_>>();
-        let mut wait_stages = wait_stages.to_vec();
-        let frame_wait_semaphore = ifc.wait_semaphore;
-        // Add this semaphore as a wait semaphore for the first submit, or to the frame commands if there is no other submit
-        match self.submits.first_mut() {
-            None => {
-                wait_semaphores.push(frame_wait_semaphore);
-                wait_stages.push(PipelineStage::COLOR_ATTACHMENT_OUTPUT);
-            }
-            Some(submit) => {
-                submit.wait_stages.push(PipelineStage::TOP_OF_PIPE);
-                submit.wait_semaphores.push(frame_wait_semaphore);
-            }
-        }
+            .map(|handle| self.get_submit_semaphore(*handle))
+            .collect::<Result<Vec<_>>>()?;
+        // The first submit on the queue of the frame commands waits on this semaphore. Since domains can share a queue,
+        // this submit is only known once the queues are resolved in finish().
+        self.frame_wait = Some((ifc.wait_semaphore, self.submits.len()));
 
-        self.submits.push(SubmitInfo {
+        self.submits.push(SubmitInfo::new(
             cmd,
-            signal_semaphore: Some(ifc.signal_semaphore),
+            ifc.signal_semaphore,
             wait_semaphores,
-            wait_stages,
-        });
+            wait_stages.to_vec(),
+        ));
 
         Ok(SubmitHandle {
             index: self.submits.len() - 1,
@@ -167,22 +214,27 @@ impl<D: ExecutionDomain + 'static, A: Allocator> SubmitBatch<D, A> {
 
     /// Submit a new command buffer in this batch with no dependencies.
     pub fn submit(&mut self, cmd: CommandBuffer<D>) -> Result<SubmitHandle> {
-        self.submits.push(SubmitInfo {
-            cmd,
-            signal_semaphore: Some(Arc::new(Semaphore::new(self.device.clone())?)),
-            wait_semaphores: vec![],
-            wait_stages: vec![],
-        });
+        self.submit_after(&[], cmd, &[])
+    }
 
-        Ok(SubmitHandle {
-            index: self.submits.len() - 1,
-        })
+    /// Submit a command buffer over any domain to its own queue, waiting on the given previous submits.
+    /// Note that a submit can only wait on submits that were added to the batch before it.
+    /// # Errors
+    /// * Fails if the number of wait stages does not match the number of submits.
+    pub fn submit_on<C: ExecutionDomain + 'static>(
+        &mut self,
+        cmd: CommandBuffer<C>,
+        submits: &[SubmitHandle],
+        wait_stages: &[PipelineStage],
+    ) -> Result<SubmitHandle> {
+        self.submit_after(submits, cmd, wait_stages)
     }
 }
 
 impl<D: ExecutionDomain + 'static, A: Allocator + 'static> SubmitBatch<D, A> {
     /// Finish this batch by submitting it to the execution manager.
     /// This returns a [`Fence`] that can be awaited to wait for completion.
+    /// If this batch contains submits to other queues, the returned fence is only signaled once these have completed as well.
     pub fn finish(mut self) -> Result<Pooled<Fence>> {
         struct PerSubmit {
             wait_semaphores: Vec<vk::SemaphoreSubmitInfo>,
@@ -190,6 +242,58 @@ impl<D: ExecutionDomain + 'static, A: Allocator + 'static> SubmitBatch<D, A> {
             signal_semaphores: Vec<vk::SemaphoreSubmitInfo>,
         }
 
+        // Different domains can resolve to the same queue, so submits are grouped by their queue instead of their domain.
+        let queues = self
+            .submits
+            .iter()
+            .map(|submit| (submit.queue_fn)(&self.exec))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Add the semaphore of the swapchain image as a wait semaphore for the first submit on the queue of the frame commands.
+        if let Some((semaphore, frame)) = self.frame_wait.take() {
+            let first = queues
+                .iter()
+                .position(|queue| *queue == queues[frame])
+                .unwrap_or(frame);
+            let stage = if first == frame {
+                PipelineStage::COLOR_ATTACHMENT_OUTPUT
+            } else {
+                PipelineStage::TOP_OF_PIPE
+            };
+            self.submits[first].wait_semaphores.push(semaphore);
+            self.submits[first].wait_stages.push(stage);
+        }
+
+        // Group consecutive submits to the same queue together. Groups must be submitted in order, so semaphores are always
+        // signaled before they are waited on.
+        let mut groups: Vec<(usize, usize)> = Vec::new();
+        for index in 0..self.submits.len() {
+            match groups.last_mut() {
+                Some((start, end)) if queues[*start] == queues[index] => *end = index + 1,
+                _ => groups.push((index, index + 1)),
+            }
+        }
+
+        // Only the last group signals the returned fence. To release resources only once all queues are done, the last group
+        // also waits on the last submit to every other queue.
+        if let Some(((last_start, _), others)) = groups.split_last() {
+            for (group, (_, end)) in others.iter().enumerate() {
+                let queue = queues[end - 1];
+                let is_last_on_queue = others[group + 1..]
+                    .iter()
+                    .all(|(other, _)| queues[*other] != queue);
+                if is_last_on_queue && queue != queues[*last_start] {
+                    let semaphore = Arc::new(Semaphore::new(self.device.clone())?);
+                    self.submits[end - 1]
+                        .signal_semaphores
+                        .push(semaphore.clone());
+                    let last = &mut self.submits[*last_start];
+                    last.wait_semaphores.push(semaphore);
+                    last.wait_stages.push(PipelineStage::BOTTOM_OF_PIPE);
+                }
+            }
+        }
+
         let mut per_submit_info = Vec::new();
         for submit in &self.submits {
             let info = PerSubmit {
@@ -209,24 +313,21 @@ impl<D: ExecutionDomain + 'static, A: Allocator + 'static> SubmitBatch<D, A> {
                 cmd_buffer: vec![vk::CommandBufferSubmitInfo {
                     s_type: vk::StructureType::COMMAND_BUFFER_SUBMIT_INFO,
                     p_next: std::ptr::null(),
-                    command_buffer: unsafe { submit.cmd.handle() },
+                    command_buffer: submit.handle,
                     device_mask: 0,
                 }],
-                signal_semaphores: match &submit.signal_semaphore {
-                    None => {
-                        vec![]
-                    }
-                    Some(semaphore) => {
-                        vec![vk::SemaphoreSubmitInfo {
-                            s_type: vk::StructureType::SEMAPHORE_SUBMIT_INFO,
-                            p_next: std::ptr::null(),
-                            semaphore: unsafe { semaphore.handle() },
-                            value: 0,
-                            stage_mask: PipelineStage::BOTTOM_OF_PIPE,
-                            device_index: 0,
-                        }]
-                    }
-                },
+                signal_semaphores: submit
+                    .signal_semaphores
+                    .iter()
+                    .map(|semaphore| vk::SemaphoreSubmitInfo {
+                        s_type: vk::StructureType::SEMAPHORE_SUBMIT_INFO,
+                        p_next: std::ptr::null(),
+                        semaphore: unsafe { semaphore.handle() },
+                        value: 0,
+                        stage_mask: PipelineStage::BOTTOM_OF_PIPE,
+                        device_index: 0,
+                    })
+                    .collect(),
             };
             per_submit_info.push(info);
         }
@@ -245,15 +346,23 @@ impl<D: ExecutionDomain + 'static, A: Allocator + 'static> SubmitBatch<D, A> {
             })
             .collect::<Vec<_>>();
 
-        self.exec
-            .submit_batch::<D>(submits.as_slice(), &self.signal_fence)?;
+        for (group, (start, end)) in groups.iter().enumerate() {
+            let submit_fn = self.submits[*start].submit_fn;
+            let fence = (group == groups.len() - 1).then_some(&*self.signal_fence);
+            submit_fn(&self.exec, &submits[*start..*end], fence)?;
+        }
+        if groups.is_empty() {
+            self.exec.submit_batch::<D>(&[], Some(&self.signal_fence))?;
+        }
+
         self.signal_fence.replace(move |fence| {
             fence.with_cleanup(move || {
                 // Take ownership of every resource inside the submit batch, to delete it afterwards
                 let _pool = self.local_pool;
                 for mut submit in self.submits {
-                    unsafe {
-                        submit.cmd.delete(self.exec.clone()).unwrap();
+                    // SAFETY: The last submit waits on all other queues, so no command buffer in the batch is still executing.
+                    if let Err(err) = unsafe { submit.cmd.delete(self.exec.clone()) } {
+                        error!("Failed to free command buffer of submit batch: {err}");
                     }
                 }
             })